@@ -117,7 +117,7 @@ fn main() {
     let result = sys.solve(&g2);
 
     match result {
-        SolveResult::Ok { dof } => {
+        SolveResult::Ok { dof, .. } => {
             println!("solved okay");
             if let (
                 Point::OnWorkplane {
@@ -38,7 +38,7 @@ fn main() {
     sys.clear_dragged();
 
     match result {
-        SolveResult::Ok { dof } => {
+        SolveResult::Ok { dof, .. } => {
             if let Point::In3d {
                 coords: [x1, y1, z1],
                 ..
@@ -0,0 +1,512 @@
+/*!
+Automatic dimensioning, to fully constrain a group at its current geometry.
+
+[`System::auto_constrain`] goes through a list of candidate constraints, in order of
+preference, and keeps each one that removes degrees of freedom without duplicating
+or conflicting with the constraints already in the group. Every dimension takes its
+value from the current geometry, so the entities only move when a line that was
+close to an axis is snapped onto it. The group is solved after snapping, so that
+the dimensions are measured on the snapped geometry.
+
+Candidates are tried in this order:
+
+1. [`Horizontal`] and [`Vertical`] for lines that are already close to an axis of
+   their workplane, if enabled by the [`Policy`].
+2. [`Diameter`] for every arc and circle.
+3. [`PtPtDistance`] for the length of every line.
+4. [`Angle`] between lines that share an endpoint.
+5. [`PtPtDistance`] from every point to the points outside the group, and then to
+   the other points within the group.
+6. [`WhereDragged`] for any point that can still move, if enabled by the [`Policy`].
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::{
+        Slvs_hEntity, SLVS_E_ARC_OF_CIRCLE, SLVS_E_CIRCLE, SLVS_E_LINE_SEGMENT, SLVS_E_POINT_IN_2D,
+        SLVS_E_POINT_IN_3D, SLVS_FREE_IN_3D,
+    },
+    constraint::{
+        Angle, AsConstraintData, AsConstraintHandle, Diameter, Horizontal, PtPtDistance, Vertical,
+        WhereDragged,
+    },
+    element::AsHandle,
+    entity::{ArcOfCircle, Circle, EntityHandle, Workplane},
+    group::Group,
+    linalg,
+    system::{SolveResult, SOLVE_TOLERANCE},
+    System,
+};
+
+/// Options for [`System::auto_constrain`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Policy {
+    /// Lines within this angle of the horizontal or vertical axis of their workplane,
+    /// in degrees, are made [`Horizontal`] or [`Vertical`]. If `None`, these
+    /// constraints are not added.
+    pub axis_tolerance: Option<f64>,
+    /// Whether to lock any points that can still move after every dimension has been
+    /// tried, using [`WhereDragged`].
+    pub lock_remaining: bool,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            axis_tolerance: Some(1.0),
+            lock_remaining: true,
+        }
+    }
+}
+
+// The rank of the constraint Jacobian as constraints are added.
+struct Progress {
+    rank: usize,
+    rows: usize,
+    cols: usize,
+    added: Vec<Box<dyn AsConstraintHandle>>,
+}
+
+impl System {
+    /// Add constraints to a group until it has no degrees of freedom left, keeping the
+    /// entities where they currently are.
+    ///
+    /// The group should be solved first, so that the existing constraints are
+    /// satisfied. A constraint is only added if it is independent of all the others,
+    /// so the group is never made inconsistent. Degrees of freedom that no candidate
+    /// can remove, such as the rotation of a normal in 3d, are left in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to be constrained.
+    /// * `policy` - Which kinds of constraints may be added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     auto_constrain::Policy,
+    ///     entity::{LineSegment, Normal, Point, Workplane},
+    ///     system::SolveResult,
+    ///     utils::make_quaternion,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g1 = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g1, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g1,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g1, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// let g2 = sys.add_group();
+    /// let start = sys
+    ///     .sketch(Point::new_on_workplane(g2, workplane, [1.0, 1.0]))
+    ///     .expect("point created");
+    /// let end = sys
+    ///     .sketch(Point::new_on_workplane(g2, workplane, [5.0, 1.0]))
+    ///     .expect("point created");
+    /// sys.sketch(LineSegment::new(g2, start, end))
+    ///     .expect("line created");
+    ///
+    /// let added = sys
+    ///     .auto_constrain(&g2, &Policy::default())
+    ///     .expect("constraints added");
+    /// assert!(!added.is_empty());
+    ///
+    /// if let SolveResult::Ok { dof, .. } = sys.solve(&g2) {
+    ///     assert_eq!(dof, 0);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the equations of an existing constraint cannot be evaluated,
+    /// or if the group cannot be solved once lines are snapped to an axis.
+    pub fn auto_constrain(
+        &mut self,
+        group: &Group,
+        policy: &Policy,
+    ) -> Result<Vec<Box<dyn AsConstraintHandle>>, &'static str> {
+        let (rank, rows, cols) = self.jacobian_rank(group)?;
+        let mut progress = Progress {
+            rank,
+            rows,
+            cols,
+            added: Vec::new(),
+        };
+
+        let entities: Vec<(Slvs_hEntity, i32)> = self
+            .entities
+            .list
+            .iter()
+            .filter(|slvs_entity| slvs_entity.group == group.handle())
+            .map(|slvs_entity| (slvs_entity.h, slvs_entity.type_))
+            .collect();
+        let lines: Vec<Slvs_hEntity> = entities
+            .iter()
+            .filter(|&&(_, type_)| SLVS_E_LINE_SEGMENT == type_ as _)
+            .map(|&(h, _)| h)
+            .collect();
+        let points: Vec<Slvs_hEntity> = entities
+            .iter()
+            .filter(|&&(_, type_)| {
+                SLVS_E_POINT_IN_2D == type_ as _ || SLVS_E_POINT_IN_3D == type_ as _
+            })
+            .map(|&(h, _)| h)
+            .collect();
+
+        if let Some(axis_tolerance) = policy.axis_tolerance {
+            let unsnapped = progress.added.len();
+            for &line in &lines {
+                let Some(workplane) = self.line_workplane(line)? else {
+                    continue;
+                };
+                let slvs_line = self.slvs_entity(line)?;
+                let start = self.point_coords_on(slvs_line.point[0], workplane.handle())?;
+                let end = self.point_coords_on(slvs_line.point[1], workplane.handle())?;
+                let angle = (end[1] - start[1])
+                    .atan2(end[0] - start[0])
+                    .to_degrees()
+                    .rem_euclid(180.0);
+
+                if angle.min(180.0 - angle) <= axis_tolerance {
+                    let data = Horizontal::from_line(*group, workplane, EntityHandle::new(line));
+                    self.try_constraint(group, data, &mut progress)?;
+                } else if (angle - 90.0).abs() <= axis_tolerance {
+                    let data = Vertical::from_line(*group, workplane, EntityHandle::new(line));
+                    self.try_constraint(group, data, &mut progress)?;
+                }
+            }
+
+            // Move the snapped lines onto their axes before anything is measured.
+            if progress.added.len() > unsnapped {
+                if let SolveResult::Fail { .. } = self.solve(group) {
+                    return Err("Group could not be solved after snapping lines to an axis.");
+                }
+            }
+        }
+
+        for &(h, type_) in &entities {
+            if SLVS_E_ARC_OF_CIRCLE == type_ as _ {
+                let diameter = 2.0 * self.arc_radius(h)?;
+                let data = Diameter::<ArcOfCircle>::new(*group, EntityHandle::new(h), diameter);
+                self.try_constraint(group, data, &mut progress)?;
+            } else if SLVS_E_CIRCLE == type_ as _ {
+                let diameter = 2.0 * self.circle_radius(h)?;
+                let data = Diameter::<Circle>::new(*group, EntityHandle::new(h), diameter);
+                self.try_constraint(group, data, &mut progress)?;
+            }
+        }
+
+        for &line in &lines {
+            let workplane = self.line_workplane(line)?;
+            let [start, end] = [0, 1].map(|i| self.slvs_entity(line).map(|l| l.point[i]));
+            let (start, end) = (start?, end?);
+            self.try_distance(group, start, end, workplane, &mut progress)?;
+        }
+
+        for (i, &line_a) in lines.iter().enumerate() {
+            for &line_b in &lines[i + 1..] {
+                let points_a = &self.slvs_entity(line_a)?.point[..2];
+                let points_b = &self.slvs_entity(line_b)?.point[..2];
+                if !points_a.iter().any(|point| points_b.contains(point)) {
+                    continue;
+                }
+
+                let workplane = self.line_workplane(line_a)?;
+                let angle = self.line_angle(
+                    line_a,
+                    line_b,
+                    workplane.map_or(SLVS_FREE_IN_3D, |w| w.handle()),
+                )?;
+                // The angle constraint is degenerate for parallel lines.
+                if angle.min(180.0 - angle) <= 1.0 {
+                    continue;
+                }
+
+                let data = Angle::new(
+                    *group,
+                    EntityHandle::new(line_a),
+                    EntityHandle::new(line_b),
+                    angle,
+                    workplane,
+                    false,
+                );
+                self.try_constraint(group, data, &mut progress)?;
+            }
+        }
+
+        let fixed_points: Vec<Slvs_hEntity> = self
+            .entities
+            .list
+            .iter()
+            .filter(|slvs_entity| {
+                slvs_entity.group != group.handle()
+                    && (SLVS_E_POINT_IN_2D == slvs_entity.type_ as _
+                        || SLVS_E_POINT_IN_3D == slvs_entity.type_ as _)
+            })
+            .map(|slvs_entity| slvs_entity.h)
+            .collect();
+        for &point in &points {
+            let workplane = self.point_workplane(point)?;
+            for &other in fixed_points.iter().chain(&points) {
+                if other != point {
+                    self.try_distance(group, point, other, workplane, &mut progress)?;
+                }
+            }
+        }
+
+        if policy.lock_remaining {
+            for &point in &points {
+                let data = WhereDragged::new(
+                    *group,
+                    EntityHandle::new(point),
+                    self.point_workplane(point)?,
+                );
+                self.try_constraint(group, data, &mut progress)?;
+            }
+        }
+
+        Ok(progress.added)
+    }
+
+    // Add a constraint, and keep it only if all of its equations are independent of
+    // the equations already in the group.
+    fn try_constraint<C: AsConstraintData>(
+        &mut self,
+        group: &Group,
+        constraint_data: C,
+        progress: &mut Progress,
+    ) -> Result<(), &'static str> {
+        if progress.rank == progress.cols {
+            return Ok(());
+        }
+
+        let constraint_handle = self.constrain(constraint_data)?;
+        let (rank, rows, _) = self.jacobian_rank(group)?;
+
+        if rank > progress.rank && rank - progress.rank == rows - progress.rows {
+            progress.rank = rank;
+            progress.rows = rows;
+            progress.added.push(
+                self.boxed_constraint_handle(*self.slvs_constraint(constraint_handle.handle())?),
+            );
+        } else {
+            self.delete_constraint(constraint_handle)?;
+        }
+
+        Ok(())
+    }
+
+    fn try_distance(
+        &mut self,
+        group: &Group,
+        point_a: Slvs_hEntity,
+        point_b: Slvs_hEntity,
+        workplane: Option<EntityHandle<Workplane>>,
+        progress: &mut Progress,
+    ) -> Result<(), &'static str> {
+        let distance = self.pt_pt_distance(
+            point_a,
+            point_b,
+            workplane.map_or(SLVS_FREE_IN_3D, |workplane| workplane.handle()),
+        )?;
+        // The distance constraint is degenerate for coincident points.
+        if distance <= SOLVE_TOLERANCE {
+            return Ok(());
+        }
+
+        let data = PtPtDistance::new(
+            *group,
+            EntityHandle::new(point_a),
+            EntityHandle::new(point_b),
+            distance,
+            workplane,
+        );
+        self.try_constraint(group, data, progress)
+    }
+
+    // The rank and size of the Jacobian of the active constraints in the group.
+    fn jacobian_rank(&mut self, group: &Group) -> Result<(usize, usize, usize), &'static str> {
+        let constraints = self.active_constraints(group.handle());
+        let equations = self.group_equations(group.handle(), &constraints);
+        let jacobian = self.jacobian(group.handle(), &equations)?;
+
+        Ok((
+            linalg::rank(&jacobian.rows),
+            jacobian.rows.len(),
+            jacobian.params.len(),
+        ))
+    }
+
+    fn point_workplane(
+        &self,
+        point: Slvs_hEntity,
+    ) -> Result<Option<EntityHandle<Workplane>>, &'static str> {
+        let slvs_point = self.slvs_entity(point)?;
+
+        Ok(match slvs_point.type_ as _ {
+            SLVS_E_POINT_IN_2D => Some(EntityHandle::new(slvs_point.wrkpl)),
+            _ => None,
+        })
+    }
+
+    // The workplane of a line, if both of its points are on the same workplane.
+    fn line_workplane(
+        &self,
+        line: Slvs_hEntity,
+    ) -> Result<Option<EntityHandle<Workplane>>, &'static str> {
+        let slvs_line = self.slvs_entity(line)?;
+        let start = self.point_workplane(slvs_line.point[0])?;
+        let end = self.point_workplane(slvs_line.point[1])?;
+
+        Ok(if start == end { start } else { None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        auto_constrain::Policy,
+        constraint::AsConstraintHandle,
+        element::AsHandle,
+        entity::{ArcOfCircle, LineSegment, Normal, Point, Workplane},
+        status::Status,
+        system::SolveResult,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn fully_constrain_sketch() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        // An L shape, with an arc at the end.
+        let g = sys.add_group();
+        let corner = sys
+            .sketch(Point::new_on_workplane(g, workplane, [2.0, 1.0]))
+            .expect("point created");
+        let right = sys
+            .sketch(Point::new_on_workplane(g, workplane, [12.0, 1.0]))
+            .expect("point created");
+        let top = sys
+            .sketch(Point::new_on_workplane(g, workplane, [2.0, 6.0]))
+            .expect("point created");
+        sys.sketch(LineSegment::new(g, corner, right))
+            .expect("line created");
+        sys.sketch(LineSegment::new(g, corner, top))
+            .expect("line created");
+        let arc_end = sys
+            .sketch(Point::new_on_workplane(g, workplane, [14.0, 3.0]))
+            .expect("point created");
+        let center = sys
+            .sketch(Point::new_on_workplane(g, workplane, [12.0, 3.0]))
+            .expect("point created");
+        sys.sketch(ArcOfCircle::new(g, workplane, center, right, arc_end))
+            .expect("arc created");
+
+        let policy = Policy {
+            lock_remaining: false,
+            ..Policy::default()
+        };
+        let added = sys.auto_constrain(&g, &policy).expect("constraints added");
+        assert!(added
+            .iter()
+            .any(|constraint| constraint.type_name().ends_with("Horizontal")));
+        assert!(added
+            .iter()
+            .any(|constraint| constraint.type_name().ends_with("Vertical")));
+
+        let status = sys.constraint_status(&g).expect("status found");
+        assert_eq!(status.dof, 0);
+        assert!(status.over_constrained.is_empty());
+        assert!(status
+            .entities
+            .iter()
+            .all(|entity_status| entity_status.status == Status::FullyConstrained));
+
+        match sys.solve(&g) {
+            SolveResult::Ok { dof, .. } => assert_eq!(dof, 0),
+            SolveResult::Fail { .. } => panic!("solve failed"),
+        }
+    }
+
+    #[test]
+    fn snap_before_dimensioning() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        // Both lines are within the axis tolerance, but not on the axis.
+        let g = sys.add_group();
+        let polyline = sys
+            .sketch_polyline(&g, &workplane, &[[0.0, 0.0], [10.0, 0.14], [20.0, 0.0]])
+            .expect("polyline created");
+
+        let added = sys
+            .auto_constrain(&g, &Policy::default())
+            .expect("constraints added");
+        assert!(added
+            .iter()
+            .any(|constraint| constraint.type_name().ends_with("Horizontal")));
+
+        let coords = |sys: &System| -> Vec<[f64; 2]> {
+            polyline
+                .points
+                .iter()
+                .map(|point| {
+                    sys.point_coords_on(point.handle(), workplane.handle())
+                        .expect("point found")
+                })
+                .collect()
+        };
+        let snapped = coords(&sys);
+        assert!(snapped
+            .iter()
+            .all(|[_, y]| (y - snapped[0][1]).abs() < 1e-6));
+
+        match sys.solve(&g) {
+            SolveResult::Ok { dof, .. } => assert_eq!(dof, 0),
+            SolveResult::Fail { .. } => panic!("solve failed"),
+        }
+        for ([x, y], [solved_x, solved_y]) in snapped.into_iter().zip(coords(&sys)) {
+            assert!((solved_x - x).abs() < 1e-6 && (solved_y - y).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,393 @@
+/*!
+Explanations for a failed solve.
+
+When a solve fails, the solver's list of failed constraints is often much longer than
+the actual problem. [`System::explain_failure`] narrows it down to a minimal set of
+constraints that still fails on its own, so that removing any one of them resolves
+the conflict. Each constraint in the set is described along with the entities it
+references.
+
+The set is found with a deletion filter: each constraint is disabled in turn, and the
+group is solved again. If the group still fails without it, the constraint is not
+needed to explain the failure, and it stays disabled.
+*/
+
+use std::fmt::Display;
+
+use crate::{
+    bindings::{Slvs_Constraint, Slvs_hConstraint},
+    constraint::AsConstraintHandle,
+    element::AsHandle,
+    entity::AsEntityHandle,
+    group::Group,
+    system::SolveResult,
+    tolerance::is_dimensional,
+    System,
+};
+
+/// A constraint that takes part in a conflict, found by [`System::explain_failure`].
+#[derive(Debug)]
+pub struct ConflictingConstraint {
+    pub constraint: Box<dyn AsConstraintHandle>,
+    /// The entities referenced by the constraint, not including its workplane.
+    pub entities: Vec<Box<dyn AsEntityHandle>>,
+    /// The value of the constraint, if it is dimensional.
+    pub value: Option<f64>,
+}
+
+impl Display for ConflictingConstraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            short_type_name(self.constraint.type_name()),
+            self.constraint.handle()
+        )?;
+        if let Some(value) = self.value {
+            write!(f, " of {value}")?;
+        }
+
+        let entities: Vec<String> = self
+            .entities
+            .iter()
+            .map(|entity| {
+                format!(
+                    "{} {}",
+                    short_type_name(entity.type_name()),
+                    entity.handle()
+                )
+            })
+            .collect();
+        match entities.split_last() {
+            Some((last, [])) => write!(f, " on {last}"),
+            Some((last, rest)) => write!(f, " between {} and {last}", rest.join(", ")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl System {
+    /// Find a minimal set of constraints in a group that cannot be solved together.
+    ///
+    /// Every constraint in the returned set is needed for the failure: the group solves
+    /// if any one of them is removed, along with the constraints that are not in the
+    /// set. The parameters are left as they were before this method was called.
+    ///
+    /// Each constraint in the set implements [`Display`], giving a short description
+    /// such as `PtPtDistance 3 of 20 between Point 1 and Point 2`. A bounded constraint
+    /// is included at the limit that it is clamped to or violates, which is also the
+    /// value that is described.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - A group that fails to solve.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{constraint::PtPtDistance, entity::Point, System};
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    ///
+    /// let p1 = sys
+    ///     .sketch(Point::new_in_3d(g, [10.0, 10.0, 10.0]))
+    ///     .expect("p1 created");
+    /// let p2 = sys
+    ///     .sketch(Point::new_in_3d(g, [20.0, 20.0, 20.0]))
+    ///     .expect("p2 created");
+    /// let p3 = sys
+    ///     .sketch(Point::new_in_3d(g, [30.0, 30.0, 30.0]))
+    ///     .expect("p3 created");
+    ///
+    /// sys.constrain(PtPtDistance::new(g, p1, p2, 10.0, None))
+    ///     .expect("distance constraint added");
+    /// sys.constrain(PtPtDistance::new(g, p2, p3, 10.0, None))
+    ///     .expect("distance constraint added");
+    /// sys.constrain(PtPtDistance::new(g, p1, p2, 20.0, None))
+    ///     .expect("distance constraint added");
+    ///
+    /// let conflict = sys.explain_failure(&g).expect("conflict found");
+    /// assert_eq!(conflict.len(), 2);
+    /// for constraint in conflict {
+    ///     println!("{constraint}");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the group solves successfully.
+    pub fn explain_failure(
+        &mut self,
+        group: &Group,
+    ) -> Result<Vec<ConflictingConstraint>, &'static str> {
+        let params = self.params.list.clone();
+        let all: Vec<Slvs_hConstraint> = self
+            .constraints
+            .list
+            .iter()
+            .filter(|slvs_constraint| slvs_constraint.group == group.handle())
+            .map(|slvs_constraint| slvs_constraint.h)
+            .collect();
+        let mut conflict = self.active_constraints(group.handle());
+
+        // A bounded constraint takes part at the limit it is held at, or at the limit it
+        // violates, rather than at the value it was added with.
+        let mut limits: Vec<(Slvs_hConstraint, f64)> = Vec::new();
+        for (&h, bound) in &self.bounds {
+            let slvs_constraint = self.slvs_constraint(h)?;
+            if slvs_constraint.group != group.handle() {
+                continue;
+            }
+            let Ok(value) = self.constraint_value(slvs_constraint) else {
+                continue;
+            };
+            if bound.is_at_limit(value) || bound.is_violated(value) {
+                limits.push((h, bound.nearest_limit(value)));
+                if !conflict.contains(&h) {
+                    conflict.push(h);
+                }
+            }
+        }
+
+        let fails = |sys: &mut System, candidates: &[Slvs_hConstraint]| {
+            let excluded: Vec<Slvs_hConstraint> = all
+                .iter()
+                .copied()
+                .filter(|h| !candidates.contains(h))
+                .collect();
+            let values: Vec<f64> = limits
+                .iter()
+                .map(|&(h, limit)| {
+                    let slvs_constraint = sys.mut_slvs_constraint(h).unwrap();
+                    std::mem::replace(&mut slvs_constraint.valA, limit)
+                })
+                .collect();
+
+            let result = sys.solve_excluding(group, &excluded);
+
+            sys.params.list.clone_from(&params);
+            for (&(h, _), value) in limits.iter().zip(values) {
+                sys.mut_slvs_constraint(h).unwrap().valA = value;
+            }
+
+            matches!(result, SolveResult::Fail { .. })
+        };
+
+        if !fails(self, &conflict) {
+            return Err("Group solves without any conflicting constraints.");
+        }
+
+        let mut ix = 0;
+        while ix < conflict.len() {
+            let mut candidates = conflict.clone();
+            candidates.remove(ix);
+
+            if fails(self, &candidates) {
+                conflict = candidates;
+            } else {
+                ix += 1;
+            }
+        }
+
+        conflict
+            .into_iter()
+            .map(|h| {
+                let mut slvs_constraint = *self.slvs_constraint(h)?;
+                if let Some(&(_, limit)) = limits.iter().find(|&&(bounded_h, _)| bounded_h == h) {
+                    slvs_constraint.valA = limit;
+                }
+                self.conflicting_constraint(&slvs_constraint)
+            })
+            .collect()
+    }
+
+    fn conflicting_constraint(
+        &self,
+        slvs_constraint: &Slvs_Constraint,
+    ) -> Result<ConflictingConstraint, &'static str> {
+        let entities = [
+            slvs_constraint.ptA,
+            slvs_constraint.ptB,
+            slvs_constraint.entityA,
+            slvs_constraint.entityB,
+            slvs_constraint.entityC,
+            slvs_constraint.entityD,
+        ]
+        .into_iter()
+        .filter(|&h| h != 0)
+        .map(|h| Ok((*self.slvs_entity(h)?).into()))
+        .collect::<Result<_, &'static str>>()?;
+
+        Ok(ConflictingConstraint {
+            constraint: self.boxed_constraint_handle(*slvs_constraint),
+            entities,
+            value: is_dimensional(slvs_constraint.type_).then_some(slvs_constraint.valA),
+        })
+    }
+}
+
+// The name of a type, without its path or generic parameters.
+fn short_type_name(type_name: &str) -> &str {
+    let name = type_name.split('<').next().unwrap_or(type_name);
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constraint::{Diameter, EqualRadius, Horizontal, PtPtDistance},
+        element::AsHandle,
+        entity::{Circle, Distance, LineSegment, Normal, Point, Workplane},
+        inequality::Bound,
+        system::SolveResult,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn circles_with_conflicting_diameters() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let g = sys.add_group();
+        let center_a = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 0.0]))
+            .expect("point created");
+        let radius_a = sys.sketch(Distance::new(g, 5.0)).expect("distance created");
+        let circle_a = sys
+            .sketch(Circle::new(g, normal, center_a, radius_a))
+            .expect("circle created");
+        let center_b = sys
+            .sketch(Point::new_on_workplane(g, workplane, [20.0, 0.0]))
+            .expect("point created");
+        let radius_b = sys.sketch(Distance::new(g, 5.0)).expect("distance created");
+        let circle_b = sys
+            .sketch(Circle::new(g, normal, center_b, radius_b))
+            .expect("circle created");
+
+        sys.constrain(PtPtDistance::new(
+            g,
+            center_a,
+            center_b,
+            20.0,
+            Some(workplane),
+        ))
+        .expect("constraint added");
+        let diameter_a = sys
+            .constrain(Diameter::new(g, circle_a, 10.0))
+            .expect("constraint added");
+        let equal = sys
+            .constrain(EqualRadius::new(g, circle_a, circle_b))
+            .expect("constraint added");
+        let diameter_b = sys
+            .constrain(Diameter::new(g, circle_b, 12.0))
+            .expect("constraint added");
+
+        let conflict = sys.explain_failure(&g).expect("conflict found");
+        let handles: Vec<u32> = conflict
+            .iter()
+            .map(|constraint| constraint.constraint.handle())
+            .collect();
+        assert_eq!(
+            handles,
+            vec![diameter_a.handle(), equal.handle(), diameter_b.handle()]
+        );
+        assert_eq!(
+            conflict[1].to_string(),
+            format!(
+                "EqualRadius {} between Circle {} and Circle {}",
+                equal.handle(),
+                circle_a.handle(),
+                circle_b.handle()
+            )
+        );
+        assert_eq!(
+            conflict[2].to_string(),
+            format!(
+                "Diameter {} of 12 on Circle {}",
+                diameter_b.handle(),
+                circle_b.handle()
+            )
+        );
+    }
+
+    #[test]
+    fn bound_in_conflict() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+        let [start, stop] = [[0.0, 0.0], [7.0, 0.0]].map(|coords| {
+            sys.sketch(Point::new_on_workplane(workplane_g, workplane, coords))
+                .expect("point created")
+        });
+
+        let g = sys.add_group();
+        let slider = sys
+            .sketch(Point::new_on_workplane(g, workplane, [10.0, 0.0]))
+            .expect("point created");
+        let line = sys
+            .sketch(LineSegment::new(g, start, slider))
+            .expect("line created");
+        sys.constrain(Horizontal::from_line(g, workplane, line))
+            .expect("constraint added");
+        let reach = sys
+            .constrain(PtPtDistance::new(g, start, slider, 10.0, Some(workplane)))
+            .expect("constraint added");
+
+        // The slider cannot be within 2 of the stop. The placeholder value of 3 could be
+        // met, so the conflict is only found at the limit.
+        let near = sys
+            .constrain_bounded(
+                PtPtDistance::new(g, stop, slider, 3.0, Some(workplane)),
+                Bound::AtMost(2.0),
+            )
+            .expect("constraint added");
+        assert!(matches!(sys.solve(&g), SolveResult::Fail { .. }));
+
+        let conflict = sys.explain_failure(&g).expect("conflict found");
+        let mut handles: Vec<u32> = conflict
+            .iter()
+            .map(|constraint| constraint.constraint.handle())
+            .collect();
+        handles.sort_unstable();
+        assert_eq!(handles, vec![reach.handle(), near.handle()]);
+
+        let near_conflict = conflict
+            .iter()
+            .find(|constraint| constraint.constraint.handle() == near.handle())
+            .expect("bound in conflict");
+        assert_eq!(near_conflict.value, Some(2.0));
+        assert_eq!(
+            sys.constraint_data(&near)
+                .expect("constraint found")
+                .distance,
+            3.0
+        );
+    }
+}
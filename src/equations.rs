@@ -0,0 +1,1026 @@
+/*!
+The equations that constraints and entities contribute to the solver.
+
+SolveSpace turns every constraint into one or more equations in the parameters, and
+solves them with Newton's method. This module mirrors that model, evaluating the
+residual of each equation at the current parameter values. The residuals are zero
+when a constraint is satisfied, and their derivatives form the constraint Jacobian
+used for analysis.
+*/
+
+use euclid::default::{Vector2D, Vector3D};
+
+use crate::{
+    bindings::{
+        Slvs_Constraint, Slvs_Entity, Slvs_hConstraint, Slvs_hEntity, Slvs_hGroup, Slvs_hParam,
+        SLVS_C_ANGLE, SLVS_C_ARC_ARC_DIFFERENCE, SLVS_C_ARC_ARC_LEN_RATIO,
+        SLVS_C_ARC_LINE_DIFFERENCE, SLVS_C_ARC_LINE_LEN_RATIO, SLVS_C_ARC_LINE_TANGENT,
+        SLVS_C_AT_MIDPOINT, SLVS_C_CUBIC_LINE_TANGENT, SLVS_C_CURVE_CURVE_TANGENT, SLVS_C_DIAMETER,
+        SLVS_C_EQUAL_ANGLE, SLVS_C_EQUAL_LENGTH_LINES, SLVS_C_EQUAL_LINE_ARC_LEN,
+        SLVS_C_EQUAL_RADIUS, SLVS_C_EQ_LEN_PT_LINE_D, SLVS_C_EQ_PT_LN_DISTANCES, SLVS_C_HORIZONTAL,
+        SLVS_C_LENGTH_DIFFERENCE, SLVS_C_LENGTH_RATIO, SLVS_C_PARALLEL, SLVS_C_PERPENDICULAR,
+        SLVS_C_POINTS_COINCIDENT, SLVS_C_PROJ_PT_DISTANCE, SLVS_C_PT_IN_PLANE,
+        SLVS_C_PT_LINE_DISTANCE, SLVS_C_PT_ON_CIRCLE, SLVS_C_PT_ON_LINE, SLVS_C_PT_PLANE_DISTANCE,
+        SLVS_C_PT_PT_DISTANCE, SLVS_C_SAME_ORIENTATION, SLVS_C_SYMMETRIC, SLVS_C_SYMMETRIC_HORIZ,
+        SLVS_C_SYMMETRIC_LINE, SLVS_C_SYMMETRIC_VERT, SLVS_C_VERTICAL, SLVS_C_WHERE_DRAGGED,
+        SLVS_E_ARC_OF_CIRCLE, SLVS_E_NORMAL_IN_3D, SLVS_FREE_IN_3D,
+    },
+    linalg,
+    utils::{quaternion_n, quaternion_u, quaternion_v},
+    System,
+};
+
+// Step used to compute the derivatives of the residuals by central differences.
+const DIFF_STEP: f64 = 1e-6;
+
+/// A source of equations for the solver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum EquationSource {
+    Constraint(Slvs_hConstraint),
+    Entity(Slvs_hEntity),
+}
+
+/// The derivatives of a set of residuals with respect to the parameters of a group.
+#[derive(Debug)]
+pub(crate) struct Jacobian {
+    /// The parameters, one for each column.
+    pub(crate) params: Vec<Slvs_hParam>,
+    pub(crate) rows: Vec<Vec<f64>>,
+}
+
+impl System {
+    // The equations from `constraints`, plus the implicit equations from the entities
+    // in `group`.
+    pub(crate) fn group_equations(
+        &self,
+        group: Slvs_hGroup,
+        constraints: &[Slvs_hConstraint],
+    ) -> Vec<EquationSource> {
+        constraints
+            .iter()
+            .map(|&constraint_h| EquationSource::Constraint(constraint_h))
+            .chain(
+                self.entities
+                    .list
+                    .iter()
+                    .filter(|entity| entity.group == group)
+                    .map(|entity| EquationSource::Entity(entity.h)),
+            )
+            .collect()
+    }
+
+    // The constraints in `group` that take part in the solution around the current
+    // parameter values. Soft constraints are left out, as are bounded constraints that
+    // are not at one of their limits.
+    pub(crate) fn active_constraints(&self, group: Slvs_hGroup) -> Vec<Slvs_hConstraint> {
+        self.constraints
+            .list
+            .iter()
+            .filter(|slvs_constraint| {
+                slvs_constraint.group == group && !self.soft.contains_key(&slvs_constraint.h)
+            })
+            .filter(
+                |slvs_constraint| match self.bounds.get(&slvs_constraint.h) {
+                    Some(bound) => self
+                        .constraint_value(slvs_constraint)
+                        .is_ok_and(|value| bound.is_at_limit(value)),
+                    None => true,
+                },
+            )
+            .map(|slvs_constraint| slvs_constraint.h)
+            .collect()
+    }
+
+    pub(crate) fn equation_residuals(
+        &self,
+        source: EquationSource,
+    ) -> Result<Vec<f64>, &'static str> {
+        match source {
+            EquationSource::Constraint(h) => self.constraint_residuals(self.slvs_constraint(h)?),
+            EquationSource::Entity(h) => self.entity_residuals(self.slvs_entity(h)?),
+        }
+    }
+
+    /// Differentiate the residuals of `equations` with respect to the parameters in
+    /// `group`, using central differences.
+    pub(crate) fn jacobian(
+        &mut self,
+        group: Slvs_hGroup,
+        equations: &[EquationSource],
+    ) -> Result<Jacobian, &'static str> {
+        self.jacobian_of(group, |sys| sys.residuals(equations))
+    }
+
+    /// Differentiate any residuals computed by `residuals` with respect to the
+    /// parameters in `group`, using central differences.
+    pub(crate) fn jacobian_of(
+        &mut self,
+        group: Slvs_hGroup,
+        residuals: impl Fn(&System) -> Result<Vec<f64>, &'static str>,
+    ) -> Result<Jacobian, &'static str> {
+        let params: Vec<Slvs_hParam> = self
+            .params
+            .list
+            .iter()
+            .filter(|param| param.group == group)
+            .map(|param| param.h)
+            .collect();
+        let mut rows = vec![vec![0.0; params.len()]; residuals(self)?.len()];
+
+        for (col, &param_h) in params.iter().enumerate() {
+            let val = self.slvs_param(param_h)?.val;
+            let step = DIFF_STEP * val.abs().max(1.0);
+
+            self.mut_slvs_param(param_h)?.val = val + step;
+            let plus = residuals(self);
+            self.mut_slvs_param(param_h)?.val = val - step;
+            let minus = residuals(self);
+            self.mut_slvs_param(param_h)?.val = val;
+
+            for (row, (plus, minus)) in rows.iter_mut().zip(plus?.into_iter().zip(minus?)) {
+                let derivative = (plus - minus) / (2.0 * step);
+                // Drop the noise left over when a residual does not depend on a parameter.
+                row[col] = if derivative.abs() > 1e-9 {
+                    derivative
+                } else {
+                    0.0
+                };
+            }
+        }
+
+        Ok(Jacobian { params, rows })
+    }
+
+    pub(crate) fn residuals(&self, equations: &[EquationSource]) -> Result<Vec<f64>, &'static str> {
+        let mut residuals = Vec::new();
+        for &equation in equations {
+            residuals.extend(self.equation_residuals(equation)?);
+        }
+        Ok(residuals)
+    }
+
+    /// Residuals of the equations for a constraint, which are zero when the constraint
+    /// is satisfied.
+    ///
+    /// The residuals for [`WhereDragged`][crate::constraint::WhereDragged] are the
+    /// parameters of the dragged point, as its target is its position before solving.
+    /// Bounded constraints are measured against the limit of their bound nearest to
+    /// their value, as they only take part in the solution when clamped to a limit.
+    pub(crate) fn constraint_residuals(
+        &self,
+        c: &Slvs_Constraint,
+    ) -> Result<Vec<f64>, &'static str> {
+        let mut c = *c;
+        if let Some(bound) = self.bounds.get(&c.h) {
+            c.valA = bound.nearest_limit(self.constraint_value(&c)?);
+        }
+        let wrkpl = c.wrkpl;
+        let in_3d = wrkpl == SLVS_FREE_IN_3D;
+
+        Ok(match c.type_ as _ {
+            SLVS_C_POINTS_COINCIDENT => {
+                if in_3d {
+                    (self.xyz(c.ptA)? - self.xyz(c.ptB)?).to_array().to_vec()
+                } else {
+                    (self.uv(c.ptA, wrkpl)? - self.uv(c.ptB, wrkpl)?)
+                        .to_array()
+                        .to_vec()
+                }
+            }
+            SLVS_C_PT_PT_DISTANCE => vec![self.pt_pt_distance(c.ptA, c.ptB, wrkpl)? - c.valA],
+            SLVS_C_PT_PLANE_DISTANCE => vec![self.pt_plane_distance(c.ptA, c.entityA)? - c.valA],
+            SLVS_C_PT_IN_PLANE => vec![self.pt_plane_distance(c.ptA, c.entityA)?],
+            SLVS_C_PT_LINE_DISTANCE => {
+                vec![self.pt_line_distance(c.ptA, c.entityA, wrkpl)? - c.valA]
+            }
+            SLVS_C_PT_ON_LINE => {
+                if in_3d {
+                    let line = self.slvs_entity(c.entityA)?;
+                    let offset = self.xyz(c.ptA)? - self.xyz(line.point[0])?;
+                    perpendicular_basis(self.line_vector(c.entityA, wrkpl)?)
+                        .map(|axis| offset.dot(axis))
+                        .to_vec()
+                } else {
+                    vec![self.pt_line_distance(c.ptA, c.entityA, wrkpl)?]
+                }
+            }
+            SLVS_C_EQUAL_LENGTH_LINES => {
+                vec![self.line_length(c.entityA, wrkpl)? - self.line_length(c.entityB, wrkpl)?]
+            }
+            SLVS_C_LENGTH_RATIO => vec![
+                self.line_length(c.entityA, wrkpl)? / self.line_length(c.entityB, wrkpl)? - c.valA,
+            ],
+            SLVS_C_LENGTH_DIFFERENCE => vec![
+                self.line_length(c.entityA, wrkpl)? - self.line_length(c.entityB, wrkpl)? - c.valA,
+            ],
+            SLVS_C_EQ_LEN_PT_LINE_D => vec![
+                self.line_length(c.entityA, wrkpl)?
+                    - self.pt_line_distance(c.ptA, c.entityB, wrkpl)?,
+            ],
+            SLVS_C_EQ_PT_LN_DISTANCES => vec![
+                self.pt_line_distance(c.ptA, c.entityA, wrkpl)?
+                    - self.pt_line_distance(c.ptB, c.entityB, wrkpl)?,
+            ],
+            SLVS_C_EQUAL_ANGLE => vec![
+                self.line_cos(c.entityA, c.entityB, wrkpl, c.other != 0)?
+                    - self.line_cos(c.entityC, c.entityD, wrkpl, false)?,
+            ],
+            SLVS_C_ANGLE => vec![
+                self.line_cos(c.entityA, c.entityB, wrkpl, c.other != 0)?
+                    - c.valA.to_radians().cos(),
+            ],
+            SLVS_C_EQUAL_LINE_ARC_LEN => {
+                vec![self.line_length(c.entityA, wrkpl)? - self.arc_length(c.entityB)?]
+            }
+            SLVS_C_SYMMETRIC => {
+                let (origin, quaternion) = self.workplane_frame(c.entityA)?;
+                let normal = Vector3D::from(quaternion_n(quaternion));
+                let (a, b) = (self.xyz(c.ptA)?, self.xyz(c.ptB)?);
+                let on_plane = ((a + b) / 2.0 - Vector3D::from(origin)).dot(normal);
+
+                if in_3d {
+                    vec![
+                        on_plane,
+                        (b - a).dot(Vector3D::from(quaternion_u(quaternion))),
+                        (b - a).dot(Vector3D::from(quaternion_v(quaternion))),
+                    ]
+                } else {
+                    let (_, wrkpl_quaternion) = self.workplane_frame(wrkpl)?;
+                    let normal_uv = Vector2D::new(
+                        normal.dot(Vector3D::from(quaternion_u(wrkpl_quaternion))),
+                        normal.dot(Vector3D::from(quaternion_v(wrkpl_quaternion))),
+                    );
+                    let (a, b) = (self.uv(c.ptA, wrkpl)?, self.uv(c.ptB, wrkpl)?);
+                    vec![on_plane, (b - a).cross(normal_uv)]
+                }
+            }
+            SLVS_C_SYMMETRIC_HORIZ => {
+                let (a, b) = (self.uv(c.ptA, wrkpl)?, self.uv(c.ptB, wrkpl)?);
+                vec![a.x + b.x, a.y - b.y]
+            }
+            SLVS_C_SYMMETRIC_VERT => {
+                let (a, b) = (self.uv(c.ptA, wrkpl)?, self.uv(c.ptB, wrkpl)?);
+                vec![a.x - b.x, a.y + b.y]
+            }
+            SLVS_C_SYMMETRIC_LINE => {
+                let (a, b) = (self.uv(c.ptA, wrkpl)?, self.uv(c.ptB, wrkpl)?);
+                let (start, end) = self.line_uv(c.entityA, wrkpl)?;
+                vec![
+                    signed_distance((a + b) / 2.0, start, end),
+                    (b - a).dot(end - start),
+                ]
+            }
+            SLVS_C_AT_MIDPOINT => {
+                let line = self.slvs_entity(c.entityA)?;
+                if in_3d {
+                    let midpoint = (self.xyz(line.point[0])? + self.xyz(line.point[1])?) / 2.0;
+                    (self.xyz(c.ptA)? - midpoint).to_array().to_vec()
+                } else {
+                    let (start, end) = self.line_uv(c.entityA, wrkpl)?;
+                    (self.uv(c.ptA, wrkpl)? - (start + end) / 2.0)
+                        .to_array()
+                        .to_vec()
+                }
+            }
+            SLVS_C_HORIZONTAL | SLVS_C_VERTICAL => {
+                let (a, b) = if c.entityA != 0 {
+                    self.line_uv(c.entityA, wrkpl)?
+                } else {
+                    (self.uv(c.ptA, wrkpl)?, self.uv(c.ptB, wrkpl)?)
+                };
+                match c.type_ as _ {
+                    SLVS_C_HORIZONTAL => vec![a.y - b.y],
+                    _ => vec![a.x - b.x],
+                }
+            }
+            SLVS_C_DIAMETER => vec![2.0 * self.curve_radius(c.entityA)? - c.valA],
+            SLVS_C_EQUAL_RADIUS => {
+                vec![self.curve_radius(c.entityA)? - self.curve_radius(c.entityB)?]
+            }
+            SLVS_C_PT_ON_CIRCLE => {
+                let curve = self.slvs_entity(c.entityA)?;
+                let offset = self.xyz(c.ptA)? - self.xyz(curve.point[0])?;
+                let quaternion = self.normal_quaternion(curve.normal)?;
+                let in_plane = Vector2D::new(
+                    offset.dot(Vector3D::from(quaternion_u(quaternion))),
+                    offset.dot(Vector3D::from(quaternion_v(quaternion))),
+                );
+                vec![in_plane.length() - self.curve_radius(c.entityA)?]
+            }
+            SLVS_C_SAME_ORIENTATION => {
+                let quaternion_a = self.normal_quaternion(c.entityA)?;
+                let quaternion_b = self.normal_quaternion(c.entityB)?;
+                let normal_a = Vector3D::from(quaternion_n(quaternion_a));
+                let normal_b = Vector3D::from(quaternion_n(quaternion_b));
+                let mut residuals = perpendicular_basis(normal_b)
+                    .map(|axis| normal_a.cross(normal_b).dot(axis))
+                    .to_vec();
+                residuals.push(
+                    Vector3D::from(quaternion_u(quaternion_a))
+                        .dot(Vector3D::from(quaternion_v(quaternion_b))),
+                );
+                residuals
+            }
+            SLVS_C_PARALLEL | SLVS_C_CUBIC_LINE_TANGENT => {
+                let (dir_a, dir_b) = if SLVS_C_PARALLEL == c.type_ as _ {
+                    (
+                        self.line_vector(c.entityA, wrkpl)?,
+                        self.line_vector(c.entityB, wrkpl)?,
+                    )
+                } else {
+                    (
+                        self.cubic_tangent(c.entityA, wrkpl, c.other != 0)?,
+                        self.line_vector(c.entityB, wrkpl)?,
+                    )
+                };
+                let cross = dir_a.normalize().cross(dir_b.normalize());
+
+                if in_3d {
+                    perpendicular_basis(dir_b)
+                        .map(|axis| cross.dot(axis))
+                        .to_vec()
+                } else {
+                    let (_, quaternion) = self.workplane_frame(wrkpl)?;
+                    vec![cross.dot(Vector3D::from(quaternion_n(quaternion)))]
+                }
+            }
+            SLVS_C_PERPENDICULAR => vec![self.line_cos(c.entityA, c.entityB, wrkpl, false)?],
+            SLVS_C_ARC_LINE_TANGENT => {
+                let arc = self.slvs_entity(c.entityA)?;
+                let radius = self.uv(arc.point[if c.other != 0 { 2 } else { 1 }], wrkpl)?
+                    - self.uv(arc.point[0], wrkpl)?;
+                let (start, end) = self.line_uv(c.entityB, wrkpl)?;
+                vec![radius.normalize().dot((end - start).normalize())]
+            }
+            SLVS_C_CURVE_CURVE_TANGENT => {
+                let tangent_a = self.curve_tangent(c.entityA, wrkpl, c.other != 0)?;
+                let tangent_b = self.curve_tangent(c.entityB, wrkpl, c.other2 != 0)?;
+                vec![tangent_a.normalize().cross(tangent_b.normalize())]
+            }
+            SLVS_C_PROJ_PT_DISTANCE => {
+                vec![self.proj_pt_distance(c.ptA, c.ptB, c.entityA)? - c.valA]
+            }
+            SLVS_C_WHERE_DRAGGED => self.param_vals(self.slvs_entity(c.ptA)?)?,
+            SLVS_C_ARC_ARC_LEN_RATIO => {
+                vec![self.arc_length(c.entityA)? / self.arc_length(c.entityB)? - c.valA]
+            }
+            SLVS_C_ARC_ARC_DIFFERENCE => {
+                vec![self.arc_length(c.entityA)? - self.arc_length(c.entityB)? - c.valA]
+            }
+            SLVS_C_ARC_LINE_LEN_RATIO => vec![
+                self.arc_length(c.entityB)? / self.line_length(c.entityA, SLVS_FREE_IN_3D)?
+                    - c.valA,
+            ],
+            SLVS_C_ARC_LINE_DIFFERENCE => vec![
+                self.arc_length(c.entityB)?
+                    - self.line_length(c.entityA, SLVS_FREE_IN_3D)?
+                    - c.valA,
+            ],
+            _ => return Err("Constraint type is not supported."),
+        })
+    }
+
+    /// Residuals of the equations that are implied by an entity: the start and end
+    /// of an arc are the same distance from its center, and the quaternion of a
+    /// normal in 3d has unit length.
+    pub(crate) fn entity_residuals(&self, e: &Slvs_Entity) -> Result<Vec<f64>, &'static str> {
+        Ok(match e.type_ as _ {
+            SLVS_E_ARC_OF_CIRCLE => {
+                let center = self.uv(e.point[0], e.wrkpl)?;
+                vec![
+                    (self.uv(e.point[1], e.wrkpl)? - center).length()
+                        - (self.uv(e.point[2], e.wrkpl)? - center).length(),
+                ]
+            }
+            SLVS_E_NORMAL_IN_3D => {
+                let quaternion = self.param_vals(e)?;
+                vec![linalg::dot(&quaternion, &quaternion) - 1.0]
+            }
+            _ => Vec::new(),
+        })
+    }
+
+    fn xyz(&self, point: Slvs_hEntity) -> Result<Vector3D<f64>, &'static str> {
+        Ok(self.point_coords(point)?.into())
+    }
+
+    fn uv(
+        &self,
+        point: Slvs_hEntity,
+        workplane: Slvs_hEntity,
+    ) -> Result<Vector2D<f64>, &'static str> {
+        Ok(self.point_coords_on(point, workplane)?.into())
+    }
+
+    fn line_uv(
+        &self,
+        line: Slvs_hEntity,
+        workplane: Slvs_hEntity,
+    ) -> Result<(Vector2D<f64>, Vector2D<f64>), &'static str> {
+        let slvs_line = self.slvs_entity(line)?;
+        Ok((
+            self.uv(slvs_line.point[0], workplane)?,
+            self.uv(slvs_line.point[1], workplane)?,
+        ))
+    }
+
+    fn line_cos(
+        &self,
+        line_a: Slvs_hEntity,
+        line_b: Slvs_hEntity,
+        workplane: Slvs_hEntity,
+        reversed: bool,
+    ) -> Result<f64, &'static str> {
+        let dir_a = self.line_vector(line_a, workplane)?.normalize();
+        let dir_b = self.line_vector(line_b, workplane)?.normalize();
+        let cos = dir_a.dot(dir_b);
+
+        Ok(if reversed { -cos } else { cos })
+    }
+
+    // Direction of a cubic at its start or end, pointing along the curve.
+    fn cubic_tangent(
+        &self,
+        cubic: Slvs_hEntity,
+        workplane: Slvs_hEntity,
+        at_end: bool,
+    ) -> Result<Vector3D<f64>, &'static str> {
+        let slvs_cubic = self.slvs_entity(cubic)?;
+        let [from, to] = if at_end { [2, 3] } else { [0, 1] };
+
+        Ok(
+            Vector3D::from(self.point_projected(slvs_cubic.point[to], workplane)?)
+                - Vector3D::from(self.point_projected(slvs_cubic.point[from], workplane)?),
+        )
+    }
+
+    // Tangent of an arc or cubic at its start or end, within a workplane.
+    fn curve_tangent(
+        &self,
+        curve: Slvs_hEntity,
+        workplane: Slvs_hEntity,
+        at_end: bool,
+    ) -> Result<Vector2D<f64>, &'static str> {
+        let slvs_curve = self.slvs_entity(curve)?;
+
+        match slvs_curve.type_ as _ {
+            SLVS_E_ARC_OF_CIRCLE => {
+                let radius = self.uv(slvs_curve.point[if at_end { 2 } else { 1 }], workplane)?
+                    - self.uv(slvs_curve.point[0], workplane)?;
+                Ok(Vector2D::new(-radius.y, radius.x))
+            }
+            _ => {
+                let [from, to] = if at_end { [2, 3] } else { [0, 1] };
+                Ok(self.uv(slvs_curve.point[to], workplane)?
+                    - self.uv(slvs_curve.point[from], workplane)?)
+            }
+        }
+    }
+}
+
+// Signed distance from `point` to the line through `start` and `end`, positive to
+// the right of the line.
+fn signed_distance(point: Vector2D<f64>, start: Vector2D<f64>, end: Vector2D<f64>) -> f64 {
+    (point - start).cross(end - start) / (end - start).length()
+}
+
+// Two unit vectors perpendicular to `vector` and to each other.
+fn perpendicular_basis(vector: Vector3D<f64>) -> [Vector3D<f64>; 2] {
+    let vector = vector.normalize();
+    let axis = if vector.x.abs() <= vector.y.abs() && vector.x.abs() <= vector.z.abs() {
+        Vector3D::new(1.0, 0.0, 0.0)
+    } else if vector.y.abs() <= vector.z.abs() {
+        Vector3D::new(0.0, 1.0, 0.0)
+    } else {
+        Vector3D::new(0.0, 0.0, 1.0)
+    };
+    let first = vector.cross(axis).normalize();
+
+    [first, vector.cross(first)]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::type_name;
+
+    use crate::{
+        constraint::{
+            Angle, ArcArcDifference, ArcArcLenRatio, ArcLineDifference, ArcLineLenRatio,
+            ArcLineTangent, AsConstraintData, AtMidpoint, CubicLineTangent, CurveCurveTangent,
+            Diameter, EqLenPtLineD, EqPtLnDistances, EqualAngle, EqualLengthLines, EqualLineArcLen,
+            EqualRadius, Horizontal, LengthDifference, LengthRatio, Parallel, Perpendicular,
+            PointsCoincident, ProjPtDistance, PtInPlane, PtLineDistance, PtOnCircle, PtOnLine,
+            PtPlaneDistance, PtPtDistance, SameOrientation, Symmetric, SymmetricHoriz,
+            SymmetricLine, SymmetricVert, Vertical,
+        },
+        element::AsHandle,
+        entity::{
+            ArcOfCircle, Circle, Cubic, Distance, EntityHandle, LineSegment, Normal, Point,
+            Workplane,
+        },
+        group::Group,
+        linalg,
+        system::SolveResult,
+        utils::make_quaternion,
+        System,
+    };
+
+    // A system with a tilted workplane away from the origin, and a group to sketch the
+    // entities of one constraint in.
+    struct Setup {
+        sys: System,
+        g: Group,
+        normal: EntityHandle<Normal>,
+        workplane: EntityHandle<Workplane>,
+    }
+
+    impl Setup {
+        fn new() -> Self {
+            let mut sys = System::new();
+            let workplane_g = sys.add_group();
+            let origin = sys
+                .sketch(Point::new_in_3d(workplane_g, [1.0, 2.0, 3.0]))
+                .expect("origin created");
+            let normal = sys
+                .sketch(Normal::new_in_3d(
+                    workplane_g,
+                    make_quaternion([0.6, 0.8, 0.0], [0.0, 0.0, 1.0]),
+                ))
+                .expect("normal created");
+            let workplane = sys
+                .sketch(Workplane::new(workplane_g, origin, normal))
+                .expect("workplane created");
+            let g = sys.add_group();
+
+            Self {
+                sys,
+                g,
+                normal,
+                workplane,
+            }
+        }
+
+        fn point(&mut self, coords: [f64; 2]) -> EntityHandle<Point> {
+            self.sys
+                .sketch(Point::new_on_workplane(self.g, self.workplane, coords))
+                .expect("point created")
+        }
+
+        fn point_3d(&mut self, coords: [f64; 3]) -> EntityHandle<Point> {
+            self.sys
+                .sketch(Point::new_in_3d(self.g, coords))
+                .expect("point created")
+        }
+
+        fn line(&mut self, a: [f64; 2], b: [f64; 2]) -> EntityHandle<LineSegment> {
+            let [a, b] = [a, b].map(|coords| self.point(coords));
+            self.sys
+                .sketch(LineSegment::new(self.g, a, b))
+                .expect("line created")
+        }
+
+        fn line_3d(&mut self, a: [f64; 3], b: [f64; 3]) -> EntityHandle<LineSegment> {
+            let [a, b] = [a, b].map(|coords| self.point_3d(coords));
+            self.sys
+                .sketch(LineSegment::new(self.g, a, b))
+                .expect("line created")
+        }
+
+        fn arc(
+            &mut self,
+            center: [f64; 2],
+            start: [f64; 2],
+            end: [f64; 2],
+        ) -> EntityHandle<ArcOfCircle> {
+            let [center, start, end] = [center, start, end].map(|coords| self.point(coords));
+            self.sys
+                .sketch(ArcOfCircle::new(self.g, self.workplane, center, start, end))
+                .expect("arc created")
+        }
+
+        fn circle(&mut self, center: [f64; 2], radius: f64) -> EntityHandle<Circle> {
+            let center = self.point(center);
+            let radius = self
+                .sys
+                .sketch(Distance::new(self.g, radius))
+                .expect("distance created");
+            self.sys
+                .sketch(Circle::new(self.g, self.normal, center, radius))
+                .expect("circle created")
+        }
+
+        // Solve the constraint with the solver, then check that its residuals are zero
+        // at the solution, and that the rank of the Jacobian gives the same degrees of
+        // freedom as the solver.
+        fn check<C: AsConstraintData>(&mut self, constraint_data: C) {
+            let name = type_name::<C>();
+            let h = self
+                .sys
+                .constrain(constraint_data)
+                .expect("constraint added")
+                .handle();
+            let residuals = |sys: &System| {
+                sys.constraint_residuals(sys.slvs_constraint(h).unwrap())
+                    .expect("residuals found")
+            };
+            assert!(
+                linalg::norm(&residuals(&self.sys)) > 1e-3,
+                "{name} is satisfied before solving"
+            );
+
+            let dof = match self.sys.solve(&self.g) {
+                SolveResult::Ok { dof, .. } => dof as usize,
+                SolveResult::Fail { .. } => panic!("{name} failed to solve"),
+            };
+            assert!(
+                linalg::norm(&residuals(&self.sys)) < 1e-6,
+                "{name} has residuals {:?} after solving",
+                residuals(&self.sys)
+            );
+
+            let group = self.g.handle();
+            let equations = self
+                .sys
+                .group_equations(group, &self.sys.active_constraints(group));
+            let jacobian = self
+                .sys
+                .jacobian(group, &equations)
+                .expect("jacobian found");
+            assert_eq!(
+                jacobian.params.len() - linalg::rank(&jacobian.rows),
+                dof,
+                "{name} has a different number of degrees of freedom"
+            );
+        }
+    }
+
+    // Every constraint type is checked, except for WhereDragged, which the solver
+    // handles by locking the parameters of the point rather than with equations.
+    #[test]
+    fn points_match_solver() {
+        let mut s = Setup::new();
+        let [a, b] = [[0.0, 0.0], [1.0, 2.0]].map(|coords| s.point(coords));
+        s.check(PointsCoincident::new(s.g, a, b, Some(s.workplane)));
+
+        let mut s = Setup::new();
+        let [a, b] = [[0.0, 0.0, 0.0], [1.0, 2.0, 3.0]].map(|coords| s.point_3d(coords));
+        s.check(PointsCoincident::new(s.g, a, b, None));
+
+        let mut s = Setup::new();
+        let [a, b] = [[0.0, 0.0], [1.0, 2.0]].map(|coords| s.point(coords));
+        s.check(PtPtDistance::new(s.g, a, b, 5.0, Some(s.workplane)));
+
+        let mut s = Setup::new();
+        let [a, b] = [[0.0, 0.0, 0.0], [1.0, 2.0, 3.0]].map(|coords| s.point_3d(coords));
+        s.check(PtPtDistance::new(s.g, a, b, 5.0, None));
+
+        let mut s = Setup::new();
+        let point = s.point_3d([5.0, 5.0, 5.0]);
+        s.check(PtPlaneDistance::new(s.g, point, s.workplane, 2.0));
+
+        let mut s = Setup::new();
+        let point = s.point_3d([5.0, 5.0, 5.0]);
+        s.check(PtInPlane::new(s.g, point, s.workplane));
+
+        let mut s = Setup::new();
+        let [a, b] = [[0.0, 0.0, 0.0], [1.0, 2.0, 3.0]].map(|coords| s.point_3d(coords));
+        s.check(Symmetric::new(s.g, a, b, s.workplane, None));
+
+        let mut s = Setup::new();
+        let [a, b] = [[1.0, 1.0], [2.0, 3.0]].map(|coords| s.point(coords));
+        s.check(SymmetricHoriz::new(s.g, s.workplane, a, b));
+
+        let mut s = Setup::new();
+        let [a, b] = [[1.0, 1.0], [2.0, 3.0]].map(|coords| s.point(coords));
+        s.check(SymmetricVert::new(s.g, s.workplane, a, b));
+
+        let mut s = Setup::new();
+        let [a, b] = [[1.0, 1.0], [2.0, 3.0]].map(|coords| s.point(coords));
+        let line = s.line([0.0, -1.0], [4.0, 0.0]);
+        s.check(SymmetricLine::new(s.g, s.workplane, a, b, line));
+
+        let mut s = Setup::new();
+        let [a, b] = [[0.0, 0.0], [1.0, 2.0]].map(|coords| s.point(coords));
+        let along = s.line([0.0, -1.0], [4.0, 0.0]);
+        s.check(ProjPtDistance::new(s.g, a, b, along, -3.0));
+    }
+
+    #[test]
+    fn lines_match_solver() {
+        let mut s = Setup::new();
+        let point = s.point([1.0, -2.0]);
+        let line = s.line([0.0, 0.0], [4.0, 1.0]);
+        s.check(PtLineDistance::new(
+            s.g,
+            point,
+            line,
+            3.0,
+            Some(s.workplane),
+        ));
+
+        let mut s = Setup::new();
+        let point = s.point_3d([1.0, -2.0, 1.0]);
+        let line = s.line_3d([0.0, 0.0, 0.0], [4.0, 1.0, 2.0]);
+        s.check(PtLineDistance::new(s.g, point, line, 3.0, None));
+
+        let mut s = Setup::new();
+        let point = s.point([1.0, -2.0]);
+        let line = s.line([0.0, 0.0], [4.0, 1.0]);
+        s.check(PtOnLine::new(s.g, point, line, Some(s.workplane)));
+
+        let mut s = Setup::new();
+        let point = s.point_3d([1.0, -2.0, 1.0]);
+        let line = s.line_3d([0.0, 0.0, 0.0], [4.0, 1.0, 2.0]);
+        s.check(PtOnLine::new(s.g, point, line, None));
+
+        let mut s = Setup::new();
+        let point = s.point([1.0, -2.0]);
+        let line = s.line([0.0, 0.0], [4.0, 1.0]);
+        s.check(AtMidpoint::new(s.g, point, line, Some(s.workplane)));
+
+        let mut s = Setup::new();
+        let point = s.point_3d([1.0, -2.0, 1.0]);
+        let line = s.line_3d([0.0, 0.0, 0.0], [4.0, 1.0, 2.0]);
+        s.check(AtMidpoint::new(s.g, point, line, None));
+
+        let mut s = Setup::new();
+        let line = s.line([0.0, 0.0], [4.0, 1.0]);
+        s.check(Horizontal::from_line(s.g, s.workplane, line));
+
+        let mut s = Setup::new();
+        let line = s.line([0.0, 0.0], [4.0, 1.0]);
+        s.check(Vertical::from_line(s.g, s.workplane, line));
+
+        let mut s = Setup::new();
+        let line_a = s.line([0.0, 0.0], [4.0, 1.0]);
+        let line_b = s.line([0.0, 2.0], [1.0, 5.0]);
+        s.check(EqualLengthLines::new(
+            s.g,
+            line_a,
+            line_b,
+            Some(s.workplane),
+        ));
+
+        let mut s = Setup::new();
+        let line_a = s.line_3d([0.0, 0.0, 0.0], [4.0, 1.0, 2.0]);
+        let line_b = s.line_3d([0.0, 2.0, 0.0], [1.0, 5.0, 1.0]);
+        s.check(EqualLengthLines::new(s.g, line_a, line_b, None));
+
+        let mut s = Setup::new();
+        let line_a = s.line([0.0, 0.0], [4.0, 1.0]);
+        let line_b = s.line([0.0, 2.0], [1.0, 5.0]);
+        s.check(LengthRatio::new(
+            s.g,
+            line_a,
+            line_b,
+            2.0,
+            Some(s.workplane),
+        ));
+
+        let mut s = Setup::new();
+        let line_a = s.line([0.0, 0.0], [4.0, 1.0]);
+        let line_b = s.line([0.0, 2.0], [1.0, 5.0]);
+        s.check(LengthDifference::new(
+            s.g,
+            line_a,
+            line_b,
+            1.5,
+            Some(s.workplane),
+        ));
+
+        let mut s = Setup::new();
+        let line_a = s.line([0.0, 0.0], [4.0, 1.0]);
+        let point = s.point([2.0, 4.0]);
+        let line_b = s.line([5.0, 0.0], [6.0, 3.0]);
+        s.check(EqLenPtLineD::new(
+            s.g,
+            line_a,
+            point,
+            line_b,
+            Some(s.workplane),
+        ));
+
+        // The points start on opposite sides of their lines, so the signs must agree.
+        let mut s = Setup::new();
+        let line_a = s.line([0.0, 0.0], [4.0, 1.0]);
+        let point_a = s.point([1.0, 2.0]);
+        let line_b = s.line([5.0, 0.0], [6.0, 3.0]);
+        let point_b = s.point([4.0, 3.0]);
+        s.check(EqPtLnDistances::new(
+            s.g,
+            line_a,
+            point_a,
+            line_b,
+            point_b,
+            Some(s.workplane),
+        ));
+
+        let mut s = Setup::new();
+        let line_a = s.line([0.0, 0.0], [4.0, 1.0]);
+        let line_b = s.line([0.0, 2.0], [1.0, 5.0]);
+        s.check(Parallel::new(s.g, line_a, line_b, Some(s.workplane)));
+
+        let mut s = Setup::new();
+        let line_a = s.line([0.0, 0.0], [4.0, 1.0]);
+        let line_b = s.line([0.0, 2.0], [3.0, 5.0]);
+        s.check(Perpendicular::new(s.g, line_a, line_b, Some(s.workplane)));
+
+        let mut s = Setup::new();
+        let line_a = s.line_3d([0.0, 0.0, 0.0], [4.0, 1.0, 2.0]);
+        let line_b = s.line_3d([0.0, 2.0, 0.0], [3.0, 5.0, 1.0]);
+        s.check(Perpendicular::new(s.g, line_a, line_b, None));
+
+        for supplementary in [false, true] {
+            let mut s = Setup::new();
+            let line_a = s.line([0.0, 0.0], [4.0, 1.0]);
+            let line_b = s.line([0.0, 2.0], [1.0, 5.0]);
+            s.check(Angle::new(
+                s.g,
+                line_a,
+                line_b,
+                30.0,
+                Some(s.workplane),
+                supplementary,
+            ));
+        }
+
+        let mut s = Setup::new();
+        let line_a = s.line_3d([0.0, 0.0, 0.0], [4.0, 1.0, 2.0]);
+        let line_b = s.line_3d([0.0, 2.0, 0.0], [1.0, 5.0, 1.0]);
+        s.check(Angle::new(s.g, line_a, line_b, 30.0, None, false));
+
+        let mut s = Setup::new();
+        let line_a = s.line([0.0, 0.0], [4.0, 1.0]);
+        let line_b = s.line([0.0, 2.0], [1.0, 5.0]);
+        let line_c = s.line([5.0, 0.0], [9.0, 0.0]);
+        let line_d = s.line([5.0, 2.0], [9.0, 3.0]);
+        s.check(EqualAngle::new(
+            s.g,
+            line_a,
+            line_b,
+            line_c,
+            line_d,
+            Some(s.workplane),
+            false,
+        ));
+    }
+
+    #[test]
+    #[ignore] // Crashes due to bug in original library.
+    fn same_orientation_matches_solver() {
+        let mut s = Setup::new();
+        let normal_a = s
+            .sys
+            .sketch(Normal::new_in_3d(
+                s.g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let normal_b = s
+            .sys
+            .sketch(Normal::new_in_3d(
+                s.g,
+                make_quaternion([0.8, 0.6, 0.0], [-0.6, 0.8, 0.0]),
+            ))
+            .expect("normal created");
+        s.check(SameOrientation::new(s.g, normal_a, normal_b));
+    }
+
+    #[test]
+    #[ignore] // Crashes due to bug in original library.
+    fn parallel_in_3d_matches_solver() {
+        let mut s = Setup::new();
+        let line_a = s.line_3d([0.0, 0.0, 0.0], [4.0, 1.0, 2.0]);
+        let line_b = s.line_3d([0.0, 2.0, 0.0], [1.0, 5.0, 1.0]);
+        s.check(Parallel::new(s.g, line_a, line_b, None));
+    }
+
+    #[test]
+    fn curves_match_solver() {
+        let mut s = Setup::new();
+        let arc = s.arc([0.0, 0.0], [2.0, 0.0], [0.0, 2.0]);
+        s.check(Diameter::new(s.g, arc, 6.0));
+
+        let mut s = Setup::new();
+        let circle = s.circle([0.0, 0.0], 2.0);
+        s.check(Diameter::new(s.g, circle, 6.0));
+
+        let mut s = Setup::new();
+        let arc = s.arc([0.0, 0.0], [2.0, 0.0], [0.0, 2.0]);
+        let circle = s.circle([5.0, 0.0], 1.0);
+        s.check(EqualRadius::new(s.g, arc, circle));
+
+        let mut s = Setup::new();
+        let point = s.point([3.0, 1.0]);
+        let arc = s.arc([0.0, 0.0], [2.0, 0.0], [0.0, 2.0]);
+        s.check(PtOnCircle::new(s.g, point, arc));
+
+        let mut s = Setup::new();
+        let line = s.line([0.0, 0.0], [4.0, 1.0]);
+        let arc = s.arc([0.0, 3.0], [2.0, 3.0], [0.0, 5.0]);
+        s.check(EqualLineArcLen::new(s.g, line, arc));
+
+        let mut s = Setup::new();
+        let arc_a = s.arc([0.0, 0.0], [2.0, 0.0], [0.0, 2.0]);
+        let arc_b = s.arc([5.0, 0.0], [6.0, 0.0], [5.0, 1.0]);
+        s.check(ArcArcLenRatio::new(s.g, arc_a, arc_b, 3.0));
+
+        let mut s = Setup::new();
+        let arc_a = s.arc([0.0, 0.0], [2.0, 0.0], [0.0, 2.0]);
+        let arc_b = s.arc([5.0, 0.0], [6.0, 0.0], [5.0, 1.0]);
+        s.check(ArcArcDifference::new(s.g, arc_a, arc_b, 2.0));
+
+        let mut s = Setup::new();
+        let arc = s.arc([0.0, 0.0], [2.0, 0.0], [0.0, 2.0]);
+        let line = s.line([3.0, 0.0], [7.0, 1.0]);
+        s.check(ArcLineLenRatio::new(s.g, arc, line, 0.5));
+
+        let mut s = Setup::new();
+        let arc = s.arc([0.0, 0.0], [2.0, 0.0], [0.0, 2.0]);
+        let line = s.line([3.0, 0.0], [7.0, 1.0]);
+        s.check(ArcLineDifference::new(s.g, arc, line, -1.0));
+
+        // Tangencies are solved from nearly tangent curves that share the end point.
+        for to_end in [false, true] {
+            let mut s = Setup::new();
+            let [center, start, end] =
+                [[0.0, 0.0], [2.0, 0.0], [0.0, 2.0]].map(|coords| s.point(coords));
+            let arc = s
+                .sys
+                .sketch(ArcOfCircle::new(s.g, s.workplane, center, start, end))
+                .expect("arc created");
+            let (joint, far) = if to_end {
+                (end, s.point([-3.0, 2.5]))
+            } else {
+                (start, s.point([2.5, -3.0]))
+            };
+            let line = s
+                .sys
+                .sketch(LineSegment::new(s.g, joint, far))
+                .expect("line created");
+            s.check(ArcLineTangent::new(s.g, s.workplane, arc, line, to_end));
+        }
+
+        let mut s = Setup::new();
+        let [a, b, c, d] =
+            [[0.0, 0.0], [1.0, 1.0], [2.0, 1.0], [3.0, 0.0]].map(|coords| s.point(coords));
+        let cubic = s
+            .sys
+            .sketch(Cubic::new(s.g, a, b, c, d))
+            .expect("cubic created");
+        let far = s.point([-3.0, -2.5]);
+        let line = s
+            .sys
+            .sketch(LineSegment::new(s.g, a, far))
+            .expect("line created");
+        s.check(CubicLineTangent::new(
+            s.g,
+            cubic,
+            line,
+            false,
+            Some(s.workplane),
+        ));
+
+        let mut s = Setup::new();
+        let [center_a, joint, start_a] =
+            [[0.0, 0.0], [2.0, 0.0], [0.0, -2.0]].map(|coords| s.point(coords));
+        let arc_a = s
+            .sys
+            .sketch(ArcOfCircle::new(s.g, s.workplane, center_a, start_a, joint))
+            .expect("arc created");
+        let [center_b, end_b] = [[4.0, 0.5], [4.0, 2.5]].map(|coords| s.point(coords));
+        let arc_b = s
+            .sys
+            .sketch(ArcOfCircle::new(s.g, s.workplane, center_b, end_b, joint))
+            .expect("arc created");
+        s.check(CurveCurveTangent::new(
+            s.g,
+            s.workplane,
+            arc_a,
+            arc_b,
+            true,
+            true,
+        ));
+
+        let mut s = Setup::new();
+        let [center, start, joint] =
+            [[0.0, 0.0], [0.0, -2.0], [2.0, 0.0]].map(|coords| s.point(coords));
+        let arc = s
+            .sys
+            .sketch(ArcOfCircle::new(s.g, s.workplane, center, start, joint))
+            .expect("arc created");
+        let [b, c, d] = [[2.5, 1.0], [3.0, 3.0], [5.0, 3.0]].map(|coords| s.point(coords));
+        let cubic = s
+            .sys
+            .sketch(Cubic::new(s.g, joint, b, c, d))
+            .expect("cubic created");
+        s.check(CurveCurveTangent::new(
+            s.g,
+            s.workplane,
+            arc,
+            cubic,
+            true,
+            false,
+        ));
+    }
+}
@@ -0,0 +1,809 @@
+/*!
+Rounding and cutting off the corner where two curves meet.
+
+[`System::fillet`] replaces the corner with an arc tangent to both curves, and
+[`System::chamfer`] replaces it with a line. The curves are shortened to meet the new
+curve at new end points, and constraints on the corner point are moved onto those
+points. The corner point is then deleted.
+*/
+
+use std::f64::consts::PI;
+
+use crate::{
+    bindings::{Slvs_Entity, SLVS_E_ARC_OF_CIRCLE, SLVS_E_LINE_SEGMENT, SLVS_E_POINT_IN_2D},
+    constraint::{
+        ArcLineTangent, AsConstraintHandle, ConstraintHandle, CurveCurveTangent, Diameter,
+        PtOnCircle, PtOnLine, PtPtDistance,
+    },
+    element::AsHandle,
+    entity::{ArcOfCircle, AsEntityHandle, EntityHandle, LineSegment, Point, Workplane},
+    geometry::{add, scale, sub},
+    group::Group,
+    system::SOLVE_TOLERANCE,
+    utils::distance,
+    System,
+};
+
+/// The arc added by [`System::fillet`].
+#[derive(Debug)]
+pub struct Fillet {
+    pub arc: EntityHandle<ArcOfCircle>,
+    /// The curves that met at the corner.
+    pub curves: [Box<dyn AsEntityHandle>; 2],
+    /// The new end points of `curves`, where they meet the arc.
+    pub points: [EntityHandle<Point>; 2],
+    /// Constraints holding the arc tangent to each of `curves`.
+    pub tangents: [Box<dyn AsConstraintHandle>; 2],
+    pub diameter: ConstraintHandle<Diameter<ArcOfCircle>>,
+}
+
+/// The line added by [`System::chamfer`].
+#[derive(Debug)]
+pub struct Chamfer {
+    pub line: EntityHandle<LineSegment>,
+    /// The curves that met at the corner.
+    pub curves: [Box<dyn AsEntityHandle>; 2],
+    /// The new end points of `curves`, where they meet the line.
+    pub points: [EntityHandle<Point>; 2],
+    /// A construction point where the curves, extended, still meet.
+    pub corner: EntityHandle<Point>,
+    /// Constraints holding `corner` on each of `curves`.
+    pub on_curves: [Box<dyn AsConstraintHandle>; 2],
+    /// The distances from `corner` to each of `points`.
+    pub setbacks: [ConstraintHandle<PtPtDistance>; 2],
+}
+
+// The point where two curves meet, in the coordinates of its workplane.
+struct Corner {
+    group: Group,
+    workplane: EntityHandle<Workplane>,
+    coords: [f64; 2],
+    neighbors: [Neighbor; 2],
+}
+
+// A curve that ends at a corner, in the coordinates of the corner's workplane.
+struct Neighbor {
+    slvs_entity: Slvs_Entity,
+    // The index in `slvs_entity.point` of the end at the corner.
+    end: usize,
+    shape: Shape,
+}
+
+enum Shape {
+    // The direction from the corner along the line, and its length.
+    Line {
+        direction: [f64; 2],
+        length: f64,
+    },
+    // Angles are in radians.
+    Arc {
+        center: [f64; 2],
+        radius: f64,
+        start_angle: f64,
+        sweep: f64,
+    },
+}
+
+// An unbounded line or a full circle, such as the points that a fillet's center can be
+// at, at a given distance from a curve.
+pub(crate) enum Locus {
+    Line {
+        point: [f64; 2],
+        direction: [f64; 2],
+    },
+    Circle {
+        center: [f64; 2],
+        radius: f64,
+    },
+}
+
+impl System {
+    /// Round off the corner where two curves meet with an arc of the given radius.
+    ///
+    /// The corner must be the end point of exactly two line segments or arcs, and
+    /// not be used by any other entity. The new entities and constraints are created
+    /// in the corner's group.
+    ///
+    /// # Arguments
+    ///
+    /// * `corner` - The point where the curves meet. It must be on a workplane.
+    /// * `radius` - The radius of the arc.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{LineSegment, Normal, Point, Workplane},
+    ///     utils::make_quaternion,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// let g = sys.add_group();
+    /// let [start, corner, end] = [[10.0, 0.0], [0.0, 0.0], [0.0, 10.0]].map(|coords| {
+    ///     sys.sketch(Point::new_on_workplane(g, workplane, coords))
+    ///         .expect("point created")
+    /// });
+    /// sys.sketch(LineSegment::new(g, start, corner))
+    ///     .expect("line created");
+    /// sys.sketch(LineSegment::new(g, corner, end))
+    ///     .expect("line created");
+    ///
+    /// let fillet = sys.fillet(&corner, 2.0).expect("corner rounded");
+    /// let arc = sys.arc_geometry_on(&fillet.arc, &workplane).expect("arc found");
+    /// assert!((arc.center[0] - 2.0).abs() < 1e-9 && (arc.center[1] - 2.0).abs() < 1e-9);
+    /// assert!((arc.sweep() - 90.0).abs() < 1e-9);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the corner is not the end point of two line segments or
+    /// arcs on a workplane, if it is used by other entities, or if the curves are too
+    /// short or too tightly curved to fit an arc of the given radius.
+    pub fn fillet(
+        &mut self,
+        corner: &EntityHandle<Point>,
+        radius: f64,
+    ) -> Result<Fillet, &'static str> {
+        if radius <= 0.0 {
+            return Err("Expected fillet radius to be positive.");
+        }
+
+        let Corner {
+            group,
+            workplane,
+            coords: corner_coords,
+            neighbors,
+        } = self.corner(corner)?;
+
+        // The center is at `radius` from both curves, on either side of each. Of the
+        // centers whose tangent points lie on both curves, use the one nearest the
+        // corner.
+        let loci = neighbors
+            .each_ref()
+            .map(|neighbor| neighbor.offsets(corner_coords, radius));
+        let (center, tangent_points) = loci[0]
+            .iter()
+            .flat_map(|locus_a| loci[1].iter().flat_map(|locus_b| locus_a.meet(locus_b)))
+            .filter_map(|center| {
+                let tangent_points = [
+                    neighbors[0].tangent_point(corner_coords, center)?,
+                    neighbors[1].tangent_point(corner_coords, center)?,
+                ];
+                Some((center, tangent_points))
+            })
+            .min_by(|(_, a), (_, b)| {
+                let reach = |points: &[[f64; 2]; 2]| {
+                    distance(points[0], corner_coords) + distance(points[1], corner_coords)
+                };
+                reach(a).total_cmp(&reach(b))
+            })
+            .ok_or("Fillet does not fit between the curves at the corner.")?;
+
+        let points = self.cut_corner(corner, group, workplane, &neighbors, tangent_points)?;
+
+        // Go counter-clockwise around the side of the center that faces the corner.
+        let [angle_a, angle_b] = tangent_points.map(|point| angle_of(center, point));
+        let sweep = (angle_b - angle_a).rem_euclid(2.0 * PI);
+        let middle_angle = angle_a + sweep / 2.0;
+        let middle = add(
+            center,
+            [radius * middle_angle.cos(), radius * middle_angle.sin()],
+        );
+        let mirrored = sub(scale(center, 2.0), middle);
+        let (arc_start, arc_end) =
+            if distance(middle, corner_coords) <= distance(mirrored, corner_coords) {
+                (points[0], points[1])
+            } else {
+                (points[1], points[0])
+            };
+
+        let center_point = self.sketch(Point::new_on_workplane(group, workplane, center))?;
+        let arc = self.sketch(ArcOfCircle::new(
+            group,
+            workplane,
+            center_point,
+            arc_start,
+            arc_end,
+        ))?;
+
+        let tangents = [
+            self.fillet_tangent(group, workplane, arc, &neighbors[0], arc_end == points[0])?,
+            self.fillet_tangent(group, workplane, arc, &neighbors[1], arc_end == points[1])?,
+        ];
+        let diameter = self.constrain(Diameter::new(group, arc, 2.0 * radius))?;
+
+        Ok(Fillet {
+            arc,
+            curves: neighbors.map(|neighbor| neighbor.slvs_entity.into()),
+            points,
+            tangents,
+            diameter,
+        })
+    }
+
+    /// Cut off the corner where two curves meet with a line.
+    ///
+    /// Each curve is shortened so that it ends at the given distance from the corner,
+    /// and the line joins the new ends. A construction point is left at the corner,
+    /// held on both curves, and the distance from it to each new end is held by a
+    /// constraint. The corner must be the end point of exactly two line segments or
+    /// arcs, and not be used by any other entity. The new entities and constraints
+    /// are created in the corner's group.
+    ///
+    /// # Arguments
+    ///
+    /// * `corner` - The point where the curves meet. It must be on a workplane.
+    /// * `setback` - The straight distance from the corner to the new end of each
+    ///   curve.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the corner is not the end point of two line segments or
+    /// arcs on a workplane, if it is used by other entities, or if a curve is too
+    /// short to be cut back by the given distance.
+    pub fn chamfer(
+        &mut self,
+        corner: &EntityHandle<Point>,
+        setback: f64,
+    ) -> Result<Chamfer, &'static str> {
+        if setback <= 0.0 {
+            return Err("Expected chamfer distance to be positive.");
+        }
+
+        let Corner {
+            group,
+            workplane,
+            coords: corner_coords,
+            neighbors,
+        } = self.corner(corner)?;
+        let [point_a, point_b] = neighbors
+            .each_ref()
+            .map(|neighbor| neighbor.setback_point(corner_coords, setback));
+        let setback_points = [
+            point_a.ok_or("Chamfer is longer than the curves at the corner.")?,
+            point_b.ok_or("Chamfer is longer than the curves at the corner.")?,
+        ];
+
+        let points = self.cut_corner(corner, group, workplane, &neighbors, setback_points)?;
+        let line = self.sketch(LineSegment::new(group, points[0], points[1]))?;
+
+        let corner = self.sketch(Point::new_on_workplane(group, workplane, corner_coords))?;
+        self.set_construction(&corner, true)?;
+        let on_curves = [
+            self.chamfer_corner_on(group, workplane, corner, &neighbors[0])?,
+            self.chamfer_corner_on(group, workplane, corner, &neighbors[1])?,
+        ];
+        let setbacks = [
+            self.constrain(PtPtDistance::new(
+                group,
+                corner,
+                points[0],
+                setback,
+                Some(workplane),
+            ))?,
+            self.constrain(PtPtDistance::new(
+                group,
+                corner,
+                points[1],
+                setback,
+                Some(workplane),
+            ))?,
+        ];
+
+        Ok(Chamfer {
+            line,
+            curves: neighbors.map(|neighbor| neighbor.slvs_entity.into()),
+            points,
+            corner,
+            on_curves,
+            setbacks,
+        })
+    }
+
+    // Hold a chamfer's construction corner on a curve, or on the curve extended.
+    fn chamfer_corner_on(
+        &mut self,
+        group: Group,
+        workplane: EntityHandle<Workplane>,
+        corner: EntityHandle<Point>,
+        neighbor: &Neighbor,
+    ) -> Result<Box<dyn AsConstraintHandle>, &'static str> {
+        let h = neighbor.slvs_entity.h;
+        Ok(match neighbor.shape {
+            Shape::Line { .. } => Box::new(self.constrain(PtOnLine::new(
+                group,
+                corner,
+                EntityHandle::new(h),
+                Some(workplane),
+            ))?),
+            Shape::Arc { .. } => Box::new(self.constrain(PtOnCircle::new(
+                group,
+                corner,
+                EntityHandle::<ArcOfCircle>::new(h),
+            ))?),
+        })
+    }
+
+    // Hold a fillet's arc tangent to a curve, at the arc's start or end.
+    fn fillet_tangent(
+        &mut self,
+        group: Group,
+        workplane: EntityHandle<Workplane>,
+        arc: EntityHandle<ArcOfCircle>,
+        neighbor: &Neighbor,
+        to_end: bool,
+    ) -> Result<Box<dyn AsConstraintHandle>, &'static str> {
+        let h = neighbor.slvs_entity.h;
+        Ok(match neighbor.shape {
+            Shape::Line { .. } => Box::new(self.constrain(ArcLineTangent::new(
+                group,
+                workplane,
+                arc,
+                EntityHandle::new(h),
+                to_end,
+            ))?),
+            Shape::Arc { .. } => Box::new(self.constrain(CurveCurveTangent::new(
+                group,
+                workplane,
+                arc,
+                EntityHandle::<ArcOfCircle>::new(h),
+                to_end,
+                neighbor.end == 2,
+            ))?),
+        })
+    }
+
+    fn corner(&self, corner: &EntityHandle<Point>) -> Result<Corner, &'static str> {
+        let slvs_corner = self.slvs_entity(corner.handle())?;
+        if SLVS_E_POINT_IN_2D != slvs_corner.type_ as _ {
+            return Err("Expected corner to be a point on a workplane.");
+        }
+        let workplane = EntityHandle::new(slvs_corner.wrkpl);
+        let corner_coords = self.point_coords_on(corner.handle(), slvs_corner.wrkpl)?;
+
+        let users: Vec<&Slvs_Entity> = self
+            .entities
+            .list
+            .iter()
+            .filter(|slvs_entity| slvs_entity.point.contains(&corner.handle()))
+            .collect();
+        let neighbors = users
+            .iter()
+            .filter_map(|slvs_entity| {
+                let end = match slvs_entity.type_ as _ {
+                    SLVS_E_LINE_SEGMENT => slvs_entity.point[..2]
+                        .iter()
+                        .position(|&h| h == corner.handle())?,
+                    SLVS_E_ARC_OF_CIRCLE => {
+                        1 + slvs_entity.point[1..3]
+                            .iter()
+                            .position(|&h| h == corner.handle())?
+                    }
+                    _ => return None,
+                };
+                Some(self.neighbor(slvs_entity, end, &workplane, corner_coords))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if users.len() != 2 || neighbors.len() != 2 {
+            return Err("Expected corner to be the end point of exactly two line segments or arcs, and of no other entity.");
+        }
+        let neighbors: [Neighbor; 2] = neighbors.try_into().map_err(|_| "Expected two curves.")?;
+
+        Ok(Corner {
+            group: Group(slvs_corner.group),
+            workplane,
+            coords: corner_coords,
+            neighbors,
+        })
+    }
+
+    fn neighbor(
+        &self,
+        slvs_entity: &Slvs_Entity,
+        end: usize,
+        workplane: &EntityHandle<Workplane>,
+        corner_coords: [f64; 2],
+    ) -> Result<Neighbor, &'static str> {
+        let shape = if SLVS_E_LINE_SEGMENT == slvs_entity.type_ as _ {
+            let far_coords =
+                self.point_coords_on(slvs_entity.point[1 - end], workplane.handle())?;
+            let length = distance(corner_coords, far_coords);
+            if length <= SOLVE_TOLERANCE {
+                return Err("Expected line at the corner to have non-zero length.");
+            }
+            Shape::Line {
+                direction: scale(sub(far_coords, corner_coords), 1.0 / length),
+                length,
+            }
+        } else {
+            let arc = self.arc_geometry_on(&EntityHandle::new(slvs_entity.h), workplane)?;
+            Shape::Arc {
+                center: arc.center,
+                radius: arc.radius,
+                start_angle: arc.start_angle.to_radians(),
+                sweep: arc.sweep().to_radians(),
+            }
+        };
+
+        Ok(Neighbor {
+            slvs_entity: *slvs_entity,
+            end,
+            shape,
+        })
+    }
+
+    // Move the ends of the curves at a corner onto new points, and the constraints on
+    // the corner along with them, then delete the corner.
+    fn cut_corner(
+        &mut self,
+        corner: &EntityHandle<Point>,
+        group: Group,
+        workplane: EntityHandle<Workplane>,
+        neighbors: &[Neighbor; 2],
+        coords: [[f64; 2]; 2],
+    ) -> Result<[EntityHandle<Point>; 2], &'static str> {
+        let points = [
+            self.sketch(Point::new_on_workplane(group, workplane, coords[0]))?,
+            self.sketch(Point::new_on_workplane(group, workplane, coords[1]))?,
+        ];
+        for (neighbor, point) in neighbors.iter().zip(points) {
+            self.mut_slvs_entity(neighbor.slvs_entity.h)?.point[neighbor.end] = point.handle();
+        }
+
+        // A constraint that also references the second curve follows its end point,
+        // others follow the end point of the first curve.
+        let second_h = neighbors[1].slvs_entity.h;
+        for slvs_constraint in &mut self.constraints.list {
+            let target = if [
+                slvs_constraint.entityA,
+                slvs_constraint.entityB,
+                slvs_constraint.entityC,
+                slvs_constraint.entityD,
+            ]
+            .contains(&second_h)
+            {
+                points[1]
+            } else {
+                points[0]
+            };
+
+            for point_h in [&mut slvs_constraint.ptA, &mut slvs_constraint.ptB] {
+                if *point_h == corner.handle() {
+                    *point_h = target.handle();
+                }
+            }
+        }
+
+        self.delete_entity(*corner)?;
+        Ok(points)
+    }
+}
+
+impl Neighbor {
+    // The lines or circles at a distance from the curve, on either side of it.
+    fn offsets(&self, corner_coords: [f64; 2], offset: f64) -> Vec<Locus> {
+        match self.shape {
+            Shape::Line { direction, .. } => [-1.0, 1.0]
+                .map(|side| Locus::Line {
+                    point: add(
+                        corner_coords,
+                        scale([-direction[1], direction[0]], side * offset),
+                    ),
+                    direction,
+                })
+                .into_iter()
+                .collect(),
+            Shape::Arc { center, radius, .. } => [radius - offset, radius + offset]
+                .into_iter()
+                .filter(|&radius| radius > SOLVE_TOLERANCE)
+                .map(|radius| Locus::Circle { center, radius })
+                .collect(),
+        }
+    }
+
+    // The point on the curve nearest a fillet's center, if it lies on the curve away
+    // from its ends.
+    fn tangent_point(&self, corner_coords: [f64; 2], center: [f64; 2]) -> Option<[f64; 2]> {
+        match self.shape {
+            Shape::Line { direction, length } => {
+                let along = dot(sub(center, corner_coords), direction);
+                (SOLVE_TOLERANCE..length - SOLVE_TOLERANCE)
+                    .contains(&along)
+                    .then(|| add(corner_coords, scale(direction, along)))
+            }
+            Shape::Arc {
+                center: arc_center,
+                radius,
+                ..
+            } => {
+                let offset = distance(center, arc_center);
+                if offset <= SOLVE_TOLERANCE {
+                    return None;
+                }
+                let point = add(arc_center, scale(sub(center, arc_center), radius / offset));
+                self.within(point).then_some(point)
+            }
+        }
+    }
+
+    // The point on the curve at a straight distance from the corner, nearest the
+    // corner along the curve.
+    fn setback_point(&self, corner_coords: [f64; 2], setback: f64) -> Option<[f64; 2]> {
+        match self.shape {
+            Shape::Line { direction, length } => (setback < length - SOLVE_TOLERANCE)
+                .then(|| add(corner_coords, scale(direction, setback))),
+            Shape::Arc { center, radius, .. } => Locus::Circle {
+                center: corner_coords,
+                radius: setback,
+            }
+            .meet(&Locus::Circle { center, radius })
+            .into_iter()
+            .filter(|point| self.within(*point))
+            .min_by(|a, b| {
+                self.sweep_from_corner(*a)
+                    .total_cmp(&self.sweep_from_corner(*b))
+            }),
+        }
+    }
+
+    // Whether a point on an arc's circle lies on the arc, away from its ends.
+    fn within(&self, point: [f64; 2]) -> bool {
+        match self.shape {
+            Shape::Line { .. } => unreachable!("Only arcs are checked for points on them."),
+            Shape::Arc {
+                center,
+                radius,
+                start_angle,
+                sweep,
+            } => {
+                let tolerance = SOLVE_TOLERANCE / radius;
+                let angle = (angle_of(center, point) - start_angle).rem_euclid(2.0 * PI);
+                angle > tolerance && angle < sweep - tolerance
+            }
+        }
+    }
+
+    // The angle around an arc from its end at the corner to a point on it.
+    fn sweep_from_corner(&self, point: [f64; 2]) -> f64 {
+        let Shape::Arc {
+            center,
+            start_angle,
+            sweep,
+            ..
+        } = self.shape
+        else {
+            unreachable!("Only arcs are measured around.")
+        };
+        let angle = (angle_of(center, point) - start_angle).rem_euclid(2.0 * PI);
+        if self.end == 1 {
+            angle
+        } else {
+            sweep - angle
+        }
+    }
+}
+
+impl Locus {
+    // The points where two loci meet.
+    pub(crate) fn meet(&self, other: &Locus) -> Vec<[f64; 2]> {
+        match (self, other) {
+            (
+                Locus::Line {
+                    point: point_a,
+                    direction: direction_a,
+                },
+                Locus::Line {
+                    point: point_b,
+                    direction: direction_b,
+                },
+            ) => {
+                let denominator = cross(*direction_a, *direction_b);
+                if denominator.abs() <= SOLVE_TOLERANCE {
+                    return Vec::new();
+                }
+                let along = cross(sub(*point_b, *point_a), *direction_b) / denominator;
+                vec![add(*point_a, scale(*direction_a, along))]
+            }
+            (Locus::Line { point, direction }, Locus::Circle { center, radius })
+            | (Locus::Circle { center, radius }, Locus::Line { point, direction }) => {
+                // The foot of the perpendicular from the center to the line, and the
+                // distance from there to where the line crosses the circle.
+                let foot = add(
+                    *point,
+                    scale(*direction, dot(sub(*center, *point), *direction)),
+                );
+                let gap = distance(foot, *center);
+                if gap > radius + SOLVE_TOLERANCE {
+                    return Vec::new();
+                }
+                let half_chord = (radius.powi(2) - gap.powi(2)).max(0.0).sqrt();
+                vec![
+                    sub(foot, scale(*direction, half_chord)),
+                    add(foot, scale(*direction, half_chord)),
+                ]
+            }
+            (
+                Locus::Circle {
+                    center: center_a,
+                    radius: radius_a,
+                },
+                Locus::Circle {
+                    center: center_b,
+                    radius: radius_b,
+                },
+            ) => {
+                let gap = distance(*center_a, *center_b);
+                if gap <= SOLVE_TOLERANCE
+                    || gap > radius_a + radius_b + SOLVE_TOLERANCE
+                    || gap < (radius_a - radius_b).abs() - SOLVE_TOLERANCE
+                {
+                    return Vec::new();
+                }
+                let direction = scale(sub(*center_b, *center_a), 1.0 / gap);
+                let along = (gap.powi(2) + radius_a.powi(2) - radius_b.powi(2)) / (2.0 * gap);
+                let half_chord = (radius_a.powi(2) - along.powi(2)).max(0.0).sqrt();
+                let foot = add(*center_a, scale(direction, along));
+                let perpendicular = [-direction[1], direction[0]];
+                vec![
+                    add(foot, scale(perpendicular, half_chord)),
+                    sub(foot, scale(perpendicular, half_chord)),
+                ]
+            }
+        }
+    }
+}
+
+fn angle_of(center: [f64; 2], point: [f64; 2]) -> f64 {
+    (point[1] - center[1]).atan2(point[0] - center[0])
+}
+
+fn dot(a: [f64; 2], b: [f64; 2]) -> f64 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+fn cross(a: [f64; 2], b: [f64; 2]) -> f64 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constraint::{Horizontal, PtPtDistance},
+        element::AsHandle,
+        entity::{ArcOfCircle, EntityHandle, LineSegment, Normal, Point, Workplane},
+        len_within_tolerance,
+        system::SolveResult,
+        utils::{distance, make_quaternion},
+        System,
+    };
+
+    fn coords(sys: &System, point: &EntityHandle<Point>) -> [f64; 2] {
+        match sys.entity_data(point).expect("point found") {
+            Point::OnWorkplane { coords, .. } => coords,
+            Point::In3d { .. } => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn fillet_and_chamfer() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let g = sys.add_group();
+        let mut point = |coords| {
+            sys.sketch(Point::new_on_workplane(g, workplane, coords))
+                .expect("point created")
+        };
+        // Two lines meeting at a right angle, and a line meeting an arc.
+        let [line_start, line_corner, line_end] =
+            [[10.0, 0.0], [0.0, 0.0], [0.0, 10.0]].map(&mut point);
+        let [arc_center, arc_corner, arc_end, arc_line_end] =
+            [[20.0, 0.0], [25.0, 0.0], [20.0, 5.0], [30.0, -5.0]].map(&mut point);
+        // Two lines meeting at a corner to be chamfered.
+        let [chamfer_start, chamfer_corner, chamfer_end] =
+            [[40.0, 0.0], [50.0, 0.0], [50.0, 10.0]].map(&mut point);
+
+        let line_a = sys
+            .sketch(LineSegment::new(g, line_start, line_corner))
+            .expect("line created");
+        sys.sketch(LineSegment::new(g, line_corner, line_end))
+            .expect("line created");
+        sys.sketch(ArcOfCircle::new(
+            g, workplane, arc_center, arc_corner, arc_end,
+        ))
+        .expect("arc created");
+        sys.sketch(LineSegment::new(g, arc_corner, arc_line_end))
+            .expect("line created");
+        sys.sketch(LineSegment::new(g, chamfer_start, chamfer_corner))
+            .expect("line created");
+        sys.sketch(LineSegment::new(g, chamfer_corner, chamfer_end))
+            .expect("line created");
+        let horizontal = sys
+            .constrain(Horizontal::from_points(
+                g,
+                workplane,
+                line_start,
+                line_corner,
+            ))
+            .expect("constraint added");
+
+        // Lines at a right angle.
+        let fillet = sys.fillet(&line_corner, 2.0).expect("corner rounded");
+        assert!(sys.entity_data(&line_corner).is_err());
+        assert_eq!(fillet.curves[0].handle(), line_a.handle());
+        assert_eq!(coords(&sys, &fillet.points[0]), [2.0, 0.0]);
+        assert_eq!(coords(&sys, &fillet.points[1]), [0.0, 2.0]);
+        if let Horizontal::Points { point_b, .. } =
+            sys.constraint_data(&horizontal).expect("constraint found")
+        {
+            assert_eq!(point_b, fillet.points[0]);
+        }
+
+        // A line meeting an arc, which is filleted on the outside of the arc.
+        let arc_fillet = sys.fillet(&arc_corner, 1.0).expect("corner rounded");
+        let arc = sys
+            .arc_geometry_on(&arc_fillet.arc, &workplane)
+            .expect("arc found");
+        len_within_tolerance!(distance(arc.center, [20.0, 0.0]), 6.0);
+        len_within_tolerance!(arc.radius, 1.0);
+
+        // Lines at a right angle, cut back by 1 on each side.
+        let chamfer = sys.chamfer(&chamfer_corner, 1.0).expect("corner cut");
+        assert_eq!(coords(&sys, &chamfer.points[0]), [49.0, 0.0]);
+        assert_eq!(coords(&sys, &chamfer.points[1]), [50.0, 1.0]);
+        assert_eq!(coords(&sys, &chamfer.corner), [50.0, 0.0]);
+        assert!(sys.is_construction(&chamfer.corner));
+        for setback in &chamfer.setbacks {
+            let PtPtDistance { distance, .. } = sys.constraint_data(setback).expect("found");
+            len_within_tolerance!(distance, 1.0);
+        }
+
+        // The new geometry satisfies its constraints, so solving leaves it in place.
+        let points = [
+            fillet.points,
+            arc_fillet.points,
+            chamfer.points,
+            [chamfer.corner; 2],
+        ]
+        .concat();
+        let before: Vec<[f64; 2]> = points.iter().map(|point| coords(&sys, point)).collect();
+        assert!(matches!(sys.solve(&g), SolveResult::Ok { .. }));
+        for (point, before) in points.iter().zip(before) {
+            let after = coords(&sys, point);
+            len_within_tolerance!(after[0], before[0]);
+            len_within_tolerance!(after[1], before[1]);
+        }
+
+        assert!(sys.fillet(&line_start, 1.0).is_err());
+        assert!(sys.chamfer(&chamfer.points[0], 20.0).is_err());
+    }
+}
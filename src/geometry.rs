@@ -0,0 +1,683 @@
+/*!
+Derived geometry of solved entities.
+
+The methods in this module resolve a point or entity handle into its geometry,
+following the references to its points, normal and workplane. Each curve can be
+resolved in world coordinates, or in the coordinates of a workplane with the `_on`
+variant of each method. Resolving in a workplane projects the geometry onto it, so
+arcs and circles can only be resolved on workplanes that they are parallel to. Arcs
+must also face the same way as the workplane, since seen from behind they would run
+clockwise.
+
+Angles are in degrees, going counter-clockwise from the `u` axis of the workplane
+that an arc lies on, to match the convention of [`ArcOfCircle`].
+*/
+
+use euclid::default::Vector3D;
+
+use crate::{
+    bindings::Slvs_hEntity,
+    element::AsHandle,
+    entity::{ArcOfCircle, Circle, Cubic, EntityHandle, LineSegment, Normal, Point, Workplane},
+    system::SOLVE_TOLERANCE,
+    utils::{distance, quaternion_n, quaternion_u, quaternion_v},
+    System,
+};
+
+/// A line segment, in `N` dimensional coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineGeometry<const N: usize> {
+    pub start: [f64; N],
+    pub end: [f64; N],
+}
+
+impl<const N: usize> LineGeometry<N> {
+    pub fn length(&self) -> f64 {
+        distance(self.start, self.end)
+    }
+
+    /// The unit vector pointing from the start of the line towards its end.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the line has zero length, so that it has no direction.
+    pub fn direction(&self) -> Result<[f64; N], &'static str> {
+        let length = self.length();
+        if length <= SOLVE_TOLERANCE {
+            return Err("Expected line to have non-zero length.");
+        }
+
+        Ok(scale(sub(self.end, self.start), 1.0 / length))
+    }
+
+    pub fn midpoint(&self) -> [f64; N] {
+        self.point_at(0.5)
+    }
+
+    /// The point at `t` along the line, where `t` goes from 0 at the start to 1 at the
+    /// end.
+    pub fn point_at(&self, t: f64) -> [f64; N] {
+        add(self.start, scale(sub(self.end, self.start), t))
+    }
+}
+
+/// An arc of a circle, in `N` dimensional coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArcGeometry<const N: usize> {
+    pub center: [f64; N],
+    pub start: [f64; N],
+    pub end: [f64; N],
+    pub radius: f64,
+    /// The angle of the start point around the center, in degrees.
+    pub start_angle: f64,
+    /// The angle of the end point around the center, in degrees.
+    pub end_angle: f64,
+}
+
+impl<const N: usize> ArcGeometry<N> {
+    /// The counter-clockwise angle from the start to the end of the arc, in degrees.
+    /// This is 360 for a full circle.
+    pub fn sweep(&self) -> f64 {
+        if distance(self.start, self.end) <= SOLVE_TOLERANCE {
+            360.0
+        } else {
+            (self.end_angle - self.start_angle).rem_euclid(360.0)
+        }
+    }
+
+    pub fn length(&self) -> f64 {
+        self.sweep().to_radians() * self.radius
+    }
+}
+
+/// A circle, in `N` dimensional coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CircleGeometry<const N: usize> {
+    pub center: [f64; N],
+    pub radius: f64,
+    /// The normal of the plane that the circle lies in, in world coordinates.
+    pub normal: [f64; 3],
+}
+
+/// A cubic Bezier curve, in `N` dimensional coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CubicGeometry<const N: usize> {
+    /// The start point, start control point, end control point and end point.
+    pub control_points: [[f64; N]; 4],
+}
+
+impl<const N: usize> CubicGeometry<N> {
+    /// The point on the curve at `t`, which goes from 0 at the start to 1 at the end.
+    pub fn point_at(&self, t: f64) -> [f64; N] {
+        let [p0, p1, p2, p3] = self.control_points;
+        let s = 1.0 - t;
+
+        add(
+            add(scale(p0, s * s * s), scale(p1, 3.0 * s * s * t)),
+            add(scale(p2, 3.0 * s * t * t), scale(p3, t * t * t)),
+        )
+    }
+
+    /// The derivative of the curve with respect to `t`.
+    pub fn tangent_at(&self, t: f64) -> [f64; N] {
+        let [p0, p1, p2, p3] = self.control_points;
+        let s = 1.0 - t;
+
+        add(
+            add(
+                scale(sub(p1, p0), 3.0 * s * s),
+                scale(sub(p2, p1), 6.0 * s * t),
+            ),
+            scale(sub(p3, p2), 3.0 * t * t),
+        )
+    }
+
+    /// Split the curve at `t`, into the curves before and after that point.
+    pub fn split(&self, t: f64) -> (CubicGeometry<N>, CubicGeometry<N>) {
+        let [p0, p1, p2, p3] = self.control_points;
+        let lerp = |a: [f64; N], b: [f64; N]| add(a, scale(sub(b, a), t));
+
+        let (p01, p12, p23) = (lerp(p0, p1), lerp(p1, p2), lerp(p2, p3));
+        let (p012, p123) = (lerp(p01, p12), lerp(p12, p23));
+        let mid = lerp(p012, p123);
+
+        (
+            CubicGeometry {
+                control_points: [p0, p01, p012, mid],
+            },
+            CubicGeometry {
+                control_points: [mid, p123, p23, p3],
+            },
+        )
+    }
+}
+
+/// The orthonormal axes of a normal, in world coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Axes {
+    pub u: [f64; 3],
+    pub v: [f64; 3],
+    pub n: [f64; 3],
+}
+
+/// A workplane, in world coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorkplaneGeometry {
+    pub origin: [f64; 3],
+    pub axes: Axes,
+}
+
+impl System {
+    /// Get the coordinates of a point in world coordinates. Points on a workplane are
+    /// placed using the origin and normal of their workplane.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{Normal, Point, Workplane},
+    ///     utils::make_quaternion,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 10.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// let point = sys
+    ///     .sketch(Point::new_on_workplane(g, workplane, [3.0, 4.0]))
+    ///     .expect("point created");
+    /// assert_eq!(sys.point_world_coords(&point), Ok([3.0, 4.0, 10.0]));
+    ///
+    /// let point_3d = sys
+    ///     .sketch(Point::new_in_3d(g, [5.0, 6.0, 7.0]))
+    ///     .expect("point created");
+    /// assert_eq!(sys.point_in_workplane(&point_3d, &workplane), Ok([5.0, 6.0]));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the point, or the workplane it lies on, is not found in the
+    /// system.
+    pub fn point_world_coords(
+        &self,
+        point: &EntityHandle<Point>,
+    ) -> Result<[f64; 3], &'static str> {
+        self.point_coords(point.handle())
+    }
+
+    /// Get the coordinates of a point in a workplane. Points that are not on
+    /// `workplane` are projected onto it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the point or workplane are not found in the system.
+    pub fn point_in_workplane(
+        &self,
+        point: &EntityHandle<Point>,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<[f64; 2], &'static str> {
+        self.point_coords_on(point.handle(), workplane.handle())
+    }
+
+    /// Get the geometry of a line segment, in world coordinates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{LineSegment, Point},
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let start = sys
+    ///     .sketch(Point::new_in_3d(g, [1.0, 2.0, 3.0]))
+    ///     .expect("point created");
+    /// let end = sys
+    ///     .sketch(Point::new_in_3d(g, [4.0, 6.0, 3.0]))
+    ///     .expect("point created");
+    /// let line = sys
+    ///     .sketch(LineSegment::new(g, start, end))
+    ///     .expect("line created");
+    ///
+    /// let geometry = sys.line_geometry(&line).expect("line found");
+    /// assert_eq!(geometry.length(), 5.0);
+    /// assert_eq!(geometry.midpoint(), [2.5, 4.0, 3.0]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the line is not found in the system.
+    pub fn line_geometry(
+        &self,
+        line: &EntityHandle<LineSegment>,
+    ) -> Result<LineGeometry<3>, &'static str> {
+        let [start, end] = self.entity_points(line.handle())?;
+
+        Ok(LineGeometry {
+            start: self.point_coords(start)?,
+            end: self.point_coords(end)?,
+        })
+    }
+
+    /// Get the geometry of a line segment, projected onto a workplane.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the line or workplane are not found in the system.
+    pub fn line_geometry_on(
+        &self,
+        line: &EntityHandle<LineSegment>,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<LineGeometry<2>, &'static str> {
+        let [start, end] = self.entity_points(line.handle())?;
+
+        Ok(LineGeometry {
+            start: self.point_coords_on(start, workplane.handle())?,
+            end: self.point_coords_on(end, workplane.handle())?,
+        })
+    }
+
+    /// Get the geometry of an arc, in world coordinates.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the arc is not found in the system.
+    pub fn arc_geometry(
+        &self,
+        arc: &EntityHandle<ArcOfCircle>,
+    ) -> Result<ArcGeometry<3>, &'static str> {
+        let slvs_arc = self.slvs_entity(arc.handle())?;
+        let on_workplane = self.arc_geometry_on(arc, &EntityHandle::new(slvs_arc.wrkpl))?;
+        let [center, start, end] = [0, 1, 2].map(|i| self.point_coords(slvs_arc.point[i]));
+
+        Ok(ArcGeometry {
+            center: center?,
+            start: start?,
+            end: end?,
+            radius: on_workplane.radius,
+            start_angle: on_workplane.start_angle,
+            end_angle: on_workplane.end_angle,
+        })
+    }
+
+    /// Get the geometry of an arc, in the coordinates of a workplane. Angles are
+    /// measured from the `u` axis of `workplane`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the arc or workplane are not found in the system, or if the
+    /// arc is not parallel to the workplane and facing the same way.
+    pub fn arc_geometry_on(
+        &self,
+        arc: &EntityHandle<ArcOfCircle>,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<ArcGeometry<2>, &'static str> {
+        let slvs_arc = self.slvs_entity(arc.handle())?;
+        self.check_parallel(slvs_arc.normal, workplane.handle(), true)?;
+
+        let [center, start, end] =
+            [0, 1, 2].map(|i| self.point_coords_on(slvs_arc.point[i], workplane.handle()));
+        let (center, start, end) = (center?, start?, end?);
+        let angle = |point: [f64; 2]| {
+            (point[1] - center[1])
+                .atan2(point[0] - center[0])
+                .to_degrees()
+                .rem_euclid(360.0)
+        };
+
+        Ok(ArcGeometry {
+            center,
+            start,
+            end,
+            radius: distance(center, start),
+            start_angle: angle(start),
+            end_angle: angle(end),
+        })
+    }
+
+    /// Get the geometry of a circle, in world coordinates.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the circle is not found in the system.
+    pub fn circle_geometry(
+        &self,
+        circle: &EntityHandle<Circle>,
+    ) -> Result<CircleGeometry<3>, &'static str> {
+        let slvs_circle = self.slvs_entity(circle.handle())?;
+
+        Ok(CircleGeometry {
+            center: self.point_coords(slvs_circle.point[0])?,
+            radius: self.circle_radius(circle.handle())?,
+            normal: quaternion_n(self.normal_quaternion(slvs_circle.normal)?),
+        })
+    }
+
+    /// Get the geometry of a circle, in the coordinates of a workplane.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the circle or workplane are not found in the system, or if
+    /// the circle is not parallel to the workplane.
+    pub fn circle_geometry_on(
+        &self,
+        circle: &EntityHandle<Circle>,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<CircleGeometry<2>, &'static str> {
+        let slvs_circle = self.slvs_entity(circle.handle())?;
+        self.check_parallel(slvs_circle.normal, workplane.handle(), false)?;
+
+        Ok(CircleGeometry {
+            center: self.point_coords_on(slvs_circle.point[0], workplane.handle())?,
+            radius: self.circle_radius(circle.handle())?,
+            normal: quaternion_n(self.normal_quaternion(slvs_circle.normal)?),
+        })
+    }
+
+    /// Get the geometry of a cubic Bezier curve, in world coordinates.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the curve is not found in the system.
+    pub fn cubic_geometry(
+        &self,
+        cubic: &EntityHandle<Cubic>,
+    ) -> Result<CubicGeometry<3>, &'static str> {
+        let slvs_cubic = self.slvs_entity(cubic.handle())?;
+        let control_points = slvs_cubic.point.map(|point| self.point_coords(point));
+
+        Ok(CubicGeometry {
+            control_points: [
+                control_points[0]?,
+                control_points[1]?,
+                control_points[2]?,
+                control_points[3]?,
+            ],
+        })
+    }
+
+    /// Get the geometry of a cubic Bezier curve, projected onto a workplane.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the curve or workplane are not found in the system.
+    pub fn cubic_geometry_on(
+        &self,
+        cubic: &EntityHandle<Cubic>,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<CubicGeometry<2>, &'static str> {
+        let slvs_cubic = self.slvs_entity(cubic.handle())?;
+        let control_points = slvs_cubic
+            .point
+            .map(|point| self.point_coords_on(point, workplane.handle()));
+
+        Ok(CubicGeometry {
+            control_points: [
+                control_points[0]?,
+                control_points[1]?,
+                control_points[2]?,
+                control_points[3]?,
+            ],
+        })
+    }
+
+    /// Get the axes of a normal, in world coordinates.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the normal is not found in the system.
+    pub fn normal_axes(&self, normal: &EntityHandle<Normal>) -> Result<Axes, &'static str> {
+        Ok(quaternion_axes(self.normal_quaternion(normal.handle())?))
+    }
+
+    /// Get the origin and axes of a workplane, in world coordinates.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the workplane is not found in the system.
+    pub fn workplane_geometry(
+        &self,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<WorkplaneGeometry, &'static str> {
+        let (origin, quaternion) = self.workplane_frame(workplane.handle())?;
+
+        Ok(WorkplaneGeometry {
+            origin,
+            axes: quaternion_axes(quaternion),
+        })
+    }
+
+    // The first two points of an entity, such as the ends of a line.
+    fn entity_points(&self, h: Slvs_hEntity) -> Result<[Slvs_hEntity; 2], &'static str> {
+        let slvs_entity = self.slvs_entity(h)?;
+        Ok([slvs_entity.point[0], slvs_entity.point[1]])
+    }
+
+    // Arcs and circles can only be projected onto parallel workplanes, where they keep
+    // their shape. Arcs also need the workplane to face the same way, or they would be
+    // swept in the opposite direction.
+    fn check_parallel(
+        &self,
+        normal: Slvs_hEntity,
+        workplane: Slvs_hEntity,
+        same_facing: bool,
+    ) -> Result<(), &'static str> {
+        let curve_normal = Vector3D::from(quaternion_n(self.normal_quaternion(normal)?));
+        let (_, quaternion) = self.workplane_frame(workplane)?;
+        let workplane_normal = Vector3D::from(quaternion_n(quaternion));
+
+        if curve_normal.cross(workplane_normal).length() > SOLVE_TOLERANCE {
+            Err("Curve is not parallel to the workplane.")
+        } else if same_facing && curve_normal.dot(workplane_normal) < 0.0 {
+            Err("Arc faces away from the workplane.")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn quaternion_axes(quaternion: [f64; 4]) -> Axes {
+    Axes {
+        u: quaternion_u(quaternion),
+        v: quaternion_v(quaternion),
+        n: quaternion_n(quaternion),
+    }
+}
+
+pub(crate) fn add<const N: usize>(a: [f64; N], b: [f64; N]) -> [f64; N] {
+    std::array::from_fn(|i| a[i] + b[i])
+}
+
+pub(crate) fn sub<const N: usize>(a: [f64; N], b: [f64; N]) -> [f64; N] {
+    std::array::from_fn(|i| a[i] - b[i])
+}
+
+pub(crate) fn scale<const N: usize>(a: [f64; N], factor: f64) -> [f64; N] {
+    a.map(|v| v * factor)
+}
+
+// Whether a point is inside a polygon, by counting the edges crossed by a ray from it.
+pub(crate) fn in_polygon(polygon: &[[f64; 2]], point: [f64; 2]) -> bool {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .filter(|(start, end)| {
+            (start[1] > point[1]) != (end[1] > point[1])
+                && point[0]
+                    < start[0] + (point[1] - start[1]) / (end[1] - start[1]) * (end[0] - start[0])
+        })
+        .count()
+        % 2
+        == 1
+}
+
+// The area of a polygon, which is positive when its corners go counter-clockwise.
+pub(crate) fn signed_area(polygon: &[[f64; 2]]) -> f64 {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+        .sum::<f64>()
+        / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity::{ArcOfCircle, Circle, Cubic, Distance, LineSegment, Normal, Point, Workplane},
+        len_within_tolerance,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn tilted_workplane() {
+        let mut sys = System::new();
+        let g = sys.add_group();
+
+        // A workplane through (0, 0, 5), with u along y and v along -x.
+        let origin = sys
+            .sketch(Point::new_in_3d(g, [0.0, 0.0, 5.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                g,
+                make_quaternion([0.0, 1.0, 0.0], [-1.0, 0.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(g, origin, normal))
+            .expect("workplane created");
+
+        let workplane_geometry = sys.workplane_geometry(&workplane).expect("found");
+        assert_eq!(workplane_geometry.origin, [0.0, 0.0, 5.0]);
+        len_within_tolerance!(workplane_geometry.axes.n[2], 1.0);
+
+        let center = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 0.0]))
+            .expect("point created");
+        let start = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 2.0]))
+            .expect("point created");
+        let end = sys
+            .sketch(Point::new_on_workplane(g, workplane, [2.0, 0.0]))
+            .expect("point created");
+        let arc = sys
+            .sketch(ArcOfCircle::new(g, workplane, center, start, end))
+            .expect("arc created");
+
+        let arc_on = sys.arc_geometry_on(&arc, &workplane).expect("found");
+        len_within_tolerance!(arc_on.radius, 2.0);
+        len_within_tolerance!(arc_on.start_angle, 90.0);
+        len_within_tolerance!(arc_on.sweep(), 270.0);
+        len_within_tolerance!(arc_on.length(), 3.0 * std::f64::consts::PI);
+
+        // In world coordinates, u is along y, so the end of the arc is at y = 2.
+        let arc_world = sys.arc_geometry(&arc).expect("found");
+        len_within_tolerance!(arc_world.end[1], 2.0);
+        len_within_tolerance!(arc_world.end[2], 5.0);
+        len_within_tolerance!(arc_world.sweep(), 270.0);
+
+        let line = sys
+            .sketch(LineSegment::new(g, start, end))
+            .expect("line created");
+        let line_on = sys.line_geometry_on(&line, &workplane).expect("found");
+        len_within_tolerance!(line_on.length(), 8.0_f64.sqrt());
+        assert_eq!(line_on.midpoint(), [1.0, 1.0]);
+
+        let control_a = sys
+            .sketch(Point::new_on_workplane(g, workplane, [1.0, 2.0]))
+            .expect("point created");
+        let control_b = sys
+            .sketch(Point::new_on_workplane(g, workplane, [2.0, 1.0]))
+            .expect("point created");
+        let cubic = sys
+            .sketch(Cubic::new(g, start, control_a, control_b, end))
+            .expect("cubic created");
+        let cubic_on = sys.cubic_geometry_on(&cubic, &workplane).expect("found");
+        assert_eq!(cubic_on.point_at(0.0), [0.0, 2.0]);
+        assert_eq!(cubic_on.point_at(0.5), [1.375, 1.375]);
+        assert_eq!(cubic_on.tangent_at(0.0), [3.0, 0.0]);
+
+        let point_line = sys
+            .sketch(LineSegment::new(g, start, start))
+            .expect("line created");
+        let point_line_on = sys
+            .line_geometry_on(&point_line, &workplane)
+            .expect("found");
+        assert!(point_line_on.direction().is_err());
+    }
+
+    #[test]
+    fn rotated_and_flipped_workplanes() {
+        let mut sys = System::new();
+        let g = sys.add_group();
+
+        let origin = sys
+            .sketch(Point::new_in_3d(g, [0.0, 0.0, 5.0]))
+            .expect("origin created");
+        let make_workplane = |sys: &mut System, u, v| {
+            let normal = sys
+                .sketch(Normal::new_in_3d(g, make_quaternion(u, v)))
+                .expect("normal created");
+            sys.sketch(Workplane::new(g, origin, normal))
+                .expect("workplane created")
+        };
+        // The first two face along z, rotated by 90 degrees, and the last faces along -z.
+        let workplane = make_workplane(&mut sys, [0.0, 1.0, 0.0], [-1.0, 0.0, 0.0]);
+        let rotated = make_workplane(&mut sys, [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+        let flipped = make_workplane(&mut sys, [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]);
+
+        let center = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 0.0]))
+            .expect("point created");
+        let start = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 2.0]))
+            .expect("point created");
+        let end = sys
+            .sketch(Point::new_on_workplane(g, workplane, [2.0, 0.0]))
+            .expect("point created");
+        let arc = sys
+            .sketch(ArcOfCircle::new(g, workplane, center, start, end))
+            .expect("arc created");
+
+        let arc_rotated = sys.arc_geometry_on(&arc, &rotated).expect("found");
+        len_within_tolerance!(arc_rotated.start_angle, 180.0);
+        len_within_tolerance!(arc_rotated.end_angle, 90.0);
+        len_within_tolerance!(arc_rotated.sweep(), 270.0);
+
+        // Seen from behind, the arc would run clockwise.
+        assert_eq!(
+            sys.arc_geometry_on(&arc, &flipped),
+            Err("Arc faces away from the workplane.")
+        );
+
+        // Circles have no direction, so they can be seen from either side.
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let radius = sys.sketch(Distance::new(g, 2.0)).expect("distance created");
+        let circle = sys
+            .sketch(Circle::new(g, normal, center, radius))
+            .expect("circle created");
+        let circle_flipped = sys.circle_geometry_on(&circle, &flipped).expect("found");
+        len_within_tolerance!(circle_flipped.radius, 2.0);
+    }
+}
@@ -0,0 +1,541 @@
+/*!
+Inequality constraints, which bound the value of a dimensional constraint.
+
+A bounded constraint is added with [`System::constrain_bounded`]. While solving, it is
+ignored as long as its value is within the [`Bound`]. If the solution violates the
+bound, the constraint is activated with its value clamped to the violated limit, and
+the group is solved again. An active constraint is released again when the solution
+stays within its bound without it, or when the group fails to solve with it but solves
+without it. This repeats until every bound is satisfied, or until no set of active
+constraints is left to try, in which case the solve fails. Either way, the values of
+the constraints are then restored to the values they were added with.
+
+The status of every bound in a solved group is reported by [`System::bound_statuses`].
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::Slvs_hConstraint,
+    constraint::{AsConstraintData, AsConstraintHandle, ConstraintHandle},
+    element::AsHandle,
+    group::Group,
+    system::{SolveResult, SOLVE_TOLERANCE},
+    tolerance::is_dimensional,
+    System,
+};
+
+/// Limits on the value of a dimensional constraint.
+///
+/// Values follow the same conventions as the constraint itself. For example, the
+/// distance set by [`PtLineDistance`][crate::constraint::PtLineDistance] is signed
+/// when it is applied within a workplane.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Bound {
+    AtLeast(f64),
+    AtMost(f64),
+    /// The value is between the first and second values, inclusive.
+    Between(f64, f64),
+}
+
+impl Bound {
+    // The limit that is violated by `value`, if any.
+    fn violated_limit(&self, value: f64) -> Option<f64> {
+        let (min, max) = match *self {
+            Bound::AtLeast(min) => (min, f64::INFINITY),
+            Bound::AtMost(max) => (f64::NEG_INFINITY, max),
+            Bound::Between(min, max) => (min, max),
+        };
+
+        if value < min - SOLVE_TOLERANCE {
+            Some(min)
+        } else if value > max + SOLVE_TOLERANCE {
+            Some(max)
+        } else {
+            None
+        }
+    }
+
+    // Whether `value` is outside the bound.
+    pub(crate) fn is_violated(&self, value: f64) -> bool {
+        self.violated_limit(value).is_some()
+    }
+
+    // The limit nearest to `value`.
+    pub(crate) fn nearest_limit(&self, value: f64) -> f64 {
+        match *self {
+            Bound::AtLeast(min) => min,
+            Bound::AtMost(max) => max,
+            Bound::Between(min, max) if (value - min).abs() <= (value - max).abs() => min,
+            Bound::Between(_, max) => max,
+        }
+    }
+
+    // Whether `value` is clamped to one of the limits.
+    pub(crate) fn is_at_limit(&self, value: f64) -> bool {
+        let limits = match *self {
+            Bound::AtLeast(min) => [min, min],
+            Bound::AtMost(max) => [max, max],
+            Bound::Between(min, max) => [min, max],
+        };
+
+        limits
+            .iter()
+            .any(|limit| (value - limit).abs() <= SOLVE_TOLERANCE)
+    }
+}
+
+/// The state of a bounded constraint, found by [`System::bound_statuses`].
+#[derive(Debug)]
+pub struct BoundStatus {
+    pub constraint: Box<dyn AsConstraintHandle>,
+    pub bound: Bound,
+    /// The current value of the constraint.
+    pub value: f64,
+    /// Whether the value is at one of the limits, where the constraint is holding it.
+    pub active: bool,
+}
+
+impl System {
+    /// Add a dimensional constraint to the system, with its value limited by `bound`
+    /// rather than fixed.
+    ///
+    /// The value passed in `constraint_data` is not used.
+    ///
+    /// # Arguments
+    ///
+    /// * `constraint_data` - A dimensional constraint, such as [`PtPtDistance`][crate::constraint::PtPtDistance]
+    ///   or [`Angle`][crate::constraint::Angle].
+    /// * `bound` - Limits on the value of the constraint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     constraint::PtPtDistance,
+    ///     entity::Point,
+    ///     inequality::Bound,
+    ///     system::SolveResult,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    ///
+    /// let p1 = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("p1 created");
+    /// let p2 = sys
+    ///     .sketch(Point::new_in_3d(g, [2.0, 0.0, 0.0]))
+    ///     .expect("p2 created");
+    ///
+    /// // p1 and p2 are at least 5 units apart.
+    /// sys.constrain_bounded(PtPtDistance::new(g, p1, p2, 0.0, None), Bound::AtLeast(5.0))
+    ///     .expect("bounded distance constraint added");
+    ///
+    /// if let SolveResult::Ok { .. } = sys.solve(&g) {
+    ///     let bounds = sys.bound_statuses(&g);
+    ///     assert!(bounds[0].active);
+    ///     assert!((bounds[0].value - 5.0).abs() < 1e-6);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the constraint is not dimensional, if the bound is empty, or
+    /// if any of the entities referenced in `constraint_data` are not found in the system.
+    pub fn constrain_bounded<C: AsConstraintData>(
+        &mut self,
+        constraint_data: C,
+        bound: Bound,
+    ) -> Result<ConstraintHandle<C>, &'static str> {
+        if !is_dimensional(constraint_data.slvs_type()) {
+            return Err("Bounds can only be applied to dimensional constraints.");
+        }
+        if let Bound::Between(min, max) = bound {
+            if min > max {
+                return Err("Lower limit of bound is greater than its upper limit.");
+            }
+        }
+
+        let constraint_handle = self.constrain(constraint_data)?;
+        self.bounds.insert(constraint_handle.handle(), bound);
+
+        Ok(constraint_handle)
+    }
+
+    /// Get the bound on a constraint, if it was added with [`System::constrain_bounded`].
+    pub fn bound<C: AsConstraintData>(
+        &self,
+        constraint_handle: &ConstraintHandle<C>,
+    ) -> Option<Bound> {
+        self.bounds.get(&constraint_handle.handle()).copied()
+    }
+
+    /// The status of each bounded constraint in a group, at the current values of its
+    /// parameters.
+    ///
+    /// After [`System::solve`], the active constraints are those that had to be clamped
+    /// to one of their limits.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to report the bounded constraints of.
+    pub fn bound_statuses(&self, group: &Group) -> Vec<BoundStatus> {
+        self.bounds
+            .iter()
+            .filter_map(|(&h, &bound)| {
+                let slvs_constraint = *self.slvs_constraint(h).ok()?;
+                if slvs_constraint.group != group.handle() {
+                    return None;
+                }
+                let value = self.constraint_value(&slvs_constraint).unwrap_or(f64::NAN);
+
+                Some(BoundStatus {
+                    constraint: self.boxed_constraint_handle(slvs_constraint),
+                    bound,
+                    value,
+                    active: bound.is_at_limit(value),
+                })
+            })
+            .collect()
+    }
+
+    // Solve using an active-set loop over the bounded constraints in the group.
+    pub(crate) fn solve_with_bounds(&mut self, group: &Group) -> SolveResult {
+        let bounded: Vec<(Slvs_hConstraint, Bound)> = self
+            .bounds
+            .iter()
+            .filter(|(&h, _)| {
+                self.slvs_constraint(h)
+                    .is_ok_and(|slvs_constraint| slvs_constraint.group == group.handle())
+            })
+            .map(|(&h, &bound)| (h, bound))
+            .collect();
+        let values: Vec<f64> = bounded
+            .iter()
+            .map(|&(h, _)| self.slvs_constraint(h).unwrap().valA)
+            .collect();
+        let mut active: Vec<Slvs_hConstraint> = Vec::new();
+        let mut visited: Vec<Vec<Slvs_hConstraint>> = Vec::new();
+
+        loop {
+            let mut sorted = active.clone();
+            sorted.sort_unstable();
+            visited.push(sorted);
+
+            let inactive: Vec<Slvs_hConstraint> = bounded
+                .iter()
+                .map(|&(h, _)| h)
+                .filter(|h| !active.contains(h))
+                .collect();
+            let result = self.solve_soft(group, &inactive);
+            let solved = matches!(result, SolveResult::Ok { .. });
+
+            let mut activated = false;
+            if solved {
+                for &(h, bound) in &bounded {
+                    if active.contains(&h) {
+                        continue;
+                    }
+                    let slvs_constraint = self.slvs_constraint(h).unwrap();
+                    let value = self.constraint_value(slvs_constraint);
+
+                    if let Some(limit) = value.ok().and_then(|value| bound.violated_limit(value)) {
+                        self.mut_slvs_constraint(h).unwrap().valA = limit;
+                        active.push(h);
+                        activated = true;
+                    }
+                }
+            }
+
+            let released =
+                !activated && self.release_bound(group, &bounded, &mut active, &visited, solved);
+
+            if !activated && !released {
+                for (&(h, _), &value) in bounded.iter().zip(&values) {
+                    self.mut_slvs_constraint(h).unwrap().valA = value;
+                }
+                return result;
+            }
+        }
+    }
+
+    // Release the first active bound that is not needed. After a successful solve, that
+    // is a bound that holds without being clamped, because the solution stays within it
+    // when it is left out. After a failed solve, it is a bound that the group solves
+    // without. Sets of active bounds that were already solved are not returned to, so
+    // the loop ends. The parameters are restored if no bound is released.
+    fn release_bound(
+        &mut self,
+        group: &Group,
+        bounded: &[(Slvs_hConstraint, Bound)],
+        active: &mut Vec<Slvs_hConstraint>,
+        visited: &[Vec<Slvs_hConstraint>],
+        solved: bool,
+    ) -> bool {
+        let params = self.params.list.clone();
+
+        for (i, &h) in active.iter().enumerate() {
+            let mut remaining = active.clone();
+            remaining.remove(i);
+            remaining.sort_unstable();
+            if visited.contains(&remaining) {
+                continue;
+            }
+
+            let inactive: Vec<Slvs_hConstraint> = bounded
+                .iter()
+                .map(|&(h, _)| h)
+                .filter(|h| !remaining.contains(h))
+                .collect();
+            if let SolveResult::Ok { .. } = self.solve_soft(group, &inactive) {
+                let (_, bound) = bounded
+                    .iter()
+                    .find(|(bounded_h, _)| *bounded_h == h)
+                    .unwrap();
+                let value = self.constraint_value(self.slvs_constraint(h).unwrap());
+
+                if !solved || value.is_ok_and(|value| bound.violated_limit(value).is_none()) {
+                    active.remove(i);
+                    return true;
+                }
+            }
+            self.params.list = params.clone();
+        }
+
+        false
+    }
+
+    // Solve with some of the constraints in the system left out.
+    pub(crate) fn solve_excluding(
+        &mut self,
+        group: &Group,
+        excluded: &[Slvs_hConstraint],
+    ) -> SolveResult {
+        if excluded.is_empty() {
+            return self.solve_slvs(group);
+        }
+
+        let constraints = self.constraints.list.clone();
+        self.constraints
+            .list
+            .retain(|slvs_constraint| !excluded.contains(&slvs_constraint.h));
+        let result = self.solve_slvs(group);
+        self.constraints.list = constraints;
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constraint::{Angle, Horizontal, PtPtDistance},
+        entity::{LineSegment, Normal, Point, Workplane},
+        inequality::Bound,
+        system::SolveResult,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn angle_range() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+        let center = sys
+            .sketch(Point::new_on_workplane(workplane_g, workplane, [0.0, 0.0]))
+            .expect("point created");
+        let fixed_end = sys
+            .sketch(Point::new_on_workplane(workplane_g, workplane, [10.0, 0.0]))
+            .expect("point created");
+        let fixed_line = sys
+            .sketch(LineSegment::new(workplane_g, center, fixed_end))
+            .expect("line created");
+
+        // A line rotating about the origin, at 10 degrees to the fixed line.
+        let g = sys.add_group();
+        let free_end = sys
+            .sketch(Point::new_on_workplane(
+                g,
+                workplane,
+                [10.0_f64.to_radians().cos(), 10.0_f64.to_radians().sin()],
+            ))
+            .expect("point created");
+        let free_line = sys
+            .sketch(LineSegment::new(g, center, free_end))
+            .expect("line created");
+        sys.constrain(PtPtDistance::new(g, center, free_end, 1.0, Some(workplane)))
+            .expect("constraint added");
+        let angle = sys
+            .constrain_bounded(
+                Angle::new(g, fixed_line, free_line, 0.0, Some(workplane), false),
+                Bound::Between(30.0, 60.0),
+            )
+            .expect("constraint added");
+        assert_eq!(sys.bound(&angle), Some(Bound::Between(30.0, 60.0)));
+
+        match sys.solve(&g) {
+            SolveResult::Ok { .. } => {
+                let bounds = sys.bound_statuses(&g);
+                assert_eq!(bounds.len(), 1);
+                assert!(bounds[0].active);
+                assert!((bounds[0].value - 30.0).abs() < 1e-6);
+            }
+            SolveResult::Fail { .. } => panic!("solve failed"),
+        }
+
+        // Already within the bound, so the constraint is left inactive.
+        sys.update_entity(&free_end, |point| {
+            if let Point::OnWorkplane { coords, .. } = point {
+                *coords = [45.0_f64.to_radians().cos(), 45.0_f64.to_radians().sin()];
+            }
+        })
+        .expect("point updated");
+
+        match sys.solve(&g) {
+            SolveResult::Ok { .. } => {
+                let bounds = sys.bound_statuses(&g);
+                assert!(!bounds[0].active);
+                assert!((bounds[0].value - 45.0).abs() < 1e-6);
+            }
+            SolveResult::Fail { .. } => panic!("solve failed"),
+        }
+    }
+
+    #[test]
+    fn release_bound() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+        let [start, stop] = [[0.0, 0.0], [7.0, 0.0]].map(|coords| {
+            sys.sketch(Point::new_on_workplane(workplane_g, workplane, coords))
+                .expect("point created")
+        });
+
+        // A point sliding along a horizontal line from the start.
+        let g = sys.add_group();
+        let slider = sys
+            .sketch(Point::new_on_workplane(g, workplane, [10.0, 0.0]))
+            .expect("point created");
+        let line = sys
+            .sketch(LineSegment::new(g, start, slider))
+            .expect("line created");
+        sys.constrain(Horizontal::from_line(g, workplane, line))
+            .expect("constraint added");
+
+        // Clamping the reach to 5 brings the slider too close to the stop, and clamping
+        // the distance to the stop then moves it back within reach.
+        let reach = sys
+            .constrain_bounded(
+                PtPtDistance::new(g, start, slider, 0.0, Some(workplane)),
+                Bound::AtMost(5.0),
+            )
+            .expect("constraint added");
+        sys.constrain_bounded(
+            PtPtDistance::new(g, stop, slider, 0.0, Some(workplane)),
+            Bound::AtLeast(2.5),
+        )
+        .expect("constraint added");
+
+        match sys.solve(&g) {
+            SolveResult::Ok { .. } => {
+                let bounds = sys.bound_statuses(&g);
+                assert!(!bounds[0].active);
+                assert!((bounds[0].value - 4.5).abs() < 1e-6);
+                assert!(bounds[1].active);
+                assert!((bounds[1].value - 2.5).abs() < 1e-6);
+            }
+            SolveResult::Fail { .. } => panic!("solve failed"),
+        }
+        assert_eq!(
+            sys.constraint_data(&reach)
+                .expect("constraint found")
+                .distance,
+            0.0
+        );
+    }
+
+    #[test]
+    fn infeasible_bounds() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+        let start = sys
+            .sketch(Point::new_on_workplane(workplane_g, workplane, [0.0, 0.0]))
+            .expect("point created");
+
+        let g = sys.add_group();
+        let slider = sys
+            .sketch(Point::new_on_workplane(g, workplane, [10.0, 0.0]))
+            .expect("point created");
+        let line = sys
+            .sketch(LineSegment::new(g, start, slider))
+            .expect("line created");
+        sys.constrain(Horizontal::from_line(g, workplane, line))
+            .expect("constraint added");
+
+        // The slider cannot be both within 4 and beyond 6 of the start. Each bound is
+        // activated in turn, and releasing either one only violates it again.
+        let near = sys
+            .constrain_bounded(
+                PtPtDistance::new(g, start, slider, 0.0, Some(workplane)),
+                Bound::AtMost(4.0),
+            )
+            .expect("constraint added");
+        let far = sys
+            .constrain_bounded(
+                PtPtDistance::new(g, start, slider, 0.0, Some(workplane)),
+                Bound::AtLeast(6.0),
+            )
+            .expect("constraint added");
+
+        assert!(matches!(sys.solve(&g), SolveResult::Fail { .. }));
+        for constraint in [near, far] {
+            assert_eq!(
+                sys.constraint_data(&constraint)
+                    .expect("constraint found")
+                    .distance,
+                0.0
+            );
+        }
+        assert_eq!(sys.bound_statuses(&g).len(), 2);
+    }
+}
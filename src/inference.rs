@@ -0,0 +1,524 @@
+/*!
+Inference of constraints from approximate geometry.
+
+When a sketch is drawn by hand, lines that are meant to be horizontal are only close
+to horizontal, and endpoints that are meant to meet are only close together.
+[`System::infer_constraints`] scans a group for these near misses, and returns each one
+as a [`Candidate`] with a confidence score. Nothing is added to the system, so the
+caller can choose which candidates to keep and apply them with
+[`System::constrain_inferred`].
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::{
+        Slvs_hEntity, SLVS_C_ARC_LINE_TANGENT, SLVS_C_EQUAL_LENGTH_LINES, SLVS_C_HORIZONTAL,
+        SLVS_C_POINTS_COINCIDENT, SLVS_C_VERTICAL, SLVS_E_ARC_OF_CIRCLE, SLVS_E_LINE_SEGMENT,
+        SLVS_E_POINT_IN_2D, SLVS_E_POINT_IN_3D, SLVS_FREE_IN_3D,
+    },
+    constraint::{
+        ArcLineTangent, AsConstraintHandle, EqualLengthLines, Horizontal, PointsCoincident,
+        Vertical,
+    },
+    element::AsHandle,
+    entity::{EntityHandle, Workplane},
+    group::Group,
+    utils::distance,
+    System,
+};
+
+/// How far the geometry may be from satisfying a constraint, for that constraint to
+/// be inferred.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tolerances {
+    /// The largest distance between points that are made coincident.
+    pub distance: f64,
+    /// The largest angle, in degrees, that a line can be from horizontal, vertical or
+    /// tangent.
+    pub angle: f64,
+    /// The largest difference in the lengths of lines that are made equal, as a
+    /// fraction of the longer line.
+    pub length_ratio: f64,
+}
+
+impl Default for Tolerances {
+    fn default() -> Self {
+        Self {
+            distance: 0.1,
+            angle: 3.0,
+            length_ratio: 0.02,
+        }
+    }
+}
+
+/// A constraint that can be inferred from the geometry.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Inferred {
+    Horizontal(Horizontal),
+    Vertical(Vertical),
+    PointsCoincident(PointsCoincident),
+    ArcLineTangent(ArcLineTangent),
+    EqualLengthLines(EqualLengthLines),
+}
+
+/// A constraint found by [`System::infer_constraints`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    pub constraint: Inferred,
+    /// How close the geometry is to satisfying the constraint, from `0.0` at the edge
+    /// of the tolerance to `1.0` when it is already satisfied.
+    pub confidence: f64,
+}
+
+impl System {
+    /// Find constraints that the geometry in a group almost satisfies.
+    ///
+    /// Constraints that are already in the system are not suggested again. The
+    /// candidates are sorted from the highest confidence to the lowest.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to be scanned. Points in other groups are also considered
+    ///   when looking for coincident points.
+    /// * `tolerances` - How close the geometry must be to satisfying each constraint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{LineSegment, Normal, Point, Workplane},
+    ///     inference::{Inferred, Tolerances},
+    ///     utils::make_quaternion,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g1 = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g1, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g1,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g1, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// // A line that is nearly horizontal.
+    /// let g2 = sys.add_group();
+    /// let start = sys
+    ///     .sketch(Point::new_on_workplane(g2, workplane, [1.0, 1.0]))
+    ///     .expect("point created");
+    /// let end = sys
+    ///     .sketch(Point::new_on_workplane(g2, workplane, [11.0, 1.1]))
+    ///     .expect("point created");
+    /// sys.sketch(LineSegment::new(g2, start, end))
+    ///     .expect("line created");
+    ///
+    /// let candidates = sys
+    ///     .infer_constraints(&g2, &Tolerances::default())
+    ///     .expect("constraints inferred");
+    /// assert!(matches!(candidates[0].constraint, Inferred::Horizontal(_)));
+    ///
+    /// sys.constrain_inferred(&candidates[0].constraint)
+    ///     .expect("constraint added");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the geometry of an entity cannot be measured.
+    pub fn infer_constraints(
+        &self,
+        group: &Group,
+        tolerances: &Tolerances,
+    ) -> Result<Vec<Candidate>, &'static str> {
+        let mut candidates = Vec::new();
+
+        let in_group = |type_: u32| -> Vec<Slvs_hEntity> {
+            self.entities
+                .list
+                .iter()
+                .filter(|slvs_entity| {
+                    slvs_entity.group == group.handle() && type_ == slvs_entity.type_ as _
+                })
+                .map(|slvs_entity| slvs_entity.h)
+                .collect()
+        };
+        let lines = in_group(SLVS_E_LINE_SEGMENT);
+        let arcs = in_group(SLVS_E_ARC_OF_CIRCLE);
+
+        // Horizontal and vertical lines
+        for &line in &lines {
+            let Some(workplane) = self.common_workplane(&self.slvs_entity(line)?.point[..2])?
+            else {
+                continue;
+            };
+            let (start, end) = self.line_on(line, workplane)?;
+            // A line shorter than this has no meaningful direction.
+            if distance(start, end) <= tolerances.distance {
+                continue;
+            }
+            let angle = (end[1] - start[1])
+                .atan2(end[0] - start[0])
+                .to_degrees()
+                .rem_euclid(180.0);
+
+            let from_horizontal = angle.min(180.0 - angle);
+            if from_horizontal <= tolerances.angle
+                && !self.has_constraint(SLVS_C_HORIZONTAL, &[line], false)
+            {
+                candidates.push(Candidate {
+                    constraint: Inferred::Horizontal(Horizontal::from_line(
+                        *group,
+                        workplane,
+                        EntityHandle::new(line),
+                    )),
+                    confidence: confidence(from_horizontal, tolerances.angle),
+                });
+            }
+
+            let from_vertical = (angle - 90.0).abs();
+            if from_vertical <= tolerances.angle
+                && !self.has_constraint(SLVS_C_VERTICAL, &[line], false)
+            {
+                candidates.push(Candidate {
+                    constraint: Inferred::Vertical(Vertical::from_line(
+                        *group,
+                        workplane,
+                        EntityHandle::new(line),
+                    )),
+                    confidence: confidence(from_vertical, tolerances.angle),
+                });
+            }
+        }
+
+        // Coincident points
+        let points: Vec<(Slvs_hEntity, bool)> = self
+            .entities
+            .list
+            .iter()
+            .filter(|slvs_entity| {
+                SLVS_E_POINT_IN_2D == slvs_entity.type_ as _
+                    || SLVS_E_POINT_IN_3D == slvs_entity.type_ as _
+            })
+            .map(|slvs_entity| (slvs_entity.h, slvs_entity.group == group.handle()))
+            .collect();
+        for (i, &(point_a, a_in_group)) in points.iter().enumerate() {
+            for &(point_b, b_in_group) in &points[i + 1..] {
+                if !(a_in_group || b_in_group)
+                    || self.has_constraint(SLVS_C_POINTS_COINCIDENT, &[point_a, point_b], false)
+                {
+                    continue;
+                }
+
+                let workplane = self.common_workplane(&[point_a, point_b])?;
+                let distance = self.pt_pt_distance(
+                    point_a,
+                    point_b,
+                    workplane.map_or(SLVS_FREE_IN_3D, |workplane| workplane.handle()),
+                )?;
+                if distance <= tolerances.distance {
+                    candidates.push(Candidate {
+                        constraint: Inferred::PointsCoincident(PointsCoincident::new(
+                            *group,
+                            EntityHandle::new(point_a),
+                            EntityHandle::new(point_b),
+                            workplane,
+                        )),
+                        confidence: confidence(distance, tolerances.distance),
+                    });
+                }
+            }
+        }
+
+        // Arcs tangent to lines at a shared endpoint
+        for &arc in &arcs {
+            let slvs_arc = *self.slvs_entity(arc)?;
+            let workplane = EntityHandle::new(slvs_arc.wrkpl);
+            let center = self.point_coords_on(slvs_arc.point[0], slvs_arc.wrkpl)?;
+
+            for &line in &lines {
+                let line_points = self.slvs_entity(line)?.point;
+                if self.common_workplane(&line_points[..2])? != Some(workplane) {
+                    continue;
+                }
+                let (start, end) = self.line_on(line, workplane)?;
+                if distance(start, end) <= tolerances.distance {
+                    continue;
+                }
+
+                for (to_end, arc_point) in [(false, slvs_arc.point[1]), (true, slvs_arc.point[2])] {
+                    if self.has_constraint(SLVS_C_ARC_LINE_TANGENT, &[arc, line], to_end) {
+                        continue;
+                    }
+                    let arc_point_coords = self.point_coords_on(arc_point, slvs_arc.wrkpl)?;
+                    let touching = line_points[..2].iter().any(|&line_point| {
+                        line_point == arc_point
+                            || self
+                                .pt_pt_distance(line_point, arc_point, slvs_arc.wrkpl)
+                                .is_ok_and(|distance| distance <= tolerances.distance)
+                    });
+                    if !touching {
+                        continue;
+                    }
+
+                    let radius = [
+                        arc_point_coords[0] - center[0],
+                        arc_point_coords[1] - center[1],
+                    ];
+                    let direction = [end[0] - start[0], end[1] - start[1]];
+                    let cos = (radius[0] * direction[0] + radius[1] * direction[1])
+                        / (radius[0].hypot(radius[1]) * direction[0].hypot(direction[1]));
+                    let from_tangent = cos.abs().clamp(0.0, 1.0).asin().to_degrees();
+
+                    if from_tangent <= tolerances.angle {
+                        candidates.push(Candidate {
+                            constraint: Inferred::ArcLineTangent(ArcLineTangent::new(
+                                *group,
+                                workplane,
+                                EntityHandle::new(arc),
+                                EntityHandle::new(line),
+                                to_end,
+                            )),
+                            confidence: confidence(from_tangent, tolerances.angle),
+                        });
+                    }
+                }
+            }
+        }
+
+        // Lines of equal length
+        for (i, &line_a) in lines.iter().enumerate() {
+            for &line_b in &lines[i + 1..] {
+                if self.has_constraint(SLVS_C_EQUAL_LENGTH_LINES, &[line_a, line_b], false) {
+                    continue;
+                }
+
+                let points = [0, 1].map(|j| {
+                    [line_a, line_b].map(|line| self.slvs_entity(line).map(|l| l.point[j]))
+                });
+                let points = points
+                    .into_iter()
+                    .flatten()
+                    .collect::<Result<Vec<Slvs_hEntity>, &'static str>>()?;
+                let workplane = self.common_workplane(&points)?;
+                let workplane_h = workplane.map_or(SLVS_FREE_IN_3D, |workplane| workplane.handle());
+
+                let length_a = self.line_length(line_a, workplane_h)?;
+                let length_b = self.line_length(line_b, workplane_h)?;
+                let difference = (length_a - length_b).abs() / length_a.max(length_b);
+                if difference <= tolerances.length_ratio {
+                    candidates.push(Candidate {
+                        constraint: Inferred::EqualLengthLines(EqualLengthLines::new(
+                            *group,
+                            EntityHandle::new(line_a),
+                            EntityHandle::new(line_b),
+                            workplane,
+                        )),
+                        confidence: confidence(difference, tolerances.length_ratio),
+                    });
+                }
+            }
+        }
+
+        candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        Ok(candidates)
+    }
+
+    /// Add an inferred constraint to the system.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the entities referenced by the constraint are not
+    /// found in the system.
+    pub fn constrain_inferred(
+        &mut self,
+        inferred: &Inferred,
+    ) -> Result<Box<dyn AsConstraintHandle>, &'static str> {
+        let h = match *inferred {
+            Inferred::Horizontal(data) => self.constrain(data)?.handle(),
+            Inferred::Vertical(data) => self.constrain(data)?.handle(),
+            Inferred::PointsCoincident(data) => self.constrain(data)?.handle(),
+            Inferred::ArcLineTangent(data) => self.constrain(data)?.handle(),
+            Inferred::EqualLengthLines(data) => self.constrain(data)?.handle(),
+        };
+
+        Ok(self.boxed_constraint_handle(*self.slvs_constraint(h)?))
+    }
+
+    // Whether there is already a constraint of this type between these entities, with
+    // the same `other` flag (which picks the end of the arc for a tangent).
+    fn has_constraint(&self, type_: u32, entities: &[Slvs_hEntity], other: bool) -> bool {
+        self.constraints.list.iter().any(|slvs_constraint| {
+            if type_ != slvs_constraint.type_ as _ || other != (slvs_constraint.other != 0) {
+                return false;
+            }
+            let referenced = [
+                slvs_constraint.ptA,
+                slvs_constraint.ptB,
+                slvs_constraint.entityA,
+                slvs_constraint.entityB,
+            ];
+            entities.iter().all(|h| referenced.contains(h))
+        })
+    }
+
+    // The workplane shared by all of the points, if they are all on the same one.
+    fn common_workplane(
+        &self,
+        points: &[Slvs_hEntity],
+    ) -> Result<Option<EntityHandle<Workplane>>, &'static str> {
+        let mut workplane = None;
+        for &point in points {
+            let slvs_point = self.slvs_entity(point)?;
+            if SLVS_E_POINT_IN_2D != slvs_point.type_ as _
+                || workplane.is_some_and(|workplane| workplane != slvs_point.wrkpl)
+            {
+                return Ok(None);
+            }
+            workplane = Some(slvs_point.wrkpl);
+        }
+
+        Ok(workplane.map(EntityHandle::new))
+    }
+
+    fn line_on(
+        &self,
+        line: Slvs_hEntity,
+        workplane: EntityHandle<Workplane>,
+    ) -> Result<([f64; 2], [f64; 2]), &'static str> {
+        let slvs_line = self.slvs_entity(line)?;
+
+        Ok((
+            self.point_coords_on(slvs_line.point[0], workplane.handle())?,
+            self.point_coords_on(slvs_line.point[1], workplane.handle())?,
+        ))
+    }
+}
+
+fn confidence(deviation: f64, tolerance: f64) -> f64 {
+    if tolerance > 0.0 {
+        (1.0 - deviation / tolerance).clamp(0.0, 1.0)
+    } else {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constraint::{ArcLineTangent, Horizontal, Vertical},
+        entity::{ArcOfCircle, LineSegment, Normal, Point, Workplane},
+        inference::{Inferred, Tolerances},
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn rough_sketch() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        // Two lines that almost meet at a right angle, with an arc leaving one of them.
+        let g = sys.add_group();
+        let a = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 0.0]))
+            .expect("point created");
+        let b = sys
+            .sketch(Point::new_on_workplane(g, workplane, [10.0, 0.2]))
+            .expect("point created");
+        let c = sys
+            .sketch(Point::new_on_workplane(g, workplane, [10.05, 0.2]))
+            .expect("point created");
+        let d = sys
+            .sketch(Point::new_on_workplane(g, workplane, [10.15, 10.1]))
+            .expect("point created");
+        sys.sketch(LineSegment::new(g, a, b)).expect("line created");
+        let vertical_line = sys.sketch(LineSegment::new(g, c, d)).expect("line created");
+        let center = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.15, 10.1]))
+            .expect("point created");
+        let arc_start = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.15, 20.1]))
+            .expect("point created");
+        let arc = sys
+            .sketch(ArcOfCircle::new(g, workplane, center, arc_start, d))
+            .expect("arc created");
+
+        // A line with no length, which has no direction to infer.
+        let e = sys
+            .sketch(Point::new_on_workplane(g, workplane, [30.0, 30.0]))
+            .expect("point created");
+        let f = sys
+            .sketch(Point::new_on_workplane(g, workplane, [30.0, 30.0]))
+            .expect("point created");
+        let short_line = sys.sketch(LineSegment::new(g, e, f)).expect("line created");
+
+        // A tangent at the other end of the arc does not hide the one at `d`.
+        sys.constrain(ArcLineTangent::new(g, workplane, arc, vertical_line, false))
+            .expect("constraint added");
+
+        let candidates = sys
+            .infer_constraints(&g, &Tolerances::default())
+            .expect("constraints inferred");
+        assert!(candidates
+            .windows(2)
+            .all(|pair| pair[0].confidence >= pair[1].confidence));
+
+        let inferred: Vec<Inferred> = candidates
+            .iter()
+            .map(|candidate| candidate.constraint)
+            .collect();
+        assert!(inferred
+            .iter()
+            .any(|constraint| matches!(constraint, Inferred::Horizontal(_))));
+        assert!(inferred.iter().any(|constraint| matches!(
+            constraint,
+            Inferred::Vertical(data) if *data == Vertical::from_line(g, workplane, vertical_line)
+        )));
+        assert!(inferred.iter().any(|constraint| matches!(
+            constraint,
+            Inferred::PointsCoincident(data) if data.point_a == b && data.point_b == c
+        )));
+        assert!(inferred.iter().any(|constraint| matches!(
+            constraint,
+            Inferred::ArcLineTangent(data) if data.arc == arc && data.to_end
+        )));
+        assert!(!inferred.iter().any(|constraint| matches!(
+            constraint,
+            Inferred::Horizontal(data) if *data == Horizontal::from_line(g, workplane, short_line)
+        ) || matches!(
+            constraint,
+            Inferred::Vertical(data) if *data == Vertical::from_line(g, workplane, short_line)
+        )));
+        assert!(inferred
+            .iter()
+            .any(|constraint| matches!(constraint, Inferred::EqualLengthLines(_))));
+
+        // Applied constraints are not suggested again.
+        let applied = candidates.len();
+        sys.constrain_inferred(&candidates[0].constraint)
+            .expect("constraint added");
+        let candidates = sys
+            .infer_constraints(&g, &Tolerances::default())
+            .expect("constraints inferred");
+        assert_eq!(candidates.len(), applied - 1);
+    }
+}
@@ -0,0 +1,1219 @@
+/*!
+Intersections between curves in a workplane, and between lines and workplanes.
+
+[`System::intersections`] finds where two lines, arcs, circles or cubic curves meet,
+once they are projected onto a workplane. Each intersection records where it lies on
+both curves as a parameter between 0 and 1:
+
+* On a line segment, the fraction of the way from its start point to its end point.
+* On an arc, the fraction of its sweep, going counter-clockwise from its start point.
+* On a circle, the fraction of a turn, going counter-clockwise from the `u` axis of
+  the workplane.
+* On a cubic curve, the Bezier parameter.
+
+Curves that touch without crossing give a single [`Contact::Tangent`] intersection.
+Where two curves lie on top of each other, the ends of the shared part are given as
+[`Contact::Overlap`] intersections, so two coincident circles have none. Line segments
+must have some length, since a point has no direction to cross along.
+*/
+
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+use crate::{
+    bindings::{SLVS_E_ARC_OF_CIRCLE, SLVS_E_CIRCLE, SLVS_E_CUBIC, SLVS_E_LINE_SEGMENT},
+    element::AsHandle,
+    entity::{AsEntityHandle, EntityHandle, LineSegment, Workplane},
+    geometry::{add, scale, sub, CubicGeometry},
+    system::SOLVE_TOLERANCE,
+    utils::{distance, quaternion_n},
+    System,
+};
+
+// Cubic curves are searched for roots in this many intervals.
+const CUBIC_SAMPLES: usize = 64;
+// Iterations used to refine a root or a tangent point on a cubic curve.
+const REFINE_ITERATIONS: usize = 60;
+// Two cubic curves are split until their parts are flat, or at most this many times,
+// before the places where they meet are refined.
+const SUBDIVISION_DEPTH: usize = 12;
+
+/// How two curves meet at an intersection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Contact {
+    /// The curves cross each other.
+    Crossing,
+    /// The curves touch without crossing.
+    Tangent,
+    /// One end of a part where the curves lie on top of each other.
+    Overlap,
+}
+
+/// A point where two curves meet, found by [`System::intersections`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Intersection {
+    /// The intersection, in the coordinates of the workplane.
+    pub point: [f64; 2],
+    /// Where the intersection lies on the first curve.
+    pub param_a: f64,
+    /// Where the intersection lies on the second curve.
+    pub param_b: f64,
+    pub contact: Contact,
+}
+
+/// A point where a line segment passes through a workplane, found by
+/// [`System::line_workplane_intersection`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlaneIntersection {
+    /// The intersection, in world coordinates.
+    pub point: [f64; 3],
+    /// The fraction of the way from the start point of the line to its end point.
+    pub param: f64,
+}
+
+impl System {
+    /// Find the points where two curves meet, when projected onto a workplane.
+    ///
+    /// The curves can be line segments, arcs, circles or cubic curves. Arcs and circles
+    /// must be parallel to the workplane. The intersections are sorted by where they
+    /// lie on `entity_a`.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity_a`, `entity_b` - The curves to intersect.
+    /// * `workplane` - The workplane to find the intersections in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{LineSegment, Normal, Point, Workplane},
+    ///     intersection::Contact,
+    ///     utils::make_quaternion,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// let mut line = |start, end| {
+    ///     let start = sys
+    ///         .sketch(Point::new_on_workplane(g, workplane, start))
+    ///         .expect("point created");
+    ///     let end = sys
+    ///         .sketch(Point::new_on_workplane(g, workplane, end))
+    ///         .expect("point created");
+    ///     sys.sketch(LineSegment::new(g, start, end))
+    ///         .expect("line created")
+    /// };
+    /// let line_a = line([0.0, 0.0], [10.0, 0.0]);
+    /// let line_b = line([2.0, -5.0], [2.0, 15.0]);
+    ///
+    /// let intersections = sys
+    ///     .intersections(&line_a, &line_b, &workplane)
+    ///     .expect("intersections found");
+    /// assert_eq!(intersections.len(), 1);
+    /// assert_eq!(intersections[0].point, [2.0, 0.0]);
+    /// assert_eq!(intersections[0].param_a, 0.2);
+    /// assert_eq!(intersections[0].param_b, 0.25);
+    /// assert_eq!(intersections[0].contact, Contact::Crossing);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either curve or the workplane are not found in the system,
+    /// if an entity is not a curve, if an arc or circle is not parallel to the
+    /// workplane, or if a line segment has zero length.
+    pub fn intersections(
+        &self,
+        entity_a: &dyn AsEntityHandle,
+        entity_b: &dyn AsEntityHandle,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<Vec<Intersection>, &'static str> {
+        let curve_a = self.curve_on(entity_a.handle(), workplane)?;
+        let curve_b = self.curve_on(entity_b.handle(), workplane)?;
+
+        curve_curve(&curve_a, &curve_b)
+    }
+
+    /// Find the point where a line segment passes through a workplane.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the line does not reach the workplane, or is parallel to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the line or workplane are not found in the system.
+    pub fn line_workplane_intersection(
+        &self,
+        line: &EntityHandle<LineSegment>,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<Option<PlaneIntersection>, &'static str> {
+        let line = self.line_geometry(line)?;
+        let (origin, quaternion) = self.workplane_frame(workplane.handle())?;
+        let normal = quaternion_n(quaternion);
+
+        let height_start = dot(sub(line.start, origin), normal);
+        let height_end = dot(sub(line.end, origin), normal);
+        if (height_start - height_end).abs() <= SOLVE_TOLERANCE {
+            return Ok(None);
+        }
+
+        let param = height_start / (height_start - height_end);
+        let param_tolerance = SOLVE_TOLERANCE / line.length();
+        if !(-param_tolerance..=1.0 + param_tolerance).contains(&param) {
+            return Ok(None);
+        }
+
+        let param = param.clamp(0.0, 1.0);
+        Ok(Some(PlaneIntersection {
+            point: line.point_at(param),
+            param,
+        }))
+    }
+
+    // Whether two curves lie on top of each other for some length, when projected onto
+    // a workplane.
+    pub(crate) fn overlaps(
+        &self,
+        h_a: u32,
+        h_b: u32,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<bool, &'static str> {
+        let curve_a = self.curve_on(h_a, workplane)?;
+        let curve_b = self.curve_on(h_b, workplane)?;
+
+        // A cubic curve only overlaps another curve between a pair of overlap ends.
+        if matches!(curve_a, Curve::Cubic(_)) || matches!(curve_b, Curve::Cubic(_)) {
+            return Ok(curve_curve(&curve_a, &curve_b)?
+                .iter()
+                .filter(|intersection| intersection.contact == Contact::Overlap)
+                .count()
+                >= 2);
+        }
+
+        // Split `a` where the ends of `b` lie on it, and check whether any of the parts
+        // between lies on `b`. Three points of a part on the line or circle of `b`
+        // can only be found if the part lies along it.
+        let mut params = vec![0.0, 1.0];
+        for end in curve_b.ends() {
+            if curve_a.offset(end).abs() <= SOLVE_TOLERANCE && curve_a.contains(end) {
+                params.push(curve_a.param(end));
+            }
+        }
+        params.sort_by(f64::total_cmp);
+
+        Ok(params.windows(2).any(|pair| {
+            let [quarter, middle, three_quarters] =
+                [0.25, 0.5, 0.75].map(|f| curve_a.point_at(pair[0] + (pair[1] - pair[0]) * f));
+            distance(middle, curve_a.point_at(pair[0])) > SOLVE_TOLERANCE
+                && [quarter, middle, three_quarters]
+                    .iter()
+                    .all(|point| curve_b.offset(*point).abs() <= SOLVE_TOLERANCE)
+                && curve_b.contains(middle)
+        }))
+    }
+
+    // Find where the line through `start` and `end` meets a curve, when projected onto
+    // a workplane. The parameters on the line are the fraction of the way from `start`
+    // to `end`, and can lie outside 0 to 1.
+    pub(crate) fn line_intersections(
+        &self,
+        start: [f64; 2],
+        end: [f64; 2],
+        h: u32,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<Vec<Intersection>, &'static str> {
+        let curve = self.curve_on(h, workplane)?;
+        let length = distance(start, end);
+        if length <= SOLVE_TOLERANCE {
+            return Err("Expected line to have non-zero length.");
+        }
+
+        // A segment reaching past the curve on both sides of `start` meets it wherever
+        // the line does.
+        let reach = curve.reach(start) + length;
+        let direction = scale(sub(end, start), reach / length);
+        let segment = Curve::Segment(sub(start, direction), add(start, direction));
+
+        Ok(curve_curve(&segment, &curve)?
+            .into_iter()
+            .map(|intersection| Intersection {
+                param_a: (2.0 * intersection.param_a - 1.0) * reach / length,
+                ..intersection
+            })
+            .collect())
+    }
+
+    // The parameter of the point on a line segment, arc or cubic curve nearest to a
+    // point, when projected onto a workplane.
+    pub(crate) fn nearest_param(
+        &self,
+        h: u32,
+        workplane: &EntityHandle<Workplane>,
+        point: [f64; 2],
+    ) -> Result<f64, &'static str> {
+        match self.curve_on(h, workplane)? {
+            Curve::Cubic(cubic) => Ok(cubic_nearest(&cubic, point)),
+            curve => Ok(curve.param(point)),
+        }
+    }
+
+    fn curve_on(&self, h: u32, workplane: &EntityHandle<Workplane>) -> Result<Curve, &'static str> {
+        match self.slvs_entity(h)?.type_ as _ {
+            SLVS_E_LINE_SEGMENT => {
+                let line = self.line_geometry_on(&EntityHandle::new(h), workplane)?;
+                Ok(Curve::Segment(line.start, line.end))
+            }
+            SLVS_E_ARC_OF_CIRCLE => {
+                let arc = self.arc_geometry_on(&EntityHandle::new(h), workplane)?;
+                Ok(Curve::Arc {
+                    center: arc.center,
+                    radius: arc.radius,
+                    start_angle: arc.start_angle.to_radians(),
+                    sweep: arc.sweep().to_radians(),
+                })
+            }
+            SLVS_E_CIRCLE => {
+                let circle = self.circle_geometry_on(&EntityHandle::new(h), workplane)?;
+                Ok(Curve::Arc {
+                    center: circle.center,
+                    radius: circle.radius,
+                    start_angle: 0.0,
+                    sweep: 2.0 * PI,
+                })
+            }
+            SLVS_E_CUBIC => Ok(Curve::Cubic(
+                self.cubic_geometry_on(&EntityHandle::new(h), workplane)?,
+            )),
+            _ => Err("Expected entity to be a line segment, arc, circle or cubic."),
+        }
+    }
+}
+
+// A curve in the coordinates of a workplane. Circles are arcs with a full sweep.
+enum Curve {
+    Segment([f64; 2], [f64; 2]),
+    Arc {
+        center: [f64; 2],
+        radius: f64,
+        // Angles are in radians.
+        start_angle: f64,
+        sweep: f64,
+    },
+    Cubic(CubicGeometry<2>),
+}
+
+impl Curve {
+    // Whether a point that is on the segment's line, or the arc's circle, lies within
+    // its ends.
+    fn contains(&self, point: [f64; 2]) -> bool {
+        match *self {
+            Curve::Segment(start, end) => {
+                let tolerance = SOLVE_TOLERANCE / distance(start, end);
+                (-tolerance..=1.0 + tolerance).contains(&segment_param(start, end, point))
+            }
+            Curve::Arc {
+                center,
+                radius,
+                start_angle,
+                sweep,
+            } => {
+                let tolerance = SOLVE_TOLERANCE / radius;
+                let angle = (angle_of(center, point) - start_angle).rem_euclid(2.0 * PI);
+                angle <= sweep + tolerance || angle >= 2.0 * PI - tolerance
+            }
+            Curve::Cubic(_) => true,
+        }
+    }
+
+    // The parameter of a point on a line segment or arc.
+    fn param(&self, point: [f64; 2]) -> f64 {
+        match *self {
+            Curve::Segment(start, end) => segment_param(start, end, point).clamp(0.0, 1.0),
+            Curve::Arc {
+                center,
+                start_angle,
+                sweep,
+                ..
+            } => {
+                let angle = (angle_of(center, point) - start_angle).rem_euclid(2.0 * PI);
+                // Points just before the start are treated as being on it.
+                if angle > sweep && sweep < 2.0 * PI {
+                    if angle - sweep < 2.0 * PI - angle {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                } else {
+                    (angle / sweep).clamp(0.0, 1.0)
+                }
+            }
+            Curve::Cubic(_) => unreachable!("Cubic parameters are found when intersecting."),
+        }
+    }
+
+    // The signed distance from a point to the segment's line, or the arc's circle.
+    fn offset(&self, point: [f64; 2]) -> f64 {
+        match *self {
+            Curve::Segment(start, end) => {
+                cross(sub(end, start), sub(point, start)) / distance(start, end)
+            }
+            Curve::Arc { center, radius, .. } => distance(center, point) - radius,
+            Curve::Cubic(_) => unreachable!("Cubic curves have no implicit form."),
+        }
+    }
+
+    // The largest distance from a point to any point on the curve, or more.
+    fn reach(&self, from: [f64; 2]) -> f64 {
+        match *self {
+            Curve::Segment(start, end) => distance(from, start).max(distance(from, end)),
+            Curve::Arc { center, radius, .. } => distance(from, center) + radius,
+            // The curve lies within the hull of its control points.
+            Curve::Cubic(cubic) => cubic
+                .control_points
+                .iter()
+                .map(|point| distance(from, *point))
+                .fold(0.0, f64::max),
+        }
+    }
+
+    // The start and end points of a line segment or arc, unless it is a full circle.
+    fn ends(&self) -> Vec<[f64; 2]> {
+        match *self {
+            Curve::Segment(start, end) => vec![start, end],
+            _ => self.arc_ends(),
+        }
+    }
+
+    // The point at a parameter on a line segment or arc.
+    fn point_at(&self, param: f64) -> [f64; 2] {
+        match *self {
+            Curve::Segment(start, end) => add(start, scale(sub(end, start), param)),
+            Curve::Arc {
+                center,
+                radius,
+                start_angle,
+                sweep,
+            } => {
+                let angle = start_angle + sweep * param;
+                add(center, [radius * angle.cos(), radius * angle.sin()])
+            }
+            Curve::Cubic(cubic) => cubic.point_at(param),
+        }
+    }
+
+    // The start and end points of an arc, unless it is a full circle.
+    fn arc_ends(&self) -> Vec<[f64; 2]> {
+        match *self {
+            Curve::Arc {
+                center,
+                radius,
+                start_angle,
+                sweep,
+            } if sweep < 2.0 * PI => [start_angle, start_angle + sweep]
+                .map(|angle| add(center, [radius * angle.cos(), radius * angle.sin()]))
+                .to_vec(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+// Find where two curves meet, sorted by where they lie on `curve_a`.
+fn curve_curve(curve_a: &Curve, curve_b: &Curve) -> Result<Vec<Intersection>, &'static str> {
+    for curve in [curve_a, curve_b] {
+        if let Curve::Segment(start, end) = curve {
+            if distance(*start, *end) <= SOLVE_TOLERANCE {
+                return Err("Expected line to have non-zero length.");
+            }
+        }
+    }
+
+    let mut intersections = match (curve_a, curve_b) {
+        (Curve::Cubic(a), Curve::Cubic(b)) => cubic_cubic(a, b),
+        (Curve::Segment(a0, a1), Curve::Segment(b0, b1)) => segment_segment(*a0, *a1, *b0, *b1),
+        (Curve::Segment(..), Curve::Arc { .. }) => segment_arc(curve_a, curve_b),
+        (Curve::Arc { .. }, Curve::Segment(..)) => swapped(segment_arc(curve_b, curve_a)),
+        (Curve::Arc { .. }, Curve::Arc { .. }) => arc_arc(curve_a, curve_b),
+        (Curve::Cubic(cubic), _) => cubic_curve(cubic, curve_b),
+        (_, Curve::Cubic(cubic)) => swapped(cubic_curve(cubic, curve_a)),
+    };
+
+    intersections.sort_by(|a, b| a.param_a.total_cmp(&b.param_a));
+    Ok(intersections)
+}
+
+fn segment_segment(a0: [f64; 2], a1: [f64; 2], b0: [f64; 2], b1: [f64; 2]) -> Vec<Intersection> {
+    let (direction_a, direction_b) = (sub(a1, a0), sub(b1, b0));
+    let (length_a, length_b) = (distance(a0, a1), distance(b0, b1));
+    let denominator = cross(direction_a, direction_b);
+
+    if denominator.abs() <= SOLVE_TOLERANCE * length_a * length_b {
+        if (cross(direction_a, sub(b0, a0)) / length_a).abs() > SOLVE_TOLERANCE {
+            return Vec::new();
+        }
+
+        // The segments are collinear, and share the part where their parameters
+        // on `a` overlap.
+        let (t0, t1) = (segment_param(a0, a1, b0), segment_param(a0, a1, b1));
+        let low = t0.min(t1).max(0.0);
+        let high = t0.max(t1).min(1.0);
+        if high < low - SOLVE_TOLERANCE / length_a {
+            return Vec::new();
+        }
+
+        let segment_b = Curve::Segment(b0, b1);
+        let mut params = vec![low];
+        if (high - low) * length_a > SOLVE_TOLERANCE {
+            params.push(high);
+        }
+        return params
+            .into_iter()
+            .map(|t| {
+                let point = add(a0, scale(direction_a, t));
+                Intersection {
+                    point,
+                    param_a: t,
+                    param_b: segment_b.param(point),
+                    contact: Contact::Overlap,
+                }
+            })
+            .collect();
+    }
+
+    let offset = sub(b0, a0);
+    let t = cross(offset, direction_b) / denominator;
+    let u = cross(offset, direction_a) / denominator;
+    let (tolerance_a, tolerance_b) = (SOLVE_TOLERANCE / length_a, SOLVE_TOLERANCE / length_b);
+
+    if (-tolerance_a..=1.0 + tolerance_a).contains(&t)
+        && (-tolerance_b..=1.0 + tolerance_b).contains(&u)
+    {
+        let t = t.clamp(0.0, 1.0);
+        vec![Intersection {
+            point: add(a0, scale(direction_a, t)),
+            param_a: t,
+            param_b: u.clamp(0.0, 1.0),
+            contact: Contact::Crossing,
+        }]
+    } else {
+        Vec::new()
+    }
+}
+
+fn segment_arc(segment: &Curve, arc: &Curve) -> Vec<Intersection> {
+    let (Curve::Segment(start, end), Curve::Arc { center, radius, .. }) = (segment, arc) else {
+        unreachable!()
+    };
+    let direction = sub(*end, *start);
+    let length = distance(*start, *end);
+
+    // The foot of the perpendicular from the center to the line, and the distance
+    // from there to where the line crosses the circle.
+    let foot = segment_param(*start, *end, *center);
+    let gap = segment.offset(*center).abs();
+
+    let candidates = if gap > radius + SOLVE_TOLERANCE {
+        Vec::new()
+    } else if (gap - radius).abs() <= SOLVE_TOLERANCE {
+        vec![(foot, Contact::Tangent)]
+    } else {
+        let half_chord = (radius.powi(2) - gap.powi(2)).sqrt() / length;
+        vec![
+            (foot - half_chord, Contact::Crossing),
+            (foot + half_chord, Contact::Crossing),
+        ]
+    };
+
+    candidates
+        .into_iter()
+        .map(|(t, contact)| (add(*start, scale(direction, t)), contact))
+        .filter(|(point, _)| segment.contains(*point) && arc.contains(*point))
+        .map(|(point, contact)| Intersection {
+            point,
+            param_a: segment.param(point),
+            param_b: arc.param(point),
+            contact,
+        })
+        .collect()
+}
+
+fn arc_arc(arc_a: &Curve, arc_b: &Curve) -> Vec<Intersection> {
+    let (
+        Curve::Arc {
+            center: center_a,
+            radius: radius_a,
+            ..
+        },
+        Curve::Arc {
+            center: center_b,
+            radius: radius_b,
+            ..
+        },
+    ) = (arc_a, arc_b)
+    else {
+        unreachable!()
+    };
+    let gap = distance(*center_a, *center_b);
+
+    let candidates = if gap <= SOLVE_TOLERANCE && (radius_a - radius_b).abs() <= SOLVE_TOLERANCE {
+        // The circles coincide, and the arcs overlap between the ends that lie on
+        // the other arc.
+        let mut ends: Vec<[f64; 2]> = Vec::new();
+        for end in arc_a.arc_ends().into_iter().chain(arc_b.arc_ends()) {
+            if ends
+                .iter()
+                .all(|other| distance(*other, end) > SOLVE_TOLERANCE)
+            {
+                ends.push(end);
+            }
+        }
+        ends.into_iter()
+            .map(|end| (end, Contact::Overlap))
+            .collect()
+    } else if gap > radius_a + radius_b + SOLVE_TOLERANCE
+        || gap < (radius_a - radius_b).abs() - SOLVE_TOLERANCE
+    {
+        Vec::new()
+    } else {
+        // The distance from the center of `a` along the line between the centers to
+        // the chord through the intersections, and half the length of that chord.
+        let direction = scale(sub(*center_b, *center_a), 1.0 / gap);
+        let along = ((gap.powi(2) + radius_a.powi(2) - radius_b.powi(2)) / (2.0 * gap))
+            .clamp(-radius_a, *radius_a);
+        let half_chord = (radius_a.powi(2) - along.powi(2)).max(0.0).sqrt();
+        let foot = add(*center_a, scale(direction, along));
+        let perpendicular = [-direction[1], direction[0]];
+
+        if half_chord <= SOLVE_TOLERANCE {
+            vec![(foot, Contact::Tangent)]
+        } else {
+            vec![
+                (
+                    add(foot, scale(perpendicular, half_chord)),
+                    Contact::Crossing,
+                ),
+                (
+                    sub(foot, scale(perpendicular, half_chord)),
+                    Contact::Crossing,
+                ),
+            ]
+        }
+    };
+
+    candidates
+        .into_iter()
+        .filter(|(point, _)| arc_a.contains(*point) && arc_b.contains(*point))
+        .map(|(point, contact)| Intersection {
+            point,
+            param_a: arc_a.param(point),
+            param_b: arc_b.param(point),
+            contact,
+        })
+        .collect()
+}
+
+// Find where a cubic curve meets a line segment or arc, from the roots and the
+// touching minima of the curve's distance from the other curve.
+fn cubic_curve(cubic: &CubicGeometry<2>, other: &Curve) -> Vec<Intersection> {
+    let offset = |t: f64| other.offset(cubic.point_at(t));
+    let samples: Vec<(f64, f64)> = (0..=CUBIC_SAMPLES)
+        .map(|i| {
+            let t = i as f64 / CUBIC_SAMPLES as f64;
+            (t, offset(t))
+        })
+        .collect();
+
+    if samples
+        .iter()
+        .all(|(_, value)| value.abs() <= SOLVE_TOLERANCE)
+    {
+        let on_other =
+            |point| other.offset(point).abs() <= SOLVE_TOLERANCE && other.contains(point);
+        return cubic_overlap(cubic, &on_other, &other.ends())
+            .into_iter()
+            .map(|t| {
+                let point = cubic.point_at(t);
+                Intersection {
+                    point,
+                    param_a: t,
+                    param_b: other.param(point),
+                    contact: Contact::Overlap,
+                }
+            })
+            .collect();
+    }
+
+    let mut candidates = Vec::new();
+    for (i, window) in samples.windows(2).enumerate() {
+        let [(t0, v0), (t1, v1)] = [window[0], window[1]];
+
+        if v0 == 0.0 {
+            candidates.push((t0, Contact::Crossing));
+        } else if v0.signum() != v1.signum() && v1 != 0.0 {
+            candidates.push((bisect(&offset, t0, t1), Contact::Crossing));
+        } else if i > 0 {
+            // Look for a point where the curve touches the other curve between
+            // samples on the same side of it.
+            let (t_before, v_before) = samples[i - 1];
+            if v0.abs() <= v_before.abs()
+                && v0.abs() <= v1.abs()
+                && v_before.signum() == v0.signum()
+            {
+                let t = minimize(&|t| offset(t).abs(), t_before, t1);
+                if offset(t).abs() <= SOLVE_TOLERANCE {
+                    candidates.push((t, Contact::Tangent));
+                }
+            }
+        }
+    }
+    if let Some(&(t, value)) = samples.last() {
+        if value == 0.0 {
+            candidates.push((t, Contact::Crossing));
+        }
+    }
+
+    let mut intersections: Vec<Intersection> = Vec::new();
+    for (t, contact) in candidates {
+        let point = cubic.point_at(t);
+        if other.contains(point)
+            && intersections
+                .iter()
+                .all(|found| distance(found.point, point) > SOLVE_TOLERANCE)
+        {
+            intersections.push(Intersection {
+                point,
+                param_a: t,
+                param_b: other.param(point),
+                contact,
+            });
+        }
+    }
+    intersections
+}
+
+// Find where two cubic curves meet, by splitting both until the parts that still
+// overlap are flat, and refining the meeting point of each of those parts.
+fn cubic_cubic(a: &CubicGeometry<2>, b: &CubicGeometry<2>) -> Vec<Intersection> {
+    let on_b = |point| distance(b.point_at(cubic_nearest(b, point)), point) <= SOLVE_TOLERANCE;
+    let shared = cubic_overlap(a, &on_b, &[b.control_points[0], b.control_points[3]]);
+    if !shared.is_empty() {
+        return shared
+            .into_iter()
+            .map(|s| {
+                let point = a.point_at(s);
+                Intersection {
+                    point,
+                    param_a: s,
+                    param_b: cubic_nearest(b, point),
+                    contact: Contact::Overlap,
+                }
+            })
+            .collect();
+    }
+
+    let mut leaves = Vec::new();
+    subdivide((a, [0.0, 1.0]), (b, [0.0, 1.0]), 0, &mut leaves);
+
+    // Neighbouring parts that come close are the same place on the curves, so only
+    // the closest part of each run is refined.
+    leaves.sort_by(|(s_a, ..), (s_b, ..)| s_a.total_cmp(s_b));
+    let step = 1.0 / CUBIC_SAMPLES as f64;
+    let mut candidates: Vec<(f64, f64, f64)> = Vec::new();
+    for (s, t, gap) in leaves {
+        match candidates.last_mut() {
+            Some(last) if s - last.0 <= step && (t - last.1).abs() <= step => {
+                if gap < last.2 {
+                    *last = (s, t, gap);
+                }
+            }
+            _ => candidates.push((s, t, gap)),
+        }
+    }
+
+    let mut intersections: Vec<Intersection> = Vec::new();
+    for (s, t, _) in candidates {
+        let Some((s, t)) = refine(a, b, s, t) else {
+            continue;
+        };
+        let point = a.point_at(s);
+
+        // Candidates joined by a part where the curves stay together are the same
+        // contact, found more than once where the curves touch.
+        if intersections.iter().any(|found| {
+            distance(found.point, point) <= SOLVE_TOLERANCE
+                || ((found.param_a - s).abs() <= step
+                    && on_b(a.point_at((found.param_a + s) / 2.0)))
+        }) {
+            continue;
+        }
+
+        // The curves cross if `a` is on opposite sides of `b` either side of the point.
+        let side = |s: f64| cross(b.tangent_at(t), sub(a.point_at(s), b.point_at(t)));
+        let (before, after) = (side((s - step).max(0.0)), side((s + step).min(1.0)));
+        let contact = if before.signum() != after.signum() || s == 0.0 || s == 1.0 {
+            Contact::Crossing
+        } else {
+            Contact::Tangent
+        };
+
+        intersections.push(Intersection {
+            point,
+            param_a: s,
+            param_b: t,
+            contact,
+        });
+    }
+    intersections
+}
+
+// Collect where the parts of two cubic curves come close, splitting both until each
+// part is flat enough to stand in for its chord. Each part is given with the range of
+// parameters it covers on the whole curve, and each leaf is collected as parameters
+// on the whole curves along with the distance between the chords there.
+fn subdivide(
+    (a, range_a): (&CubicGeometry<2>, [f64; 2]),
+    (b, range_b): (&CubicGeometry<2>, [f64; 2]),
+    depth: usize,
+    leaves: &mut Vec<(f64, f64, f64)>,
+) {
+    let bounds = |cubic: &CubicGeometry<2>| {
+        let [xs, ys] = [0, 1].map(|i| cubic.control_points.map(|point| point[i]));
+        let low = [xs, ys].map(|values| values.into_iter().fold(f64::INFINITY, f64::min));
+        let high = [xs, ys].map(|values| values.into_iter().fold(f64::NEG_INFINITY, f64::max));
+        (low, high)
+    };
+    let ((low_a, high_a), (low_b, high_b)) = (bounds(a), bounds(b));
+    if (0..2)
+        .any(|i| low_a[i] > high_b[i] + SOLVE_TOLERANCE || low_b[i] > high_a[i] + SOLVE_TOLERANCE)
+    {
+        return;
+    }
+
+    // A flat part lies within the tolerance of its chord, so parts that meet have
+    // chords within three times the tolerance of each other.
+    let flat = |cubic: &CubicGeometry<2>| {
+        let [start, control_a, control_b, end] = cubic.control_points;
+        [control_a, control_b]
+            .into_iter()
+            .all(|control| segment_gap(start, end, control) <= SOLVE_TOLERANCE)
+    };
+    let middle = |range: [f64; 2]| (range[0] + range[1]) / 2.0;
+    if depth == SUBDIVISION_DEPTH || (flat(a) && flat(b)) {
+        let [a0, .., a1] = a.control_points;
+        let [b0, .., b1] = b.control_points;
+        let (s, t, gap) = segment_approach(a0, a1, b0, b1);
+        if gap <= 3.0 * SOLVE_TOLERANCE {
+            let along = |range: [f64; 2], param: f64| range[0] + (range[1] - range[0]) * param;
+            leaves.push((along(range_a, s), along(range_b, t), gap));
+        }
+        return;
+    }
+
+    let (a_before, a_after) = a.split(0.5);
+    let (b_before, b_after) = b.split(0.5);
+    for part_a in [
+        (&a_before, [range_a[0], middle(range_a)]),
+        (&a_after, [middle(range_a), range_a[1]]),
+    ] {
+        for part_b in [
+            (&b_before, [range_b[0], middle(range_b)]),
+            (&b_after, [middle(range_b), range_b[1]]),
+        ] {
+            subdivide(part_a, part_b, depth + 1, leaves);
+        }
+    }
+}
+
+// Refine where two cubic curves meet from nearby parameters on each, by Newton's
+// method where they cross, or by finding their closest approach where they touch.
+fn refine(a: &CubicGeometry<2>, b: &CubicGeometry<2>, s: f64, t: f64) -> Option<(f64, f64)> {
+    let (mut s_newton, mut t_newton) = (s, t);
+    for _ in 0..REFINE_ITERATIONS {
+        let gap = sub(b.point_at(t_newton), a.point_at(s_newton));
+        let (tangent_a, tangent_b) = (a.tangent_at(s_newton), b.tangent_at(t_newton));
+        let denominator = cross(tangent_a, scale(tangent_b, -1.0));
+        if denominator.abs() <= f64::EPSILON {
+            break;
+        }
+        s_newton = (s_newton + cross(gap, scale(tangent_b, -1.0)) / denominator).clamp(0.0, 1.0);
+        t_newton = (t_newton + cross(tangent_a, gap) / denominator).clamp(0.0, 1.0);
+    }
+
+    // Newton's method only converges slowly where the curves touch, so it is only
+    // trusted where they cross at an angle.
+    let (tangent_a, tangent_b) = (a.tangent_at(s_newton), b.tangent_at(t_newton));
+    let sin = cross(tangent_a, tangent_b).abs()
+        / (distance(tangent_a, [0.0; 2]) * distance(tangent_b, [0.0; 2]));
+    if distance(a.point_at(s_newton), b.point_at(t_newton)) <= SOLVE_TOLERANCE
+        && sin > SOLVE_TOLERANCE.sqrt()
+    {
+        return Some((s_newton, t_newton));
+    }
+
+    let step = 1.0 / CUBIC_SAMPLES as f64;
+    let gap = |s: f64| distance(a.point_at(s), b.point_at(cubic_nearest(b, a.point_at(s))));
+    let s = minimize(&gap, (s - step).max(0.0), (s + step).min(1.0));
+    let t = cubic_nearest(b, a.point_at(s));
+    (gap(s) <= SOLVE_TOLERANCE).then_some((s, t))
+}
+
+// Where a cubic curve lies on top of another curve, the ends of each part that it
+// shares with it, as parameters on the cubic curve. `on_other` tells whether a point
+// lies on the other curve, and `other_ends` are the end points of the other curve.
+fn cubic_overlap(
+    cubic: &CubicGeometry<2>,
+    on_other: &impl Fn([f64; 2]) -> bool,
+    other_ends: &[[f64; 2]],
+) -> Vec<f64> {
+    // Split the cubic curve where the ends of the other curve lie on it, and check
+    // whether any of the parts between lies on the other curve.
+    let mut params = vec![0.0, 1.0];
+    for &end in other_ends {
+        let t = cubic_nearest(cubic, end);
+        if distance(cubic.point_at(t), end) <= SOLVE_TOLERANCE {
+            params.push(t);
+        }
+    }
+    params.sort_by(f64::total_cmp);
+
+    let mut shared: Vec<f64> = Vec::new();
+    for pair in params.windows(2) {
+        let lies_on = distance(cubic.point_at(pair[0]), cubic.point_at(pair[1])) > SOLVE_TOLERANCE
+            && [0.25, 0.5, 0.75]
+                .iter()
+                .all(|f| on_other(cubic.point_at(pair[0] + (pair[1] - pair[0]) * f)));
+        if !lies_on {
+            continue;
+        }
+        for &t in pair {
+            if shared
+                .iter()
+                .all(|&found| distance(cubic.point_at(found), cubic.point_at(t)) > SOLVE_TOLERANCE)
+            {
+                shared.push(t);
+            }
+        }
+    }
+    shared
+}
+
+// The parameter of the point on a cubic curve nearest to a point.
+fn cubic_nearest(cubic: &CubicGeometry<2>, point: [f64; 2]) -> f64 {
+    let gap = |t: f64| distance(cubic.point_at(t), point);
+    let nearest = (0..=CUBIC_SAMPLES)
+        .min_by(|&i, &j| {
+            let t = |i| i as f64 / CUBIC_SAMPLES as f64;
+            gap(t(i)).total_cmp(&gap(t(j)))
+        })
+        .unwrap_or(0);
+    let low = nearest.saturating_sub(1) as f64 / CUBIC_SAMPLES as f64;
+    let high = (nearest + 1).min(CUBIC_SAMPLES) as f64 / CUBIC_SAMPLES as f64;
+
+    // Away from the ends, the nearest point is where the curve runs square to the
+    // direction of `point`, which is found more precisely as a root.
+    let slope = |t: f64| dot(cubic.tangent_at(t), sub(cubic.point_at(t), point));
+    if slope(low) < 0.0 && slope(high) > 0.0 {
+        bisect(&slope, low, high)
+    } else {
+        minimize(&gap, low, high)
+    }
+}
+
+// Find a root of `f` between `low` and `high`, where it changes sign.
+fn bisect(f: &impl Fn(f64) -> f64, mut low: f64, mut high: f64) -> f64 {
+    let sign = f(low).signum();
+    for _ in 0..REFINE_ITERATIONS {
+        let mid = (low + high) / 2.0;
+        if f(mid).signum() == sign {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+// Find the minimum of `f` between `low` and `high`, by golden section search.
+fn minimize(f: &impl Fn(f64) -> f64, mut low: f64, mut high: f64) -> f64 {
+    let ratio = (5.0_f64.sqrt() - 1.0) / 2.0;
+    for _ in 0..REFINE_ITERATIONS {
+        let a = high - ratio * (high - low);
+        let b = low + ratio * (high - low);
+        if f(a) < f(b) {
+            high = b;
+        } else {
+            low = a;
+        }
+    }
+    (low + high) / 2.0
+}
+
+fn swapped(intersections: Vec<Intersection>) -> Vec<Intersection> {
+    intersections
+        .into_iter()
+        .map(|intersection| Intersection {
+            param_a: intersection.param_b,
+            param_b: intersection.param_a,
+            ..intersection
+        })
+        .collect()
+}
+
+// The parameters of the closest points on two line segments, and the distance
+// between them.
+fn segment_approach(a0: [f64; 2], a1: [f64; 2], b0: [f64; 2], b1: [f64; 2]) -> (f64, f64, f64) {
+    if distance(a0, a1) > 0.0 && distance(b0, b1) > 0.0 {
+        if let Some(found) = segment_segment(a0, a1, b0, b1).first() {
+            return (found.param_a, found.param_b, 0.0);
+        }
+    }
+
+    let onto = |start: [f64; 2], end: [f64; 2], point: [f64; 2]| {
+        if distance(start, end) > 0.0 {
+            segment_param(start, end, point).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    };
+    let at = |start: [f64; 2], end: [f64; 2], t: f64| add(start, scale(sub(end, start), t));
+    [
+        (0.0, onto(b0, b1, a0)),
+        (1.0, onto(b0, b1, a1)),
+        (onto(a0, a1, b0), 0.0),
+        (onto(a0, a1, b1), 1.0),
+    ]
+    .into_iter()
+    .map(|(s, t)| (s, t, distance(at(a0, a1, s), at(b0, b1, t))))
+    .min_by(|(.., gap_a), (.., gap_b)| gap_a.total_cmp(gap_b))
+    .unwrap()
+}
+
+// The distance from a point to a line segment.
+fn segment_gap(start: [f64; 2], end: [f64; 2], point: [f64; 2]) -> f64 {
+    let (.., gap) = segment_approach(start, end, point, point);
+    gap
+}
+
+fn segment_param(start: [f64; 2], end: [f64; 2], point: [f64; 2]) -> f64 {
+    let direction = sub(end, start);
+    dot(sub(point, start), direction) / dot(direction, direction)
+}
+
+fn angle_of(center: [f64; 2], point: [f64; 2]) -> f64 {
+    (point[1] - center[1]).atan2(point[0] - center[0])
+}
+
+fn dot<const N: usize>(a: [f64; N], b: [f64; N]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn cross(a: [f64; 2], b: [f64; 2]) -> f64 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity::{ArcOfCircle, Circle, Cubic, Distance, LineSegment, Normal, Point, Workplane},
+        intersection::Contact,
+        len_within_tolerance,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn tangent_and_overlapping_curves() {
+        let mut sys = System::new();
+        let g = sys.add_group();
+
+        let origin = sys
+            .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(g, origin, normal))
+            .expect("workplane created");
+        let mut point = |coords| {
+            sys.sketch(Point::new_on_workplane(g, workplane, coords))
+                .expect("point created")
+        };
+        let (center, top, left, bottom, right) = (
+            point([0.0, 0.0]),
+            point([0.0, 5.0]),
+            point([-5.0, 0.0]),
+            point([0.0, -5.0]),
+            point([5.0, 0.0]),
+        );
+        let (tangent_start, tangent_end) = (point([-10.0, 5.0]), point([10.0, 5.0]));
+        let (control_a, control_b) = (point([20.0, 20.0]), point([-20.0, -20.0]));
+
+        let radius = sys.sketch(Distance::new(g, 5.0)).expect("distance created");
+        let circle = sys
+            .sketch(Circle::new(g, normal, center, radius))
+            .expect("circle created");
+        let tangent = sys
+            .sketch(LineSegment::new(g, tangent_start, tangent_end))
+            .expect("line created");
+        // From the top, counter-clockwise to the bottom, and from the left
+        // counter-clockwise to the right.
+        let arc_a = sys
+            .sketch(ArcOfCircle::new(g, workplane, center, top, bottom))
+            .expect("arc created");
+        let arc_b = sys
+            .sketch(ArcOfCircle::new(g, workplane, center, left, right))
+            .expect("arc created");
+        let cubic = sys
+            .sketch(Cubic::new(g, bottom, control_a, control_b, top))
+            .expect("cubic created");
+
+        let found = sys
+            .intersections(&tangent, &circle, &workplane)
+            .expect("intersections found");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].contact, Contact::Tangent);
+        len_within_tolerance!(found[0].param_a, 0.5);
+        len_within_tolerance!(found[0].param_b, 0.25);
+
+        // The arcs share the quarter from the left to the bottom.
+        let found = sys
+            .intersections(&arc_a, &arc_b, &workplane)
+            .expect("intersections found");
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|found| found.contact == Contact::Overlap));
+        len_within_tolerance!(found[0].param_a, 0.5);
+        len_within_tolerance!(found[0].param_b, 0.0);
+        len_within_tolerance!(found[1].param_a, 1.0);
+        len_within_tolerance!(found[1].param_b, 0.5);
+
+        // The cubic starts and ends on the circle, and crosses it four times on the way.
+        let found = sys
+            .intersections(&circle, &cubic, &workplane)
+            .expect("intersections found");
+        assert_eq!(found.len(), 6);
+        assert!(found.iter().all(|found| found.contact == Contact::Crossing));
+        let ends: Vec<f64> = found
+            .iter()
+            .map(|found| found.param_b)
+            .filter(|&t| t == 0.0 || t == 1.0)
+            .collect();
+        assert_eq!(ends.len(), 2);
+
+        let vertical = sys
+            .sketch(LineSegment::new(g, bottom, top))
+            .expect("line created");
+        assert_eq!(
+            sys.line_workplane_intersection(&vertical, &workplane),
+            Ok(None)
+        );
+
+        let point_line = sys
+            .sketch(LineSegment::new(g, top, top))
+            .expect("line created");
+        assert_eq!(
+            sys.intersections(&point_line, &circle, &workplane),
+            Err("Expected line to have non-zero length.")
+        );
+    }
+
+    #[test]
+    fn cubic_curves() {
+        let mut sys = System::new();
+        let g = sys.add_group();
+
+        let origin = sys
+            .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(g, origin, normal))
+            .expect("workplane created");
+        let mut cubic = |control_points: [[f64; 2]; 4]| {
+            let [p0, p1, p2, p3] = control_points.map(|coords| {
+                sys.sketch(Point::new_on_workplane(g, workplane, coords))
+                    .expect("point created")
+            });
+            sys.sketch(Cubic::new(g, p0, p1, p2, p3))
+                .expect("cubic created")
+        };
+
+        // An arch rising to 7.5, and the first half of it.
+        let arch = cubic([[0.0, 0.0], [3.0, 10.0], [7.0, 10.0], [10.0, 0.0]]);
+        let half = cubic([[0.0, 0.0], [1.5, 5.0], [3.25, 7.5], [5.0, 7.5]]);
+        let crossing = cubic([[0.0, 5.0], [3.0, 5.0], [7.0, 5.0], [10.0, 5.0]]);
+        let touching = cubic([[0.0, 7.5], [3.0, 7.5], [7.0, 7.5], [10.0, 7.5]]);
+        let straight = cubic([[2.0, 0.0], [4.0, 0.0], [6.0, 0.0], [8.0, 0.0]]);
+        // The arch raised slightly, which runs close to it without meeting it.
+        let raised = cubic([[0.0, 0.001], [3.0, 10.001], [7.0, 10.001], [10.0, 0.001]]);
+
+        let found = sys
+            .intersections(&arch, &crossing, &workplane)
+            .expect("intersections found");
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|found| found.contact == Contact::Crossing));
+        len_within_tolerance!(found[0].param_a, (1.0 - (1.0_f64 / 3.0).sqrt()) / 2.0);
+        len_within_tolerance!(found[1].param_a, (1.0 + (1.0_f64 / 3.0).sqrt()) / 2.0);
+        len_within_tolerance!(found[0].point[1], 5.0);
+
+        let found = sys
+            .intersections(&arch, &touching, &workplane)
+            .expect("intersections found");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].contact, Contact::Tangent);
+        len_within_tolerance!(found[0].param_a, 0.5);
+        len_within_tolerance!(found[0].param_b, 0.5);
+
+        let found = sys
+            .intersections(&arch, &half, &workplane)
+            .expect("intersections found");
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|found| found.contact == Contact::Overlap));
+        len_within_tolerance!(found[0].param_a, 0.0);
+        len_within_tolerance!(found[1].param_a, 0.5);
+        len_within_tolerance!(found[1].param_b, 1.0);
+
+        let found = sys
+            .intersections(&arch, &raised, &workplane)
+            .expect("intersections found");
+        assert!(found.is_empty());
+
+        // A straight cubic lying along a line, past the line's end.
+        let [start, end] = [[0.0, 0.0], [5.0, 0.0]].map(|coords| {
+            sys.sketch(Point::new_on_workplane(g, workplane, coords))
+                .expect("point created")
+        });
+        let line = sys
+            .sketch(LineSegment::new(g, start, end))
+            .expect("line created");
+        let found = sys
+            .intersections(&line, &straight, &workplane)
+            .expect("intersections found");
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|found| found.contact == Contact::Overlap));
+        len_within_tolerance!(found[0].param_a, 0.4);
+        len_within_tolerance!(found[1].param_a, 1.0);
+    }
+}
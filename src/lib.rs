@@ -134,7 +134,7 @@ sys.constrain(Diameter::new(g2, arc, 17.0 * 2.0))
 let result = sys.solve(&g2);
 
 match result {
-    SolveResult::Ok { dof } => {
+    SolveResult::Ok { dof, .. } => {
         println!("solved okay");
         if let (
             Point::OnWorkplane {
@@ -221,13 +221,39 @@ match result {
 ```
 */
 
+pub mod auto_constrain;
+pub mod conflict;
 pub mod constraint;
 pub mod entity;
+pub mod fillet;
+pub mod geometry;
 pub mod group;
+pub mod inequality;
+pub mod inference;
+pub mod intersection;
+pub mod measurement;
+pub mod motion;
+pub mod offset;
+pub mod optimize;
+pub mod pattern;
+pub mod profile;
+pub mod redundancy;
+pub mod section;
+pub mod shapes;
+pub mod snapshot;
+pub mod soft;
+pub mod spatial;
+pub mod status;
+pub mod tessellation;
+pub mod tolerance;
+pub mod trim;
 pub mod utils;
+pub mod validation;
 
 pub use system::System;
 
 mod bindings;
 mod element;
+pub(crate) mod equations;
+pub(crate) mod linalg;
 pub mod system;
@@ -0,0 +1,159 @@
+/*!
+Small dense linear algebra routines, used to analyse the constraint Jacobian.
+*/
+
+// Rows or columns with a norm smaller than this, relative to their original norm,
+// are considered to be linearly dependent.
+const RANK_TOLERANCE: f64 = 1e-6;
+
+pub(crate) fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+pub(crate) fn norm(a: &[f64]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+// Remove the components of `vec` along each of the orthonormal `basis` vectors.
+fn orthogonalize(vec: &mut [f64], basis: &[Vec<f64>]) {
+    // Done twice, to limit the loss of orthogonality from rounding.
+    for _ in 0..2 {
+        for b in basis {
+            let d = dot(vec, b);
+            vec.iter_mut().zip(b).for_each(|(v, b)| *v -= d * b);
+        }
+    }
+}
+
+/// Add `row` to the orthonormal `basis`, if it is independent of the vectors already
+/// in it. Returns whether the row was added.
+pub(crate) fn extend_basis(basis: &mut Vec<Vec<f64>>, row: &[f64]) -> bool {
+    let row_norm = norm(row);
+    let mut residual = row.to_vec();
+    orthogonalize(&mut residual, basis);
+
+    let residual_norm = norm(&residual);
+    if row_norm > 0.0 && residual_norm > RANK_TOLERANCE * row_norm {
+        residual.iter_mut().for_each(|v| *v /= residual_norm);
+        basis.push(residual);
+        true
+    } else {
+        false
+    }
+}
+
+/// Orthonormal basis for the row space of `matrix`, built up row by row.
+///
+/// Returns the basis, and whether each row was independent of the rows before it.
+pub(crate) fn row_basis(matrix: &[Vec<f64>]) -> (Vec<Vec<f64>>, Vec<bool>) {
+    let mut basis: Vec<Vec<f64>> = Vec::new();
+    let independent = matrix
+        .iter()
+        .map(|row| extend_basis(&mut basis, row))
+        .collect();
+
+    (basis, independent)
+}
+
+pub(crate) fn rank(matrix: &[Vec<f64>]) -> usize {
+    row_basis(matrix).0.len()
+}
+
+pub(crate) fn transpose(matrix: &[Vec<f64>], cols: usize) -> Vec<Vec<f64>> {
+    (0..cols)
+        .map(|col| matrix.iter().map(|row| row[col]).collect())
+        .collect()
+}
+
+/// Orthonormal basis for the null space of `matrix`, which has `cols` columns.
+pub(crate) fn null_space(matrix: &[Vec<f64>], cols: usize) -> Vec<Vec<f64>> {
+    let (mut basis, _) = row_basis(matrix);
+    let row_rank = basis.len();
+
+    for i in 0..cols {
+        let mut unit = vec![0.0; cols];
+        unit[i] = 1.0;
+        orthogonalize(&mut unit, &basis);
+
+        let unit_norm = norm(&unit);
+        if unit_norm > RANK_TOLERANCE {
+            unit.iter_mut().for_each(|v| *v /= unit_norm);
+            basis.push(unit);
+        }
+    }
+
+    basis.split_off(row_rank)
+}
+
+/// Solve the square system `matrix * x = rhs` by Gaussian elimination with partial
+/// pivoting. Returns `None` if the matrix is singular.
+pub(crate) fn solve(matrix: &[Vec<f64>], rhs: &[f64]) -> Option<Vec<f64>> {
+    let n = rhs.len();
+    let mut a: Vec<Vec<f64>> = matrix
+        .iter()
+        .zip(rhs)
+        .map(|(row, &b)| row.iter().copied().chain([b]).collect())
+        .collect();
+    let scale = a
+        .iter()
+        .flat_map(|row| row[..n].iter())
+        .fold(0.0_f64, |max, v| max.max(v.abs()));
+
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() <= f64::EPSILON * scale * n as f64 {
+            return None;
+        }
+        a.swap(col, pivot);
+
+        let (pivot_rows, rows) = a.split_at_mut(col + 1);
+        let pivot_row = &pivot_rows[col];
+        for row in rows {
+            let factor = row[col] / pivot_row[col];
+            row.iter_mut()
+                .zip(pivot_row)
+                .skip(col)
+                .for_each(|(v, p)| *v -= factor * p);
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (a[row][n] - sum) / a[row][row];
+    }
+
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rank_and_null_space() {
+        let matrix = vec![
+            vec![1.0, 2.0, 3.0],
+            vec![2.0, 4.0, 6.0],
+            vec![0.0, 1.0, 1.0],
+        ];
+        let (_, independent) = row_basis(&matrix);
+        assert_eq!(independent, vec![true, false, true]);
+
+        let null = null_space(&matrix, 3);
+        assert_eq!(null.len(), 1);
+        for row in &matrix {
+            assert!(dot(row, &null[0]).abs() < 1e-12);
+        }
+        assert!((norm(&null[0]) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn solve_square() {
+        let x = solve(&[vec![2.0, 1.0], vec![1.0, 3.0]], &[3.0, 5.0]).expect("solved");
+        assert!((x[0] - 0.8).abs() < 1e-12);
+        assert!((x[1] - 1.4).abs() < 1e-12);
+
+        assert!(solve(&[vec![1.0, 2.0], vec![2.0, 4.0]], &[1.0, 2.0]).is_none());
+    }
+}
@@ -0,0 +1,443 @@
+/*!
+Geometric quantities that can be read off the current state of the system.
+
+A [`Measurement`] describes a quantity, such as the distance between two points or
+the angle between two lines, which does not need to be dimensioned by a constraint.
+Measurements are evaluated against the current parameter values with [`System::measure`],
+so they are typically taken after a call to [`System::solve`].
+*/
+
+use euclid::default::Vector3D;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+use crate::{
+    bindings::{
+        Slvs_Constraint, Slvs_hEntity, SLVS_C_ANGLE, SLVS_C_ARC_ARC_DIFFERENCE,
+        SLVS_C_ARC_ARC_LEN_RATIO, SLVS_C_ARC_LINE_DIFFERENCE, SLVS_C_ARC_LINE_LEN_RATIO,
+        SLVS_C_DIAMETER, SLVS_C_LENGTH_DIFFERENCE, SLVS_C_LENGTH_RATIO, SLVS_C_PROJ_PT_DISTANCE,
+        SLVS_C_PT_LINE_DISTANCE, SLVS_C_PT_PLANE_DISTANCE, SLVS_C_PT_PT_DISTANCE,
+        SLVS_E_ARC_OF_CIRCLE, SLVS_E_LINE_SEGMENT, SLVS_FREE_IN_3D,
+    },
+    element::AsHandle,
+    entity::{ArcOfCircle, Circle, EntityHandle, LineSegment, Point, Workplane},
+    group::Group,
+    system::SOLVE_TOLERANCE,
+    utils::{distance, quaternion_n},
+    System,
+};
+
+/// A geometric quantity to be measured.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Measurement {
+    /// The distance between `point_a` and `point_b`.
+    PtPtDistance {
+        point_a: EntityHandle<Point>,
+        point_b: EntityHandle<Point>,
+        /// If provided, the distance is measured when projected onto this workplane.
+        workplane: Option<EntityHandle<Workplane>>,
+    },
+    /// The shortest distance between `point` and the infinite line through `line`.
+    PtLineDistance {
+        point: EntityHandle<Point>,
+        line: EntityHandle<LineSegment>,
+        /// If provided, the distance is measured when projected onto this workplane.
+        workplane: Option<EntityHandle<Workplane>>,
+    },
+    /// The signed distance from `plane` to `point`, positive in the direction of
+    /// the plane's normal.
+    PtPlaneDistance {
+        point: EntityHandle<Point>,
+        plane: EntityHandle<Workplane>,
+    },
+    /// The length of `line`.
+    LineLength {
+        line: EntityHandle<LineSegment>,
+        /// If provided, the length is measured when projected onto this workplane.
+        workplane: Option<EntityHandle<Workplane>>,
+    },
+    /// The angle between `line_a` and `line_b`, in degrees between 0 and 180.
+    Angle {
+        line_a: EntityHandle<LineSegment>,
+        line_b: EntityHandle<LineSegment>,
+        /// If provided, the angle is measured when projected onto this workplane.
+        workplane: Option<EntityHandle<Workplane>>,
+    },
+    /// The radius of `arc`.
+    ArcRadius { arc: EntityHandle<ArcOfCircle> },
+    /// The length of `arc`, going counter-clockwise from its start to its end.
+    ArcLength { arc: EntityHandle<ArcOfCircle> },
+    /// The radius of `circle`.
+    CircleRadius { circle: EntityHandle<Circle> },
+    /// The area of a closed loop in `group`, less the holes in it.
+    ///
+    /// `profile` is the index of the loop in the [`loops`][crate::profile::Profiles::loops]
+    /// found by [`System::profiles`].
+    ProfileArea {
+        group: Group,
+        workplane: EntityHandle<Workplane>,
+        profile: usize,
+    },
+    /// The length of the boundaries of a closed loop in `group` and the holes in it.
+    ///
+    /// `profile` is the index of the loop in the [`loops`][crate::profile::Profiles::loops]
+    /// found by [`System::profiles`].
+    ProfilePerimeter {
+        group: Group,
+        workplane: EntityHandle<Workplane>,
+        profile: usize,
+    },
+}
+
+impl System {
+    /// Measure a geometric quantity, using the current parameter values.
+    ///
+    /// # Arguments
+    ///
+    /// * `measurement` - The quantity to be measured.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{entity::Point, measurement::Measurement, System};
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let p1 = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("p1 created");
+    /// let p2 = sys
+    ///     .sketch(Point::new_in_3d(g, [3.0, 4.0, 0.0]))
+    ///     .expect("p2 created");
+    ///
+    /// let dist = sys
+    ///     .measure(&Measurement::PtPtDistance {
+    ///         point_a: p1,
+    ///         point_b: p2,
+    ///         workplane: None,
+    ///     })
+    ///     .expect("distance measured");
+    /// assert_eq!(dist, 5.0);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the entities referenced in `measurement` are not
+    /// found in the system, or are not of the expected type.
+    pub fn measure(&self, measurement: &Measurement) -> Result<f64, &'static str> {
+        match *measurement {
+            Measurement::PtPtDistance {
+                point_a,
+                point_b,
+                workplane,
+            } => self.pt_pt_distance(point_a.handle(), point_b.handle(), workplane_h(workplane)),
+            Measurement::PtLineDistance {
+                point,
+                line,
+                workplane,
+            } => Ok(self
+                .pt_line_distance(point.handle(), line.handle(), workplane_h(workplane))?
+                .abs()),
+            Measurement::PtPlaneDistance { point, plane } => {
+                self.pt_plane_distance(point.handle(), plane.handle())
+            }
+            Measurement::LineLength { line, workplane } => {
+                self.line_length(line.handle(), workplane_h(workplane))
+            }
+            Measurement::Angle {
+                line_a,
+                line_b,
+                workplane,
+            } => self.line_angle(line_a.handle(), line_b.handle(), workplane_h(workplane)),
+            Measurement::ArcRadius { arc } => self.arc_radius(arc.handle()),
+            Measurement::ArcLength { arc } => self.arc_length(arc.handle()),
+            Measurement::CircleRadius { circle } => self.circle_radius(circle.handle()),
+            Measurement::ProfileArea {
+                group,
+                workplane,
+                profile,
+            } => Ok(self
+                .region_properties(&self.profiles(&group, &workplane)?, profile, &workplane)?
+                .area),
+            Measurement::ProfilePerimeter {
+                group,
+                workplane,
+                profile,
+            } => Ok(self
+                .region_properties(&self.profiles(&group, &workplane)?, profile, &workplane)?
+                .perimeter),
+        }
+    }
+}
+
+fn workplane_h(workplane: Option<EntityHandle<Workplane>>) -> Slvs_hEntity {
+    workplane.map_or(SLVS_FREE_IN_3D, |workplane| workplane.handle())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Measurements on slvs elements
+////////////////////////////////////////////////////////////////////////////////
+
+impl System {
+    pub(crate) fn pt_pt_distance(
+        &self,
+        point_a: Slvs_hEntity,
+        point_b: Slvs_hEntity,
+        workplane: Slvs_hEntity,
+    ) -> Result<f64, &'static str> {
+        Ok(distance(
+            self.point_projected(point_a, workplane)?,
+            self.point_projected(point_b, workplane)?,
+        ))
+    }
+
+    pub(crate) fn line_vector(
+        &self,
+        line: Slvs_hEntity,
+        workplane: Slvs_hEntity,
+    ) -> Result<Vector3D<f64>, &'static str> {
+        let slvs_line = self.slvs_entity(line)?;
+        let start = Vector3D::from(self.point_projected(slvs_line.point[0], workplane)?);
+        let end = Vector3D::from(self.point_projected(slvs_line.point[1], workplane)?);
+
+        Ok(end - start)
+    }
+
+    pub(crate) fn line_length(
+        &self,
+        line: Slvs_hEntity,
+        workplane: Slvs_hEntity,
+    ) -> Result<f64, &'static str> {
+        Ok(self.line_vector(line, workplane)?.length())
+    }
+
+    // Within a workplane, the distance is signed, positive to the right of the line
+    // when looking from its start towards its end (the same convention as the solver).
+    // Otherwise, it is always positive.
+    pub(crate) fn pt_line_distance(
+        &self,
+        point: Slvs_hEntity,
+        line: Slvs_hEntity,
+        workplane: Slvs_hEntity,
+    ) -> Result<f64, &'static str> {
+        let slvs_line = self.slvs_entity(line)?;
+        let start = Vector3D::from(self.point_projected(slvs_line.point[0], workplane)?);
+        let direction = self.line_vector(line, workplane)?;
+        let offset = Vector3D::from(self.point_projected(point, workplane)?) - start;
+
+        let cross = offset.cross(direction);
+        if workplane == SLVS_FREE_IN_3D {
+            Ok(cross.length() / direction.length())
+        } else {
+            let (_, quaternion) = self.workplane_frame(workplane)?;
+            Ok(cross.dot(Vector3D::from(quaternion_n(quaternion))) / direction.length())
+        }
+    }
+
+    pub(crate) fn pt_plane_distance(
+        &self,
+        point: Slvs_hEntity,
+        plane: Slvs_hEntity,
+    ) -> Result<f64, &'static str> {
+        let (origin, quaternion) = self.workplane_frame(plane)?;
+        let offset = Vector3D::from(self.point_coords(point)?) - Vector3D::from(origin);
+
+        Ok(offset.dot(Vector3D::from(quaternion_n(quaternion))))
+    }
+
+    // The distance between two points, when projected onto a line or normal.
+    pub(crate) fn proj_pt_distance(
+        &self,
+        point_a: Slvs_hEntity,
+        point_b: Slvs_hEntity,
+        target: Slvs_hEntity,
+    ) -> Result<f64, &'static str> {
+        let direction = match self.slvs_entity(target)?.type_ as _ {
+            SLVS_E_LINE_SEGMENT => self.line_vector(target, SLVS_FREE_IN_3D)?,
+            _ => Vector3D::from(quaternion_n(self.normal_quaternion(target)?)),
+        };
+        let offset = Vector3D::from(self.point_coords(point_b)?)
+            - Vector3D::from(self.point_coords(point_a)?);
+
+        Ok(offset.dot(direction.normalize()))
+    }
+
+    pub(crate) fn line_angle(
+        &self,
+        line_a: Slvs_hEntity,
+        line_b: Slvs_hEntity,
+        workplane: Slvs_hEntity,
+    ) -> Result<f64, &'static str> {
+        let vec_a = self.line_vector(line_a, workplane)?;
+        let vec_b = self.line_vector(line_b, workplane)?;
+
+        Ok((vec_a.dot(vec_b) / (vec_a.length() * vec_b.length()))
+            .clamp(-1.0, 1.0)
+            .acos()
+            .to_degrees())
+    }
+
+    pub(crate) fn arc_radius(&self, arc: Slvs_hEntity) -> Result<f64, &'static str> {
+        let slvs_arc = self.slvs_entity(arc)?;
+
+        Ok(distance(
+            self.point_coords(slvs_arc.point[0])?,
+            self.point_coords(slvs_arc.point[1])?,
+        ))
+    }
+
+    // The counter-clockwise angle swept by the arc, in radians. Coincident start
+    // and end points represent a full circle.
+    pub(crate) fn arc_sweep(&self, arc: Slvs_hEntity) -> Result<f64, &'static str> {
+        let slvs_arc = self.slvs_entity(arc)?;
+        let [center, start, end] =
+            [0, 1, 2].map(|i| self.point_coords_on(slvs_arc.point[i], slvs_arc.wrkpl));
+        let (center, start, end) = (center?, start?, end?);
+
+        if distance(start, end) <= SOLVE_TOLERANCE {
+            return Ok(2.0 * PI);
+        }
+
+        let sweep = (end[1] - center[1]).atan2(end[0] - center[0])
+            - (start[1] - center[1]).atan2(start[0] - center[0]);
+        Ok(sweep.rem_euclid(2.0 * PI))
+    }
+
+    pub(crate) fn arc_length(&self, arc: Slvs_hEntity) -> Result<f64, &'static str> {
+        Ok(self.arc_sweep(arc)? * self.arc_radius(arc)?)
+    }
+
+    // The radius of an arc or circle.
+    pub(crate) fn curve_radius(&self, curve: Slvs_hEntity) -> Result<f64, &'static str> {
+        match self.slvs_entity(curve)?.type_ as _ {
+            SLVS_E_ARC_OF_CIRCLE => self.arc_radius(curve),
+            _ => self.circle_radius(curve),
+        }
+    }
+
+    pub(crate) fn circle_radius(&self, circle: Slvs_hEntity) -> Result<f64, &'static str> {
+        let slvs_circle = self.slvs_entity(circle)?;
+        let slvs_distance = self.slvs_entity(slvs_circle.distance)?;
+
+        Ok(self.slvs_param(slvs_distance.param[0])?.val)
+    }
+
+    // The current value of the quantity set by a dimensional constraint, using the
+    // same conventions as the constraint's value.
+    pub(crate) fn constraint_value(&self, c: &Slvs_Constraint) -> Result<f64, &'static str> {
+        match c.type_ as _ {
+            SLVS_C_PT_PT_DISTANCE => self.pt_pt_distance(c.ptA, c.ptB, c.wrkpl),
+            SLVS_C_PT_PLANE_DISTANCE => self.pt_plane_distance(c.ptA, c.entityA),
+            SLVS_C_PT_LINE_DISTANCE => self.pt_line_distance(c.ptA, c.entityA, c.wrkpl),
+            SLVS_C_PROJ_PT_DISTANCE => self.proj_pt_distance(c.ptA, c.ptB, c.entityA),
+            SLVS_C_DIAMETER => Ok(2.0 * self.curve_radius(c.entityA)?),
+            SLVS_C_ANGLE => {
+                let angle = self.line_angle(c.entityA, c.entityB, c.wrkpl)?;
+                Ok(if c.other != 0 { 180.0 - angle } else { angle })
+            }
+            SLVS_C_LENGTH_RATIO => {
+                Ok(self.line_length(c.entityA, c.wrkpl)? / self.line_length(c.entityB, c.wrkpl)?)
+            }
+            SLVS_C_LENGTH_DIFFERENCE => {
+                Ok(self.line_length(c.entityA, c.wrkpl)? - self.line_length(c.entityB, c.wrkpl)?)
+            }
+            SLVS_C_ARC_ARC_LEN_RATIO => {
+                Ok(self.arc_length(c.entityA)? / self.arc_length(c.entityB)?)
+            }
+            SLVS_C_ARC_ARC_DIFFERENCE => {
+                Ok(self.arc_length(c.entityA)? - self.arc_length(c.entityB)?)
+            }
+            SLVS_C_ARC_LINE_LEN_RATIO => {
+                Ok(self.arc_length(c.entityB)? / self.line_length(c.entityA, SLVS_FREE_IN_3D)?)
+            }
+            SLVS_C_ARC_LINE_DIFFERENCE => {
+                Ok(self.arc_length(c.entityB)? - self.line_length(c.entityA, SLVS_FREE_IN_3D)?)
+            }
+            _ => Err("Constraint does not have a value that can be measured."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity::{ArcOfCircle, LineSegment, Normal, Point, Workplane},
+        len_within_tolerance,
+        measurement::Measurement,
+        utils::make_quaternion,
+        System,
+    };
+    use std::f64::consts::PI;
+
+    #[test]
+    fn measure_on_workplane() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [10.0, -20.0, 5.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let g = sys.add_group();
+        let point_a = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 0.0]))
+            .expect("point created");
+        let point_b = sys
+            .sketch(Point::new_on_workplane(g, workplane, [10.0, 0.0]))
+            .expect("point created");
+        let point_c = sys
+            .sketch(Point::new_in_3d(g, [10.0, -10.0, 20.0]))
+            .expect("point created");
+        let line = sys
+            .sketch(LineSegment::new(g, point_a, point_b))
+            .expect("line created");
+
+        len_within_tolerance!(
+            sys.measure(&Measurement::LineLength {
+                line,
+                workplane: None
+            })
+            .expect("measured"),
+            10.0
+        );
+        len_within_tolerance!(
+            sys.measure(&Measurement::PtLineDistance {
+                point: point_c,
+                line,
+                workplane: Some(workplane)
+            })
+            .expect("measured"),
+            10.0
+        );
+        len_within_tolerance!(
+            sys.measure(&Measurement::PtPlaneDistance {
+                point: point_c,
+                plane: workplane
+            })
+            .expect("measured"),
+            15.0
+        );
+
+        let arc_end = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 10.0]))
+            .expect("point created");
+        let arc = sys
+            .sketch(ArcOfCircle::new(g, workplane, point_a, point_b, arc_end))
+            .expect("arc created");
+
+        len_within_tolerance!(
+            sys.measure(&Measurement::ArcLength { arc })
+                .expect("measured"),
+            5.0 * PI
+        );
+    }
+}
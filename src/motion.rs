@@ -0,0 +1,791 @@
+/*!
+The ways in which an under-constrained group can still move.
+
+Each [`FreeMotion`] is one vector from a basis of the null space of the constraint
+Jacobian, so that moving the parameters along it leaves every constraint satisfied to
+first order. There is one motion for each degree of freedom in the group. The motions
+are given as the velocity of each point that moves, in the coordinates chosen by a
+[`VelocityFrame`], which is enough to animate the sketch.
+
+[`System::suggest_dimensions`] uses the same null space to rank the dimensions that
+could be added next, by how much of the remaining motion each one would remove.
+*/
+
+use euclid::default::Vector3D;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+    bindings::{
+        Slvs_Entity, Slvs_hEntity, SLVS_E_ARC_OF_CIRCLE, SLVS_E_CIRCLE, SLVS_E_LINE_SEGMENT,
+        SLVS_E_POINT_IN_2D, SLVS_E_POINT_IN_3D, SLVS_FREE_IN_3D,
+    },
+    constraint::{Angle, AsConstraintHandle, Diameter, PtPtDistance},
+    element::AsHandle,
+    entity::{ArcOfCircle, Circle, EntityHandle, Point, Workplane},
+    group::Group,
+    linalg,
+    utils::{quaternion_u, quaternion_v},
+    System,
+};
+
+// Velocity components smaller than this are treated as zero.
+const VELOCITY_TOLERANCE: f64 = 1e-6;
+
+// Dimensions scoring less than this do not remove any motion.
+const SCORE_TOLERANCE: f64 = 1e-6;
+
+// Angles within this many degrees of parallel are not suggested, since the angle
+// constraint cannot move away from them.
+const PARALLEL_TOLERANCE: f64 = 1e-3;
+
+/// The coordinates that the velocities of a [`FreeMotion`] are given in.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum VelocityFrame {
+    /// The coordinates of each point itself, so that points on a workplane move within
+    /// that workplane, and points in 3d move in 3d.
+    Native,
+    /// The coordinates of a workplane. Any motion out of the workplane is left out.
+    Workplane(EntityHandle<Workplane>),
+    /// Global coordinates in 3d.
+    In3d,
+}
+
+/// The velocity of a point, in the coordinates chosen by a [`VelocityFrame`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Velocity {
+    /// The velocity of a point within the coordinate system of a workplane.
+    OnWorkplane([f64; 2]),
+    /// The velocity of a point in 3d.
+    In3d([f64; 3]),
+}
+
+/// How a single point moves, as part of a [`FreeMotion`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PointMotion {
+    pub point: EntityHandle<Point>,
+    pub velocity: Velocity,
+}
+
+/// A motion of the group that does not violate any of its constraints.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FreeMotion {
+    /// Every point in the group that moves. This is empty if the motion only rotates
+    /// a normal or changes a distance entity.
+    pub points: Vec<PointMotion>,
+}
+
+/// A dimension that can be suggested by [`System::suggest_dimensions`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Dimension {
+    PtPtDistance(PtPtDistance),
+    Angle(Angle),
+    ArcDiameter(Diameter<ArcOfCircle>),
+    CircleDiameter(Diameter<Circle>),
+}
+
+/// A dimension found by [`System::suggest_dimensions`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    /// The dimension, with its value set to the current geometry.
+    pub dimension: Dimension,
+    /// How much of the remaining motion the dimension would remove, from `0.0` when it
+    /// is already held by the other constraints to `1.0` when it only changes along the
+    /// free motions of the group.
+    pub score: f64,
+}
+
+impl System {
+    /// Find a basis for the motions of a group that leave its constraints satisfied.
+    ///
+    /// This is computed around the current parameter values, so the group should be
+    /// solved first. The number of motions returned is the number of degrees of freedom
+    /// in the group. Soft constraints are ignored, and bounded constraints only count
+    /// when they are at one of their limits.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to be analysed.
+    /// * `frame` - The coordinates to give the velocities of the points in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     constraint::PtPtDistance,
+    ///     entity::Point,
+    ///     motion::{Velocity, VelocityFrame},
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g1 = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g1, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    ///
+    /// let g2 = sys.add_group();
+    /// let p = sys
+    ///     .sketch(Point::new_in_3d(g2, [10.0, 0.0, 0.0]))
+    ///     .expect("p created");
+    /// sys.constrain(PtPtDistance::new(g2, origin, p, 10.0, None))
+    ///     .expect("distance constraint added");
+    ///
+    /// // p can only move tangent to a sphere around the origin.
+    /// let motions = sys
+    ///     .free_motions(&g2, VelocityFrame::Native)
+    ///     .expect("motions found");
+    /// assert_eq!(motions.len(), 2);
+    /// for motion in motions {
+    ///     if let Velocity::In3d([x, _, _]) = motion.points[0].velocity {
+    ///         assert_eq!(x, 0.0);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the equations of a constraint cannot be evaluated, or if the
+    /// workplane of `frame` does not exist.
+    pub fn free_motions(
+        &mut self,
+        group: &Group,
+        frame: VelocityFrame,
+    ) -> Result<Vec<FreeMotion>, &'static str> {
+        let constraints = self.active_constraints(group.handle());
+        let equations = self.group_equations(group.handle(), &constraints);
+        let jacobian = self.jacobian(group.handle(), &equations)?;
+        let null_space = linalg::null_space(&jacobian.rows, jacobian.params.len());
+
+        let points: Vec<_> = self
+            .entities
+            .list
+            .iter()
+            .filter(|slvs_entity| {
+                slvs_entity.group == group.handle()
+                    && (SLVS_E_POINT_IN_2D == slvs_entity.type_ as _
+                        || SLVS_E_POINT_IN_3D == slvs_entity.type_ as _)
+            })
+            .collect();
+
+        let mut motions = Vec::new();
+        for vector in &null_space {
+            let mut point_motions = Vec::new();
+            for slvs_entity in &points {
+                let velocity: Vec<f64> = slvs_entity
+                    .param
+                    .iter()
+                    .filter(|&&param| param != 0)
+                    .map(|param| {
+                        jacobian
+                            .params
+                            .iter()
+                            .position(|p| p == param)
+                            .map_or(0.0, |col| vector[col])
+                    })
+                    .collect();
+
+                if velocity
+                    .iter()
+                    .all(|speed| speed.abs() <= VELOCITY_TOLERANCE)
+                {
+                    continue;
+                }
+
+                point_motions.push(PointMotion {
+                    point: EntityHandle::new(slvs_entity.h),
+                    velocity: self.velocity_in(slvs_entity, &velocity, frame)?,
+                });
+            }
+            motions.push(FreeMotion {
+                points: point_motions,
+            });
+        }
+
+        Ok(motions)
+    }
+
+    /// Suggest the dimensions that would remove the most motion from a group.
+    ///
+    /// The candidates are the distances from the points of the group to each other and
+    /// to the points of earlier groups, the diameters of its arcs and circles, and the
+    /// angles between its lines. Each one is scored by how much its value changes along
+    /// the free motions of the group, compared to how much it changes overall, so that
+    /// dimensions which are already fixed by the other constraints are left out. The
+    /// suggestions are sorted from the highest score to the lowest, and can be added
+    /// with [`System::constrain_suggested`].
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to be analysed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{entity::Point, motion::Dimension, System};
+    ///
+    /// let mut sys = System::new();
+    /// let g1 = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g1, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    ///
+    /// let g2 = sys.add_group();
+    /// let p = sys
+    ///     .sketch(Point::new_in_3d(g2, [10.0, 0.0, 0.0]))
+    ///     .expect("p created");
+    ///
+    /// let suggestions = sys.suggest_dimensions(&g2).expect("suggestions found");
+    /// assert_eq!(suggestions.len(), 1);
+    /// if let Dimension::PtPtDistance(distance) = suggestions[0].dimension {
+    ///     assert_eq!(distance.distance, 10.0);
+    /// }
+    ///
+    /// sys.constrain_suggested(&suggestions[0].dimension)
+    ///     .expect("dimension added");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the equations of a constraint cannot be evaluated.
+    pub fn suggest_dimensions(&mut self, group: &Group) -> Result<Vec<Suggestion>, &'static str> {
+        let constraints = self.active_constraints(group.handle());
+        let equations = self.group_equations(group.handle(), &constraints);
+        let jacobian = self.jacobian(group.handle(), &equations)?;
+        let null_space = linalg::null_space(&jacobian.rows, jacobian.params.len());
+
+        if null_space.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let candidates = self.dimension_candidates(group)?;
+        let rows = self.candidate_rows(group, &candidates)?;
+
+        let mut suggestions = Vec::new();
+        for (dimension, row) in candidates.into_iter().zip(&rows) {
+            let total = linalg::norm(row);
+            if total <= VELOCITY_TOLERANCE {
+                continue;
+            }
+
+            let held: Vec<_> = null_space
+                .iter()
+                .map(|vector| linalg::dot(vector, row))
+                .collect();
+            let score = linalg::norm(&held) / total;
+            if score > SCORE_TOLERANCE {
+                suggestions.push(Suggestion { dimension, score });
+            }
+        }
+
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(suggestions)
+    }
+
+    /// Add a dimension found by [`System::suggest_dimensions`] to the system.
+    ///
+    /// # Arguments
+    ///
+    /// * `dimension` - The dimension to be added.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the constraint cannot be added.
+    pub fn constrain_suggested(
+        &mut self,
+        dimension: &Dimension,
+    ) -> Result<Box<dyn AsConstraintHandle>, &'static str> {
+        let h = match *dimension {
+            Dimension::PtPtDistance(data) => self.constrain(data)?.handle(),
+            Dimension::Angle(data) => self.constrain(data)?.handle(),
+            Dimension::ArcDiameter(data) => self.constrain(data)?.handle(),
+            Dimension::CircleDiameter(data) => self.constrain(data)?.handle(),
+        };
+
+        Ok(self.boxed_constraint_handle(*self.slvs_constraint(h)?))
+    }
+
+    // Convert the velocity of a point from its own coordinates into `frame`.
+    fn velocity_in(
+        &self,
+        slvs_point: &Slvs_Entity,
+        velocity: &[f64],
+        frame: VelocityFrame,
+    ) -> Result<Velocity, &'static str> {
+        let in_3d = match *velocity {
+            [u, v] => match frame {
+                VelocityFrame::Native => return Ok(Velocity::OnWorkplane([u, v])),
+                _ => {
+                    let (_, quaternion) = self.workplane_frame(slvs_point.wrkpl)?;
+                    let (axis_u, axis_v) = (quaternion_u(quaternion), quaternion_v(quaternion));
+                    [0, 1, 2].map(|i| u * axis_u[i] + v * axis_v[i])
+                }
+            },
+            [x, y, z] => [x, y, z],
+            _ => unreachable!("points have two or three parameters"),
+        };
+
+        match frame {
+            VelocityFrame::Native | VelocityFrame::In3d => Ok(Velocity::In3d(in_3d)),
+            VelocityFrame::Workplane(workplane) => {
+                let (_, quaternion) = self.workplane_frame(workplane.handle())?;
+                Ok(Velocity::OnWorkplane([
+                    linalg::dot(&in_3d, &quaternion_u(quaternion)),
+                    linalg::dot(&in_3d, &quaternion_v(quaternion)),
+                ]))
+            }
+        }
+    }
+
+    // The gradient of the value of each candidate with respect to the parameters of the
+    // group. The coordinates of the points and the radii of the circles are
+    // differentiated once, and each gradient is found from them by the chain rule.
+    fn candidate_rows(
+        &mut self,
+        group: &Group,
+        candidates: &[Dimension],
+    ) -> Result<Vec<Vec<f64>>, &'static str> {
+        let measured: Vec<(Slvs_hEntity, bool)> = self
+            .entities
+            .list
+            .iter()
+            .filter(|slvs_entity| {
+                slvs_entity.group == group.handle()
+                    && (SLVS_E_POINT_IN_2D == slvs_entity.type_ as _
+                        || SLVS_E_POINT_IN_3D == slvs_entity.type_ as _
+                        || SLVS_E_CIRCLE == slvs_entity.type_ as _)
+            })
+            .map(|slvs_entity| (slvs_entity.h, SLVS_E_CIRCLE == slvs_entity.type_ as _))
+            .collect();
+        let jacobian = self.jacobian_of(group.handle(), |sys| {
+            let mut values = Vec::new();
+            for &(h, is_circle) in &measured {
+                if is_circle {
+                    values.push(sys.circle_radius(h)?);
+                } else {
+                    values.extend(sys.point_coords(h)?);
+                }
+            }
+            Ok(values)
+        })?;
+
+        let mut gradient_rows = jacobian.rows.iter();
+        let gradients: BTreeMap<Slvs_hEntity, Vec<&Vec<f64>>> = measured
+            .iter()
+            .map(|&(h, is_circle)| {
+                let len = if is_circle { 1 } else { 3 };
+                (h, gradient_rows.by_ref().take(len).collect())
+            })
+            .collect();
+        // Entities from earlier groups do not move, and add nothing to the gradient.
+        let add = |row: &mut [f64], h: Slvs_hEntity, weights: &[f64]| {
+            for (weight, gradient_row) in
+                weights.iter().zip(gradients.get(&h).into_iter().flatten())
+            {
+                row.iter_mut()
+                    .zip(gradient_row.iter())
+                    .for_each(|(value, derivative)| *value += weight * derivative);
+            }
+        };
+
+        candidates
+            .iter()
+            .map(|dimension| {
+                let mut row = vec![0.0; jacobian.params.len()];
+                match *dimension {
+                    Dimension::PtPtDistance(data) => {
+                        let (point_a, point_b) = (data.point_a.handle(), data.point_b.handle());
+                        let direction = (Vector3D::from(self.point_coords(point_a)?)
+                            - Vector3D::from(self.point_coords(point_b)?))
+                        .normalize();
+                        add(&mut row, point_a, &direction.to_array());
+                        add(&mut row, point_b, &(-direction).to_array());
+                    }
+                    Dimension::ArcDiameter(data) => {
+                        let slvs_arc = self.slvs_entity(data.arc.handle())?;
+                        let (center, start) = (slvs_arc.point[0], slvs_arc.point[1]);
+                        let direction = (Vector3D::from(self.point_coords(start)?)
+                            - Vector3D::from(self.point_coords(center)?))
+                        .normalize();
+                        add(&mut row, start, &(direction * 2.0).to_array());
+                        add(&mut row, center, &(direction * -2.0).to_array());
+                    }
+                    Dimension::CircleDiameter(data) => add(&mut row, data.arc.handle(), &[2.0]),
+                    Dimension::Angle(data) => {
+                        let workplane = data
+                            .workplane
+                            .map_or(SLVS_FREE_IN_3D, |workplane| workplane.handle());
+                        let lines = [data.line_a.handle(), data.line_b.handle()];
+                        let [vec_a, vec_b] = lines.map(|line| self.line_vector(line, workplane));
+                        let (vec_a, vec_b) = (vec_a?, vec_b?);
+                        let (unit_a, unit_b) = (vec_a.normalize(), vec_b.normalize());
+                        let cos = unit_a.dot(unit_b).clamp(-1.0, 1.0);
+                        let sin = (1.0 - cos * cos).sqrt();
+
+                        // The derivative of the angle between the lines, in radians, with
+                        // respect to each of their direction vectors.
+                        let d_a = (unit_a * cos - unit_b) / (vec_a.length() * sin);
+                        let d_b = (unit_b * cos - unit_a) / (vec_b.length() * sin);
+                        for (line, d) in lines.into_iter().zip([d_a, d_b]) {
+                            let slvs_line = self.slvs_entity(line)?;
+                            add(&mut row, slvs_line.point[1], &d.to_array());
+                            add(&mut row, slvs_line.point[0], &(-d).to_array());
+                        }
+                    }
+                }
+                Ok(row)
+            })
+            .collect()
+    }
+
+    // Every dimension that could be added to the group, with the values of the current
+    // geometry.
+    fn dimension_candidates(&self, group: &Group) -> Result<Vec<Dimension>, &'static str> {
+        let in_group: Vec<_> = self
+            .entities
+            .list
+            .iter()
+            .filter(|slvs_entity| slvs_entity.group == group.handle())
+            .collect();
+
+        // The points of the group, and the points of earlier groups that they can be
+        // measured from.
+        let points: Vec<_> = self
+            .entities
+            .list
+            .iter()
+            .filter(|slvs_entity| {
+                slvs_entity.group <= group.handle()
+                    && (SLVS_E_POINT_IN_2D == slvs_entity.type_ as _
+                        || SLVS_E_POINT_IN_3D == slvs_entity.type_ as _)
+            })
+            .map(|slvs_entity| slvs_entity.h)
+            .collect();
+
+        let mut candidates = Vec::new();
+        for (i, &point_a) in points.iter().enumerate() {
+            for &point_b in &points[i + 1..] {
+                let (slvs_a, slvs_b) = (self.slvs_entity(point_a)?, self.slvs_entity(point_b)?);
+                if slvs_a.group != group.handle() && slvs_b.group != group.handle() {
+                    continue;
+                }
+
+                let workplane = if SLVS_E_POINT_IN_2D == slvs_a.type_ as _
+                    && SLVS_E_POINT_IN_2D == slvs_b.type_ as _
+                    && slvs_a.wrkpl == slvs_b.wrkpl
+                {
+                    Some(EntityHandle::new(slvs_a.wrkpl))
+                } else {
+                    None
+                };
+                let distance = self.pt_pt_distance(
+                    point_a,
+                    point_b,
+                    workplane.map_or(SLVS_FREE_IN_3D, |workplane| workplane.handle()),
+                )?;
+                if distance <= VELOCITY_TOLERANCE {
+                    continue;
+                }
+
+                candidates.push(Dimension::PtPtDistance(PtPtDistance::new(
+                    *group,
+                    EntityHandle::new(point_a),
+                    EntityHandle::new(point_b),
+                    distance,
+                    workplane,
+                )));
+            }
+        }
+
+        for slvs_entity in &in_group {
+            let diameter = 2.0
+                * match slvs_entity.type_ as _ {
+                    SLVS_E_ARC_OF_CIRCLE | SLVS_E_CIRCLE => self.curve_radius(slvs_entity.h)?,
+                    _ => continue,
+                };
+            candidates.push(match slvs_entity.type_ as _ {
+                SLVS_E_ARC_OF_CIRCLE => Dimension::ArcDiameter(Diameter::new(
+                    *group,
+                    EntityHandle::new(slvs_entity.h),
+                    diameter,
+                )),
+                _ => Dimension::CircleDiameter(Diameter::new(
+                    *group,
+                    EntityHandle::new(slvs_entity.h),
+                    diameter,
+                )),
+            });
+        }
+
+        let lines: Vec<_> = in_group
+            .iter()
+            .filter(|slvs_entity| SLVS_E_LINE_SEGMENT == slvs_entity.type_ as _)
+            .collect();
+        for (i, line_a) in lines.iter().enumerate() {
+            for line_b in &lines[i + 1..] {
+                if line_a.wrkpl != line_b.wrkpl {
+                    continue;
+                }
+
+                let angle = self.line_angle(line_a.h, line_b.h, line_a.wrkpl)?;
+                if angle.is_nan()
+                    || angle <= PARALLEL_TOLERANCE
+                    || angle >= 180.0 - PARALLEL_TOLERANCE
+                {
+                    continue;
+                }
+
+                candidates.push(Dimension::Angle(Angle::new(
+                    *group,
+                    EntityHandle::new(line_a.h),
+                    EntityHandle::new(line_b.h),
+                    angle,
+                    (line_a.wrkpl != SLVS_FREE_IN_3D).then(|| EntityHandle::new(line_a.wrkpl)),
+                    false,
+                )));
+            }
+        }
+
+        Ok(candidates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constraint::{Horizontal, PtPtDistance},
+        element::AsHandle,
+        entity::{ArcOfCircle, Circle, Distance, LineSegment, Normal, Point, Workplane},
+        linalg,
+        motion::{Dimension, Velocity, VelocityFrame},
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn sliding_line() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        // A horizontal line of fixed length, which can only slide around the workplane.
+        let g = sys.add_group();
+        let start = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 1.0]))
+            .expect("point created");
+        let end = sys
+            .sketch(Point::new_on_workplane(g, workplane, [10.0, 1.0]))
+            .expect("point created");
+        let line = sys
+            .sketch(LineSegment::new(g, start, end))
+            .expect("line created");
+        sys.constrain(Horizontal::from_line(g, workplane, line))
+            .expect("constraint added");
+        sys.constrain(PtPtDistance::new(g, start, end, 10.0, Some(workplane)))
+            .expect("constraint added");
+
+        let motions = sys
+            .free_motions(&g, VelocityFrame::Native)
+            .expect("motions found");
+        assert_eq!(motions.len(), 2);
+
+        // Both ends of the line always move together.
+        for motion in &motions {
+            assert_eq!(motion.points.len(), 2);
+            match (motion.points[0].velocity, motion.points[1].velocity) {
+                (Velocity::OnWorkplane([u0, v0]), Velocity::OnWorkplane([u1, v1])) => {
+                    assert!((u0 - u1).abs() < 1e-6);
+                    assert!((v0 - v1).abs() < 1e-6);
+                }
+                _ => panic!("points should be on the workplane"),
+            }
+        }
+
+        // The workplane spans y and z, so there is no motion along x in 3d.
+        let motions_3d = sys
+            .free_motions(&g, VelocityFrame::In3d)
+            .expect("motions found");
+        let motions_on_workplane = sys
+            .free_motions(&g, VelocityFrame::Workplane(workplane))
+            .expect("motions found");
+        for ((native, in_3d), on_workplane) in
+            motions.iter().zip(&motions_3d).zip(&motions_on_workplane)
+        {
+            match (
+                native.points[0].velocity,
+                in_3d.points[0].velocity,
+                on_workplane.points[0].velocity,
+            ) {
+                (
+                    Velocity::OnWorkplane([u, v]),
+                    Velocity::In3d([x, y, z]),
+                    Velocity::OnWorkplane([u_wp, v_wp]),
+                ) => {
+                    assert!(x.abs() < 1e-6);
+                    assert!((y - u).abs() < 1e-6);
+                    assert!((z - v).abs() < 1e-6);
+                    assert!((u_wp - u).abs() < 1e-6);
+                    assert!((v_wp - v).abs() < 1e-6);
+                }
+                _ => panic!("velocities should be in the requested frames"),
+            }
+        }
+    }
+
+    #[test]
+    fn suggest_dimensions() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+        let fixed = sys
+            .sketch(Point::new_on_workplane(workplane_g, workplane, [0.0, 0.0]))
+            .expect("point created");
+        sys.sketch(Point::new_on_workplane(workplane_g, workplane, [10.0, 0.0]))
+            .expect("point created");
+
+        // A free circle, with its center and radius left to move.
+        let g = sys.add_group();
+        let center = sys
+            .sketch(Point::new_on_workplane(g, workplane, [3.0, 4.0]))
+            .expect("center created");
+        let radius = sys.sketch(Distance::new(g, 2.0)).expect("radius created");
+        let circle = sys
+            .sketch(Circle::new(g, normal, center, radius))
+            .expect("circle created");
+
+        let suggestions = sys.suggest_dimensions(&g).expect("suggestions found");
+        assert!(suggestions.iter().any(|suggestion| matches!(
+            suggestion.dimension,
+            Dimension::CircleDiameter(diameter) if diameter.arc == circle && diameter.diameter == 4.0
+        )));
+        assert!(suggestions.iter().any(|suggestion| matches!(
+            suggestion.dimension,
+            Dimension::PtPtDistance(distance)
+                if distance.point_a == fixed && (distance.distance - 5.0).abs() < 1e-9
+        )));
+        assert!(suggestions
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+
+        // Each suggestion removes one degree of freedom, and is not suggested again.
+        for dof in [2, 1, 0] {
+            let suggestions = sys.suggest_dimensions(&g).expect("suggestions found");
+            let dimension = suggestions[0].dimension;
+            sys.constrain_suggested(&dimension)
+                .expect("dimension added");
+            assert_eq!(
+                sys.free_motions(&g, VelocityFrame::Native)
+                    .expect("motions found")
+                    .len(),
+                dof
+            );
+            assert!(!sys
+                .suggest_dimensions(&g)
+                .expect("suggestions found")
+                .iter()
+                .any(|suggestion| suggestion.dimension == dimension));
+        }
+        assert!(sys
+            .suggest_dimensions(&g)
+            .expect("suggestions found")
+            .is_empty());
+    }
+
+    #[test]
+    fn candidate_rows_match_constraints() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let g = sys.add_group();
+        let corner = sys
+            .sketch(Point::new_on_workplane(g, workplane, [1.0, 2.0]))
+            .expect("point created");
+        let right = sys
+            .sketch(Point::new_on_workplane(g, workplane, [6.0, 3.0]))
+            .expect("point created");
+        let top = sys
+            .sketch(Point::new_on_workplane(g, workplane, [2.0, 7.0]))
+            .expect("point created");
+        sys.sketch(LineSegment::new(g, corner, right))
+            .expect("line created");
+        sys.sketch(LineSegment::new(g, corner, top))
+            .expect("line created");
+        let center = sys
+            .sketch(Point::new_on_workplane(g, workplane, [6.0, 6.0]))
+            .expect("point created");
+        let arc_end = sys
+            .sketch(Point::new_on_workplane(g, workplane, [9.0, 6.0]))
+            .expect("point created");
+        sys.sketch(ArcOfCircle::new(g, workplane, center, right, arc_end))
+            .expect("arc created");
+        let radius = sys.sketch(Distance::new(g, 2.0)).expect("radius created");
+        sys.sketch(Circle::new(g, normal, top, radius))
+            .expect("circle created");
+        sys.sketch(Point::new_in_3d(g, [4.0, -1.0, 5.0]))
+            .expect("point created");
+
+        let candidates = sys.dimension_candidates(&g).expect("candidates found");
+        let rows = sys.candidate_rows(&g, &candidates).expect("rows found");
+        assert!(candidates
+            .iter()
+            .any(|dimension| matches!(dimension, Dimension::Angle(_))));
+        assert!(candidates
+            .iter()
+            .any(|dimension| matches!(dimension, Dimension::ArcDiameter(_))));
+
+        for (dimension, row) in candidates.iter().zip(&rows) {
+            let slvs_constraint = match *dimension {
+                Dimension::PtPtDistance(data) => sys.slvs_constraint_from(&data, 0),
+                Dimension::Angle(data) => sys.slvs_constraint_from(&data, 0),
+                Dimension::ArcDiameter(data) => sys.slvs_constraint_from(&data, 0),
+                Dimension::CircleDiameter(data) => sys.slvs_constraint_from(&data, 0),
+            }
+            .expect("constraint data found");
+            let gradient = sys
+                .jacobian_of(g.handle(), |sys| sys.constraint_residuals(&slvs_constraint))
+                .expect("gradient found");
+
+            // The residual of a constraint may be scaled differently from its value.
+            let expected = &gradient.rows[0];
+            let cos = linalg::dot(row, expected) / (linalg::norm(row) * linalg::norm(expected));
+            assert!((cos.abs() - 1.0).abs() < 1e-6);
+        }
+    }
+}
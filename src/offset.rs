@@ -0,0 +1,748 @@
+/*!
+Curves at a constant distance from a chain of line segments and arcs.
+
+[`System::offset`] creates a parallel copy of a loop or chain of curves on a workplane,
+such as a [`Profile`][crate::profile::Profile] or [`Chain`][crate::profile::Chain].
+Lines are offset to parallel lines, and arcs to concentric arcs with a larger or
+smaller radius. Where the curves of the chain meet at an angle, the offset curves are
+lengthened or shortened until they meet, or joined by an arc around the corner.
+
+The offset can be linked to the original chain with constraints, so that it follows
+the original when it is moved and the offset's group is solved again.
+*/
+
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+use crate::{
+    bindings::{
+        Slvs_Entity, Slvs_hEntity, SLVS_E_ARC_OF_CIRCLE, SLVS_E_LINE_SEGMENT, SLVS_FREE_IN_3D,
+    },
+    constraint::{
+        ArcLineTangent, AsConstraintHandle, CurveCurveTangent, Diameter, Parallel, PtLineDistance,
+    },
+    element::AsHandle,
+    entity::{ArcOfCircle, AsEntityHandle, EntityHandle, LineSegment, Point, Workplane},
+    fillet::Locus,
+    geometry::{add, scale, sub},
+    group::Group,
+    profile::{find, ProfileEdge},
+    system::SOLVE_TOLERANCE,
+    utils::distance,
+    System,
+};
+
+/// How the offset curves are joined where the curves of the chain meet at an angle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OffsetCorner {
+    /// The offset curves are lengthened or shortened until they meet.
+    Extend,
+    /// Where the offset curves move apart, they are joined by an arc around the
+    /// corner, with the offset distance as its radius. Where they cross, they are
+    /// shortened to meet.
+    Round,
+}
+
+/// The curves created by [`System::offset`].
+#[derive(Debug)]
+pub struct Offset {
+    /// The offset of each curve of the chain, in the same order.
+    pub curves: Vec<Box<dyn AsEntityHandle>>,
+    /// The arcs joining the offset curves at rounded corners.
+    pub corners: Vec<EntityHandle<ArcOfCircle>>,
+    /// The constraints linking the offset to the original chain, if it is linked.
+    pub links: Vec<Box<dyn AsConstraintHandle>>,
+}
+
+// A curve of the chain, followed in the direction of the chain, and its offset.
+struct Piece {
+    slvs_entity: Slvs_Entity,
+    reversed: bool,
+    locus: Locus,
+    // The end points of the offset curve.
+    start: [f64; 2],
+    end: [f64; 2],
+    // The point of the chain at the end of the curve.
+    corner_h: Slvs_hEntity,
+    corner: [f64; 2],
+    // The directions of the chain at the start and end of the curve.
+    tangents: [[f64; 2]; 2],
+    // The counter-clockwise sweep of an arc of the chain, in radians.
+    sweep: Option<f64>,
+}
+
+// How the offsets of two curves that follow each other in the chain are joined.
+enum Joint {
+    // The offsets share an end point. They are smooth if the chain does not turn there.
+    Meet {
+        coords: [f64; 2],
+        smooth: bool,
+    },
+    // The offsets are joined by an arc around the corner.
+    Round {
+        corner_h: Slvs_hEntity,
+        corner: [f64; 2],
+        counter_clockwise: bool,
+    },
+}
+
+// A curve of the offset, in the order it is followed.
+struct Link {
+    h: Slvs_hEntity,
+    // The curve of the chain that this is the offset of, unless it is a corner arc.
+    original_h: Option<Slvs_hEntity>,
+    is_line: bool,
+    // Whether the curve is followed from its end point to its start point.
+    reversed: bool,
+    // The radius of an arc.
+    radius: f64,
+}
+
+impl System {
+    /// Create curves at a constant distance from a loop or chain of line segments and
+    /// arcs on a workplane.
+    ///
+    /// Positive offsets move the curves to the left of the direction that the
+    /// chain is followed in, which is the inside of a counter-clockwise loop. The chain
+    /// is treated as a loop if its last curve ends where its first curve starts.
+    ///
+    /// If `linked` is set, offset lines are held parallel to their originals, at the
+    /// offset distance, and offset arcs share the center point of their originals.
+    /// The radii of arcs are held by diameter constraints, or by tangency to the curves
+    /// they meet smoothly, so the offset follows when the original chain is moved, but
+    /// not when the radius of one of its arcs changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to create the offset in.
+    /// * `chain` - The curves to offset, in order, such as the edges of a
+    ///   [`Profile`][crate::profile::Profile].
+    /// * `offset` - The distance from the chain to its offset.
+    /// * `corner` - How to join the offset curves at corners of the chain.
+    /// * `linked` - Whether to link the offset to the original chain with constraints.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{LineSegment, Normal, Point, Workplane},
+    ///     offset::OffsetCorner,
+    ///     profile::ProfileEdge,
+    ///     system::SolveResult,
+    ///     utils::make_quaternion,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// // Two sides of a square, meeting at a corner.
+    /// let [start, corner, end] = [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0]].map(|coords| {
+    ///     sys.sketch(Point::new_on_workplane(g, workplane, coords))
+    ///         .expect("point created")
+    /// });
+    /// let chain = [(start, corner), (corner, end)].map(|(a, b)| ProfileEdge {
+    ///     entity: Box::new(sys.sketch(LineSegment::new(g, a, b)).expect("line created")),
+    ///     reversed: false,
+    /// });
+    ///
+    /// let g = sys.add_group();
+    /// let offset = sys
+    ///     .offset(&g, &chain, 1.0, OffsetCorner::Extend, true)
+    ///     .expect("offset created");
+    /// assert_eq!(offset.curves.len(), 2);
+    /// assert!(offset.corners.is_empty());
+    ///
+    /// // Each offset line can still slide along itself at its free end.
+    /// assert!(matches!(sys.solve(&g), SolveResult::Ok { dof: 2, .. }));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a curve is not found in the system, if the chain is empty
+    /// or contains curves other than line segments and arcs, if its curves are not all
+    /// on the same workplane, or if the distance is zero or too large for the curves of
+    /// the chain.
+    pub fn offset(
+        &mut self,
+        group: &Group,
+        chain: &[ProfileEdge],
+        offset: f64,
+        corner: OffsetCorner,
+        linked: bool,
+    ) -> Result<Offset, &'static str> {
+        if offset.abs() <= SOLVE_TOLERANCE {
+            return Err("Expected offset distance to be non-zero.");
+        }
+        let first = chain
+            .first()
+            .ok_or("Expected chain to have at least one curve.")?;
+        let workplane_h = self.slvs_entity(first.entity.handle())?.wrkpl;
+        if workplane_h == SLVS_FREE_IN_3D {
+            return Err("Expected chain to lie on a workplane.");
+        }
+        let workplane = EntityHandle::<Workplane>::new(workplane_h);
+
+        let mut pieces = chain
+            .iter()
+            .map(|edge| self.piece(edge, &workplane, offset))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let merged = self.merged_points();
+        let [first_h, last_h] = [
+            travel_ends(&pieces[0])[0],
+            travel_ends(&pieces[pieces.len() - 1])[1],
+        ];
+        let closed = pieces.len() > 1 && find(&merged, first_h) == find(&merged, last_h);
+
+        // Joint `i` joins piece `i` to the piece after it.
+        let joint_count = if closed {
+            pieces.len()
+        } else {
+            pieces.len() - 1
+        };
+        let mut joints = Vec::with_capacity(joint_count);
+        for i in 0..joint_count {
+            let next = (i + 1) % pieces.len();
+            let (a, b) = (&pieces[i], &pieces[next]);
+            let turn = cross(a.tangents[1], b.tangents[0]);
+
+            let joint = if distance(a.end, b.start) <= SOLVE_TOLERANCE {
+                Joint::Meet {
+                    coords: a.end,
+                    smooth: turn.abs() <= SOLVE_TOLERANCE
+                        && dot(a.tangents[1], b.tangents[0]) > 0.0,
+                }
+            } else if corner == OffsetCorner::Round && turn * offset < 0.0 {
+                Joint::Round {
+                    corner_h: a.corner_h,
+                    corner: a.corner,
+                    counter_clockwise: turn > 0.0,
+                }
+            } else {
+                let coords = a
+                    .locus
+                    .meet(&b.locus)
+                    .into_iter()
+                    .min_by(|p, q| distance(*p, a.corner).total_cmp(&distance(*q, a.corner)))
+                    .ok_or("Expected offset curves to meet at each corner of the chain.")?;
+                pieces[i].end = coords;
+                pieces[next].start = coords;
+                Joint::Meet {
+                    coords,
+                    smooth: false,
+                }
+            };
+            joints.push(joint);
+        }
+        if !pieces.iter().all(Piece::keeps_direction) {
+            return Err("Offset distance is too large for the curves of the chain.");
+        }
+
+        // Points shared by the offset curves at joints, then the points at the open
+        // ends of the chain.
+        let mut starts = vec![None; pieces.len()];
+        let mut ends = vec![None; pieces.len()];
+        for (i, joint) in joints.iter().enumerate() {
+            let next = (i + 1) % pieces.len();
+            match joint {
+                Joint::Meet { coords, .. } => {
+                    let point = self.sketch(Point::new_on_workplane(*group, workplane, *coords))?;
+                    ends[i] = Some(point);
+                    starts[next] = Some(point);
+                }
+                Joint::Round { .. } => {
+                    ends[i] = Some(self.sketch(Point::new_on_workplane(
+                        *group,
+                        workplane,
+                        pieces[i].end,
+                    ))?);
+                    starts[next] = Some(self.sketch(Point::new_on_workplane(
+                        *group,
+                        workplane,
+                        pieces[next].start,
+                    ))?);
+                }
+            }
+        }
+        if !closed {
+            let last = pieces.len() - 1;
+            starts[0] =
+                Some(self.sketch(Point::new_on_workplane(*group, workplane, pieces[0].start))?);
+            ends[last] =
+                Some(self.sketch(Point::new_on_workplane(*group, workplane, pieces[last].end))?);
+        }
+        let starts = starts.into_iter().flatten().collect::<Vec<_>>();
+        let ends = ends.into_iter().flatten().collect::<Vec<_>>();
+
+        let mut curves: Vec<Box<dyn AsEntityHandle>> = Vec::new();
+        let mut corners = Vec::new();
+        let mut path = Vec::new();
+        for (i, piece) in pieces.iter().enumerate() {
+            let [start, end] = if piece.reversed {
+                [ends[i], starts[i]]
+            } else {
+                [starts[i], ends[i]]
+            };
+            match piece.locus {
+                Locus::Line { .. } => {
+                    let line = self.sketch(LineSegment::new(*group, start, end))?;
+                    path.push(Link {
+                        h: line.handle(),
+                        original_h: Some(piece.slvs_entity.h),
+                        is_line: true,
+                        reversed: piece.reversed,
+                        radius: 0.0,
+                    });
+                    curves.push(Box::new(line));
+                }
+                Locus::Circle { center, radius } => {
+                    let center = if linked {
+                        EntityHandle::new(piece.slvs_entity.point[0])
+                    } else {
+                        self.sketch(Point::new_on_workplane(*group, workplane, center))?
+                    };
+                    let arc =
+                        self.sketch(ArcOfCircle::new(*group, workplane, center, start, end))?;
+                    path.push(Link {
+                        h: arc.handle(),
+                        original_h: Some(piece.slvs_entity.h),
+                        is_line: false,
+                        reversed: piece.reversed,
+                        radius,
+                    });
+                    curves.push(Box::new(arc));
+                }
+            }
+
+            if let Some(Joint::Round {
+                corner_h,
+                corner,
+                counter_clockwise,
+            }) = joints.get(i)
+            {
+                let center = if linked {
+                    EntityHandle::new(*corner_h)
+                } else {
+                    self.sketch(Point::new_on_workplane(*group, workplane, *corner))?
+                };
+                let next = (i + 1) % pieces.len();
+                let [start, end] = if *counter_clockwise {
+                    [ends[i], starts[next]]
+                } else {
+                    [starts[next], ends[i]]
+                };
+                let arc = self.sketch(ArcOfCircle::new(*group, workplane, center, start, end))?;
+                path.push(Link {
+                    h: arc.handle(),
+                    original_h: None,
+                    is_line: false,
+                    reversed: !counter_clockwise,
+                    radius: offset.abs(),
+                });
+                corners.push(arc);
+            }
+        }
+
+        let links = if linked {
+            let smooth = joints
+                .iter()
+                .flat_map(|joint| match joint {
+                    Joint::Meet { smooth, .. } => vec![*smooth],
+                    Joint::Round { .. } => vec![true, true],
+                })
+                .collect::<Vec<_>>();
+            self.link_offset(group, &workplane, &path, &smooth)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Offset {
+            curves,
+            corners,
+            links,
+        })
+    }
+
+    fn piece(
+        &self,
+        edge: &ProfileEdge,
+        workplane: &EntityHandle<Workplane>,
+        offset: f64,
+    ) -> Result<Piece, &'static str> {
+        let slvs_entity = *self.slvs_entity(edge.entity.handle())?;
+        if slvs_entity.wrkpl != workplane.handle() {
+            return Err("Expected all curves of the chain to lie on the same workplane.");
+        }
+        let reversed = edge.reversed;
+
+        match slvs_entity.type_ as _ {
+            SLVS_E_LINE_SEGMENT => {
+                let line = self.line_geometry_on(&EntityHandle::new(slvs_entity.h), workplane)?;
+                let (start, end) = if reversed {
+                    (line.end, line.start)
+                } else {
+                    (line.start, line.end)
+                };
+                let length = distance(start, end);
+                if length <= SOLVE_TOLERANCE {
+                    return Err("Expected lines of the chain to have non-zero length.");
+                }
+                let direction = scale(sub(end, start), 1.0 / length);
+                let shift = scale([-direction[1], direction[0]], offset);
+
+                Ok(Piece {
+                    slvs_entity,
+                    reversed,
+                    locus: Locus::Line {
+                        point: add(start, shift),
+                        direction,
+                    },
+                    start: add(start, shift),
+                    end: add(end, shift),
+                    corner_h: slvs_entity.point[if reversed { 0 } else { 1 }],
+                    corner: end,
+                    tangents: [direction, direction],
+                    sweep: None,
+                })
+            }
+            SLVS_E_ARC_OF_CIRCLE => {
+                let arc = self.arc_geometry_on(&EntityHandle::new(slvs_entity.h), workplane)?;
+                let (start, end) = if reversed {
+                    (arc.end, arc.start)
+                } else {
+                    (arc.start, arc.end)
+                };
+                // The center is on the left when going counter-clockwise.
+                let radius = if reversed {
+                    arc.radius + offset
+                } else {
+                    arc.radius - offset
+                };
+                if radius <= SOLVE_TOLERANCE {
+                    return Err("Offset distance is too large for the curves of the chain.");
+                }
+                let on_offset = |point: [f64; 2]| {
+                    add(
+                        arc.center,
+                        scale(sub(point, arc.center), radius / arc.radius),
+                    )
+                };
+                let tangent = |point: [f64; 2]| {
+                    let radial = scale(sub(point, arc.center), 1.0 / arc.radius);
+                    let tangent = [-radial[1], radial[0]];
+                    if reversed {
+                        scale(tangent, -1.0)
+                    } else {
+                        tangent
+                    }
+                };
+
+                Ok(Piece {
+                    slvs_entity,
+                    reversed,
+                    locus: Locus::Circle {
+                        center: arc.center,
+                        radius,
+                    },
+                    start: on_offset(start),
+                    end: on_offset(end),
+                    corner_h: slvs_entity.point[if reversed { 1 } else { 2 }],
+                    corner: end,
+                    tangents: [tangent(start), tangent(end)],
+                    sweep: Some(arc.sweep().to_radians()),
+                })
+            }
+            _ => Err("Expected chain to be made of line segments and arcs."),
+        }
+    }
+
+    // Constrain the offset curves, followed in order, to follow the original chain.
+    // `smooth` is whether each curve meets the one after it without turning.
+    //
+    // Where curves meet smoothly, they are held tangent, and a line that is tangent at
+    // an end is not also held at its distance, or parallel if it is tangent at both
+    // ends, since the tangency already fixes it.
+    fn link_offset(
+        &mut self,
+        group: &Group,
+        workplane: &EntityHandle<Workplane>,
+        path: &[Link],
+        smooth: &[bool],
+    ) -> Result<Vec<Box<dyn AsConstraintHandle>>, &'static str> {
+        let mut links: Vec<Box<dyn AsConstraintHandle>> = Vec::new();
+        let mut fixed_ends = vec![0; path.len()];
+        let mut tangent_arcs = vec![false; path.len()];
+
+        for (i, _) in smooth.iter().enumerate().filter(|(_, smooth)| **smooth) {
+            let next = (i + 1) % path.len();
+            let (a, b) = (&path[i], &path[next]);
+            // The arc ends that meet, as `to_end` flags of the tangency constraints.
+            let (a_end, b_end) = (!a.reversed, b.reversed);
+
+            match (a.is_line, b.is_line) {
+                // Lines in line with each other only fix the second line.
+                (true, true) => fixed_ends[next] += 1,
+                (false, true) | (true, false) => {
+                    let (arc, line, to_end) = if a.is_line {
+                        (b, a, b_end)
+                    } else {
+                        (a, b, a_end)
+                    };
+                    links.push(Box::new(self.constrain(ArcLineTangent::new(
+                        *group,
+                        *workplane,
+                        EntityHandle::new(arc.h),
+                        EntityHandle::new(line.h),
+                        to_end,
+                    ))?));
+                    fixed_ends[if a.is_line { i } else { next }] += 1;
+                }
+                (false, false) => {
+                    links.push(Box::new(self.constrain(CurveCurveTangent::new(
+                        *group,
+                        *workplane,
+                        EntityHandle::<ArcOfCircle>::new(a.h),
+                        EntityHandle::<ArcOfCircle>::new(b.h),
+                        a_end,
+                        b_end,
+                    ))?));
+                    tangent_arcs[next] = true;
+                }
+            }
+        }
+
+        for (link, (fixed_ends, tangent_arc)) in
+            path.iter().zip(fixed_ends.into_iter().zip(tangent_arcs))
+        {
+            if link.is_line {
+                let (line, original) = (
+                    EntityHandle::<LineSegment>::new(link.h),
+                    EntityHandle::<LineSegment>::new(
+                        link.original_h
+                            .ok_or("Expected line to have an original.")?,
+                    ),
+                );
+                if fixed_ends < 2 {
+                    links.push(Box::new(self.constrain(Parallel::new(
+                        *group,
+                        line,
+                        original,
+                        Some(*workplane),
+                    ))?));
+                }
+                if fixed_ends == 0 {
+                    let point_h = self.slvs_entity(link.h)?.point[0];
+                    let offset =
+                        self.pt_line_distance(point_h, original.handle(), workplane.handle())?;
+                    links.push(Box::new(self.constrain(PtLineDistance::new(
+                        *group,
+                        EntityHandle::new(point_h),
+                        original,
+                        offset,
+                        Some(*workplane),
+                    ))?));
+                }
+            } else if !tangent_arc {
+                // An arc following another arc smoothly takes its radius from it.
+                links.push(Box::new(self.constrain(Diameter::new(
+                    *group,
+                    EntityHandle::<ArcOfCircle>::new(link.h),
+                    2.0 * link.radius,
+                ))?));
+            }
+        }
+
+        Ok(links)
+    }
+}
+
+impl Piece {
+    // Whether the offset curve still runs the same way as the original, once its ends
+    // have been moved to meet the curves around it.
+    fn keeps_direction(&self) -> bool {
+        match (&self.locus, self.sweep) {
+            (Locus::Line { direction, .. }, _) => {
+                dot(sub(self.end, self.start), *direction) > SOLVE_TOLERANCE
+            }
+            (Locus::Circle { center, .. }, Some(sweep)) => {
+                let (start, end) = (angle_of(*center, self.start), angle_of(*center, self.end));
+                let offset_sweep = if self.reversed {
+                    (start - end).rem_euclid(2.0 * PI)
+                } else {
+                    (end - start).rem_euclid(2.0 * PI)
+                };
+                sweep >= 2.0 * PI - SOLVE_TOLERANCE || (offset_sweep - sweep).abs() < PI
+            }
+            _ => true,
+        }
+    }
+}
+
+// The start and end points of a curve of the chain, in the direction of the chain.
+fn travel_ends(piece: &Piece) -> [Slvs_hEntity; 2] {
+    let points = &piece.slvs_entity.point;
+    let ends = if SLVS_E_ARC_OF_CIRCLE == piece.slvs_entity.type_ as _ {
+        [points[1], points[2]]
+    } else {
+        [points[0], points[1]]
+    };
+    if piece.reversed {
+        [ends[1], ends[0]]
+    } else {
+        ends
+    }
+}
+
+fn angle_of(center: [f64; 2], point: [f64; 2]) -> f64 {
+    (point[1] - center[1]).atan2(point[0] - center[0])
+}
+
+fn dot(a: [f64; 2], b: [f64; 2]) -> f64 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+fn cross(a: [f64; 2], b: [f64; 2]) -> f64 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity::{EntityHandle, LineSegment, Normal, Point, Workplane},
+        len_within_tolerance,
+        offset::OffsetCorner,
+        profile::ProfileEdge,
+        system::SolveResult,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn offset_loops() {
+        let mut sys = System::new();
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let g = sys.add_group();
+        let rectangle = sys
+            .sketch_rectangle(&g, &workplane, [0.0, 0.0], [10.0, 5.0], true)
+            .expect("rectangle created");
+        let slot = sys
+            .sketch_slot(&g, &workplane, [[20.0, 0.0], [30.0, 0.0]], 2.0, true)
+            .expect("slot created");
+        assert!(matches!(sys.solve(&g), SolveResult::Ok { .. }));
+
+        let rectangle_edges = rectangle.lines.map(|line| ProfileEdge {
+            entity: Box::new(line),
+            reversed: false,
+        });
+        let slot_edges = [
+            ProfileEdge {
+                entity: Box::new(slot.lines[0]),
+                reversed: false,
+            },
+            ProfileEdge {
+                entity: Box::new(slot.arcs[0]),
+                reversed: false,
+            },
+            ProfileEdge {
+                entity: Box::new(slot.lines[1]),
+                reversed: false,
+            },
+            ProfileEdge {
+                entity: Box::new(slot.arcs[1]),
+                reversed: false,
+            },
+        ];
+
+        let offset_g = sys.add_group();
+        let rounded = sys
+            .offset(&offset_g, &rectangle_edges, -1.0, OffsetCorner::Round, true)
+            .expect("offset created");
+        assert_eq!(rounded.curves.len(), 4);
+        assert_eq!(rounded.corners.len(), 4);
+        let inner = sys
+            .offset(&offset_g, &rectangle_edges, 1.0, OffsetCorner::Extend, true)
+            .expect("offset created");
+        assert!(inner.corners.is_empty());
+        let around_slot = sys
+            .offset(&offset_g, &slot_edges, -1.0, OffsetCorner::Extend, true)
+            .expect("offset created");
+        assert!(around_slot.corners.is_empty());
+        assert!(sys
+            .offset(
+                &offset_g,
+                &rectangle_edges,
+                3.0,
+                OffsetCorner::Extend,
+                false
+            )
+            .is_err());
+
+        assert!(matches!(
+            sys.solve(&offset_g),
+            SolveResult::Ok { dof: 0, .. }
+        ));
+
+        // Moving the rectangle moves its offsets with it.
+        for point in rectangle.points {
+            sys.update_entity(&point, |point| {
+                if let Point::OnWorkplane { coords, .. } = point {
+                    coords[0] += 5.0;
+                }
+            })
+            .expect("point moved");
+        }
+        assert!(matches!(sys.solve(&g), SolveResult::Ok { .. }));
+        assert!(matches!(
+            sys.solve(&offset_g),
+            SolveResult::Ok { dof: 0, .. }
+        ));
+
+        let bottom = sys
+            .line_geometry_on(
+                &EntityHandle::<LineSegment>::new(inner.curves[0].handle()),
+                &workplane,
+            )
+            .expect("line found");
+        len_within_tolerance!(bottom.start[0], 6.0);
+        len_within_tolerance!(bottom.start[1], 1.0);
+        let corner = sys
+            .arc_geometry_on(&rounded.corners[0], &workplane)
+            .expect("arc found");
+        len_within_tolerance!(corner.center[0], 15.0);
+        len_within_tolerance!(corner.center[1], 0.0);
+        len_within_tolerance!(corner.radius, 1.0);
+        let slot_end = sys
+            .arc_geometry_on(
+                &EntityHandle::new(around_slot.curves[1].handle()),
+                &workplane,
+            )
+            .expect("arc found");
+        len_within_tolerance!(slot_end.radius, 3.0);
+    }
+}
@@ -0,0 +1,298 @@
+/*!
+Optimization of a measurement, subject to the constraints within a group.
+
+An [`Objective`] names a [measurement][crate::measurement::Measurement] to be
+minimized or maximized. The constraints in the group are treated as equality
+constraints, and any remaining degrees of freedom are used as design variables.
+
+The optimizer is a projected gradient descent: every step moves the parameters
+against the gradient of the objective, and the solver is then used to pull them back
+onto the constraints. The step length adapts as the optimization progresses, and the
+optimum is reached once no step can improve the objective any further.
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::Slvs_hParam,
+    element::AsHandle,
+    group::Group,
+    measurement::Measurement,
+    system::{SolveResult, SOLVE_TOLERANCE},
+    System,
+};
+
+// Length of the first step taken, in the same units as the parameters.
+const INITIAL_STEP: f64 = 1.0;
+// Relative step used to compute the gradient by central differences.
+const DIFF_STEP: f64 = 1e-6;
+const MAX_ITERATIONS: usize = 1000;
+
+/// The quantity to be optimized.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Objective {
+    Minimize(Measurement),
+    Maximize(Measurement),
+}
+
+/// Results of [`System::optimize`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OptimizeResult {
+    /// Whether the optimizer found a point where the objective could not be improved
+    /// any further. If `false`, the iteration limit was reached first, which usually
+    /// means the objective is unbounded.
+    pub converged: bool,
+    /// The number of steps attempted.
+    pub iterations: usize,
+    /// The measured value at the optimum.
+    pub value: f64,
+    /// The number of unconstrained degrees of freedom in the group.
+    pub dof: i32,
+}
+
+impl System {
+    /// Minimize or maximize a measurement, while satisfying all constraints in a group.
+    ///
+    /// The group is solved first, and the optimization starts from that solution. When
+    /// complete, the entities in the group are left in their optimal configuration.
+    /// Only local optima are found, so the result may depend on the initial positions
+    /// of the entities.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - Only entities within this group are modified during optimization.
+    /// * `objective` - The measurement to be optimized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     constraint::PtPtDistance,
+    ///     entity::Point,
+    ///     measurement::Measurement,
+    ///     optimize::Objective,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g1 = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g1, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let center = sys
+    ///     .sketch(Point::new_in_3d(g1, [10.0, 0.0, 0.0]))
+    ///     .expect("center created");
+    ///
+    /// // A point that is 5 units away from the center.
+    /// let g2 = sys.add_group();
+    /// let point = sys
+    ///     .sketch(Point::new_in_3d(g2, [12.0, 3.0, 1.0]))
+    ///     .expect("point created");
+    /// sys.constrain(PtPtDistance::new(g2, center, point, 5.0, None))
+    ///     .expect("distance constraint added");
+    ///
+    /// // Move it as far from the origin as possible.
+    /// let result = sys
+    ///     .optimize(
+    ///         &g2,
+    ///         &Objective::Maximize(Measurement::PtPtDistance {
+    ///             point_a: origin,
+    ///             point_b: point,
+    ///             workplane: None,
+    ///         }),
+    ///     )
+    ///     .expect("optimization complete");
+    ///
+    /// assert!(result.converged);
+    /// assert!((result.value - 15.0).abs() < 1e-3);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the measurement cannot be taken, or if the group cannot be
+    /// solved before optimizing.
+    pub fn optimize(
+        &mut self,
+        group: &Group,
+        objective: &Objective,
+    ) -> Result<OptimizeResult, &'static str> {
+        let (measurement, sign) = match objective {
+            Objective::Minimize(measurement) => (measurement, 1.0),
+            Objective::Maximize(measurement) => (measurement, -1.0),
+        };
+
+        let result = self.minimize(group, |sys| Ok(sign * sys.measure(measurement)?))?;
+
+        Ok(OptimizeResult {
+            value: sign * result.value,
+            ..result
+        })
+    }
+
+    // Minimize a function of the system state, over the parameters in a group.
+    pub(crate) fn minimize<F>(
+        &mut self,
+        group: &Group,
+        objective: F,
+    ) -> Result<OptimizeResult, &'static str>
+    where
+        F: Fn(&System) -> Result<f64, &'static str>,
+    {
+        let dof = match self.solve(group) {
+            SolveResult::Ok { dof, .. } => dof,
+            SolveResult::Fail { .. } => return Err("Unable to solve group before optimizing."),
+        };
+        let params: Vec<Slvs_hParam> = self
+            .params
+            .list
+            .iter()
+            .filter(|param| param.group == group.handle())
+            .map(|param| param.h)
+            .collect();
+
+        let mut value = objective(self)?;
+        let mut step = INITIAL_STEP;
+        let mut iterations = 0;
+        let mut converged = false;
+
+        while iterations < MAX_ITERATIONS {
+            iterations += 1;
+
+            let gradient = self.gradient(&params, &objective)?;
+            let gradient_norm = gradient.iter().map(|d| d * d).sum::<f64>().sqrt();
+            if gradient_norm == 0.0 {
+                converged = true;
+                break;
+            }
+
+            let start = self.params.list.clone();
+            for (&param_h, d) in params.iter().zip(&gradient) {
+                self.mut_slvs_param(param_h)?.val -= step * d / gradient_norm;
+            }
+
+            let improved = match self.solve(group) {
+                SolveResult::Ok { .. } => Some(objective(self)?).filter(|&new| new < value),
+                SolveResult::Fail { .. } => None,
+            };
+
+            if let Some(new_value) = improved {
+                value = new_value;
+                step *= 2.0;
+            } else {
+                self.params.list = start;
+                step /= 2.0;
+
+                if step < SOLVE_TOLERANCE {
+                    converged = true;
+                    break;
+                }
+            }
+        }
+
+        Ok(OptimizeResult {
+            converged,
+            iterations,
+            value,
+            dof,
+        })
+    }
+
+    fn gradient<F>(
+        &mut self,
+        params: &[Slvs_hParam],
+        objective: &F,
+    ) -> Result<Vec<f64>, &'static str>
+    where
+        F: Fn(&System) -> Result<f64, &'static str>,
+    {
+        params
+            .iter()
+            .map(|&param_h| {
+                let val = self.slvs_param(param_h)?.val;
+                let diff_step = DIFF_STEP * val.abs().max(1.0);
+
+                self.mut_slvs_param(param_h)?.val = val + diff_step;
+                let plus = objective(self);
+                self.mut_slvs_param(param_h)?.val = val - diff_step;
+                let minus = objective(self);
+                self.mut_slvs_param(param_h)?.val = val;
+
+                Ok((plus? - minus?) / (2.0 * diff_step))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constraint::PtPtDistance,
+        entity::{Normal, Point, Workplane},
+        measurement::Measurement,
+        optimize::Objective,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn gap_on_workplane() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+        let center = sys
+            .sketch(Point::new_on_workplane(workplane_g, workplane, [10.0, 0.0]))
+            .expect("center created");
+
+        // Two points, each 5 units away from the center.
+        let g = sys.add_group();
+        let point_a = sys
+            .sketch(Point::new_on_workplane(g, workplane, [12.0, 1.0]))
+            .expect("point created");
+        sys.constrain(PtPtDistance::new(g, center, point_a, 5.0, Some(workplane)))
+            .expect("constraint added");
+        let point_b = sys
+            .sketch(Point::new_on_workplane(g, workplane, [8.0, 4.0]))
+            .expect("point created");
+        sys.constrain(PtPtDistance::new(g, center, point_b, 5.0, Some(workplane)))
+            .expect("constraint added");
+
+        let result = sys
+            .optimize(
+                &g,
+                &Objective::Minimize(Measurement::PtPtDistance {
+                    point_a: origin,
+                    point_b: point_a,
+                    workplane: Some(workplane),
+                }),
+            )
+            .expect("optimized");
+        assert!(result.converged);
+        assert_eq!(result.dof, 2);
+        assert!((result.value - 5.0).abs() < 1e-3);
+
+        let result = sys
+            .optimize(
+                &g,
+                &Objective::Maximize(Measurement::PtPtDistance {
+                    point_a: origin,
+                    point_b,
+                    workplane: Some(workplane),
+                }),
+            )
+            .expect("optimized");
+        assert!(result.converged);
+        assert!((result.value - 15.0).abs() < 1e-3);
+    }
+}
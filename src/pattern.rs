@@ -0,0 +1,747 @@
+/*!
+Copies of entities, mirrored about a line or repeated in a pattern.
+
+[`System::mirror`] copies points and curves on a workplane to the other side of a line,
+and [`System::pattern_linear`] and [`System::pattern_circular`] repeat them at equal
+steps along a line or around a point. The copies are created in a group and held to
+the entities they were copied from with constraints, so that they follow the originals
+when those are moved and the group is solved again.
+
+Each copy of a curve gets its own copies of the curve's points, and the copies are
+returned in an [`EntityMap`] that is looked up with the handle of the original.
+*/
+
+use std::collections::BTreeMap;
+
+use crate::{
+    bindings::{
+        Slvs_Entity, Slvs_hEntity, SLVS_E_ARC_OF_CIRCLE, SLVS_E_CIRCLE, SLVS_E_CUBIC,
+        SLVS_E_LINE_SEGMENT, SLVS_E_POINT_IN_2D, SLVS_FREE_IN_3D,
+    },
+    constraint::{
+        Angle, ArcArcDifference, AsConstraintHandle, EqPtLnDistances, EqualLengthLines,
+        EqualRadius, ProjPtDistance, SymmetricLine,
+    },
+    element::AsHandle,
+    entity::{
+        ArcOfCircle, AsEntityData, AsEntityHandle, Circle, Cubic, Distance, EntityHandle,
+        LineSegment, Point, Workplane,
+    },
+    geometry::{add, scale, sub},
+    group::Group,
+    system::SOLVE_TOLERANCE,
+    utils::distance,
+    System,
+};
+
+/// The copies made by [`System::mirror`], [`System::pattern_linear`] or
+/// [`System::pattern_circular`], keyed by the entities they were copied from.
+///
+/// Copied curves are built on copies of their points, so the points of the original
+/// curves can be looked up as well.
+#[derive(Debug, Default)]
+pub struct EntityMap(BTreeMap<Slvs_hEntity, Box<dyn AsEntityHandle>>);
+
+impl EntityMap {
+    /// Get the copy of an entity, or `None` if it was not copied.
+    pub fn get<E: AsEntityData + Copy + 'static>(
+        &self,
+        original: &EntityHandle<E>,
+    ) -> Option<EntityHandle<E>> {
+        self.0
+            .get(&original.handle())
+            .and_then(|copy| EntityHandle::try_from(copy).ok())
+    }
+
+    /// All of the copies, ordered by the handles of their originals.
+    pub fn copies(&self) -> impl Iterator<Item = &dyn AsEntityHandle> {
+        self.0.values().map(|copy| copy.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn point(&self, original_h: Slvs_hEntity) -> EntityHandle<Point> {
+        EntityHandle::new(self.0[&original_h].handle())
+    }
+}
+
+/// The copies created by [`System::mirror`].
+#[derive(Debug)]
+pub struct Mirror {
+    pub copies: EntityMap,
+    /// The constraints holding the copies to their originals.
+    pub constraints: Vec<Box<dyn AsConstraintHandle>>,
+}
+
+/// The copies created by [`System::pattern_linear`] or [`System::pattern_circular`].
+#[derive(Debug)]
+pub struct Pattern {
+    /// The copies of each instance of the pattern, going away from the originals.
+    pub instances: Vec<EntityMap>,
+    /// Points and lines that are only created to hold the instances in place. They are
+    /// marked as construction geometry, with [`System::set_construction`].
+    pub construction: Vec<Box<dyn AsEntityHandle>>,
+    /// The constraints holding each instance to the one before it.
+    pub constraints: Vec<Box<dyn AsConstraintHandle>>,
+}
+
+// The entities to copy.
+struct Originals {
+    // The points of all entities, in the order they are found.
+    points: Vec<Slvs_hEntity>,
+    curves: Vec<Slvs_Entity>,
+    // The number of arcs ending at each point. Each arc already holds its end point at
+    // the same distance from its center as its start point, so the copy of the end
+    // point is held by the length of the arc instead of by constraints of its own.
+    arc_ends: BTreeMap<Slvs_hEntity, usize>,
+}
+
+impl Originals {
+    // Whether the copy of a point needs constraints of its own to be held in place.
+    fn is_free(&self, point_h: Slvs_hEntity) -> bool {
+        !self.arc_ends.contains_key(&point_h)
+    }
+}
+
+impl System {
+    /// Copy points and curves on a workplane to the other side of a line.
+    ///
+    /// Each copied point is held symmetric to its original about the line. Copies of
+    /// arcs run in the opposite direction to keep them counter-clockwise, and are held at
+    /// the length of their originals, as copies of circles are held at their radius.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to create the copies in.
+    /// * `entities` - The points, line segments, arcs, circles and cubics to copy.
+    /// * `about_line` - The line to mirror the entities about.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{LineSegment, Normal, Point, Workplane},
+    ///     len_within_tolerance,
+    ///     system::SolveResult,
+    ///     utils::make_quaternion,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// let mut sketch_line = |a, b| {
+    ///     let [a, b] = [a, b].map(|coords| {
+    ///         sys.sketch(Point::new_on_workplane(g, workplane, coords))
+    ///             .expect("point created")
+    ///     });
+    ///     sys.sketch(LineSegment::new(g, a, b)).expect("line created")
+    /// };
+    /// let axis = sketch_line([0.0, 0.0], [0.0, 10.0]);
+    /// let line = sketch_line([2.0, 1.0], [5.0, 4.0]);
+    ///
+    /// let g = sys.add_group();
+    /// let mirror = sys.mirror(&g, &[&line], &axis).expect("line mirrored");
+    /// assert!(matches!(sys.solve(&g), SolveResult::Ok { dof: 0, .. }));
+    ///
+    /// let copy = mirror.copies.get(&line).expect("line copied");
+    /// let copy = sys
+    ///     .line_geometry_on(&copy, &workplane)
+    ///     .expect("geometry found");
+    /// len_within_tolerance!(copy.start[0], -2.0);
+    /// len_within_tolerance!(copy.end[0], -5.0);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an entity or the line is not found in the system, if an
+    /// entity is not a point or curve, or if the entities are not on the workplane of
+    /// the line.
+    pub fn mirror(
+        &mut self,
+        group: &Group,
+        entities: &[&dyn AsEntityHandle],
+        about_line: &EntityHandle<LineSegment>,
+    ) -> Result<Mirror, &'static str> {
+        let workplane = self.workplane_of(about_line.handle())?;
+        let originals = self.originals(entities, &workplane)?;
+        let axis = self.line_geometry_on(about_line, &workplane)?;
+        if axis.length() <= SOLVE_TOLERANCE {
+            return Err("Expected line to mirror about to have non-zero length.");
+        }
+        let direction = axis.direction()?;
+
+        let copies = self.copy_originals(group, &workplane, &originals, true, |coords| {
+            let along = dot(sub(coords, axis.start), direction);
+            let foot = add(axis.start, scale(direction, along));
+            sub(scale(foot, 2.0), coords)
+        })?;
+
+        let mut constraints: Vec<Box<dyn AsConstraintHandle>> = Vec::new();
+        for point_h in &originals.points {
+            let original = EntityHandle::new(*point_h);
+            let copy = copies.point(*point_h);
+            if originals.is_free(*point_h) {
+                constraints.push(Box::new(self.constrain(SymmetricLine::new(
+                    *group,
+                    workplane,
+                    original,
+                    copy,
+                    *about_line,
+                ))?));
+            }
+        }
+        constraints.extend(self.hold_sizes(group, &originals, None, &copies)?);
+
+        Ok(Mirror {
+            copies,
+            constraints,
+        })
+    }
+
+    /// Repeat points and curves on a workplane at equal steps along a line.
+    ///
+    /// Each point of an instance is held at the spacing from the same point of the
+    /// instance before it, in the direction of the line, and at the same distance from
+    /// the line, so the pattern follows when the originals or the line are moved.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to create the copies in.
+    /// * `entities` - The points, line segments, arcs, circles and cubics to copy.
+    /// * `along` - The line giving the direction of the pattern, from its start point
+    ///   to its end point.
+    /// * `spacing` - The distance between instances. Negative spacings repeat the
+    ///   entities in the opposite direction of the line.
+    /// * `count` - The number of copies to create, not counting the originals.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an entity or the line is not found in the system, if an
+    /// entity is not a point or curve, if the entities are not on the workplane of the
+    /// line, if the line has zero length, or if the spacing or count are zero.
+    pub fn pattern_linear(
+        &mut self,
+        group: &Group,
+        entities: &[&dyn AsEntityHandle],
+        along: &EntityHandle<LineSegment>,
+        spacing: f64,
+        count: usize,
+    ) -> Result<Pattern, &'static str> {
+        if count == 0 {
+            return Err("Expected pattern to have at least one copy.");
+        }
+        if spacing.abs() <= SOLVE_TOLERANCE {
+            return Err("Expected pattern spacing to be non-zero.");
+        }
+        let workplane = self.workplane_of(along.handle())?;
+        let originals = self.originals(entities, &workplane)?;
+        let line = self.line_geometry_on(along, &workplane)?;
+        if line.length() <= SOLVE_TOLERANCE {
+            return Err("Expected line of the pattern to have non-zero length.");
+        }
+        let step = scale(line.direction()?, spacing);
+
+        let mut instances: Vec<EntityMap> = Vec::with_capacity(count);
+        let mut constraints: Vec<Box<dyn AsConstraintHandle>> = Vec::new();
+        for k in 1..=count {
+            let offset = scale(step, k as f64);
+            let copies = self.copy_originals(group, &workplane, &originals, false, |coords| {
+                add(coords, offset)
+            })?;
+
+            for point_h in &originals.points {
+                let previous = instances
+                    .last()
+                    .map_or(EntityHandle::new(*point_h), |previous| {
+                        previous.point(*point_h)
+                    });
+                let copy = copies.point(*point_h);
+                if originals.is_free(*point_h) {
+                    constraints.push(Box::new(self.constrain(ProjPtDistance::new(
+                        *group, previous, copy, *along, spacing,
+                    ))?));
+                    constraints.push(Box::new(self.constrain(EqPtLnDistances::new(
+                        *group,
+                        *along,
+                        previous,
+                        *along,
+                        copy,
+                        Some(workplane),
+                    ))?));
+                }
+            }
+            constraints.extend(self.hold_sizes(group, &originals, instances.last(), &copies)?);
+            instances.push(copies);
+        }
+
+        Ok(Pattern {
+            instances,
+            construction: Vec::new(),
+            constraints,
+        })
+    }
+
+    /// Repeat points and curves on a workplane at equal angles around a point.
+    ///
+    /// A turn is the same as mirroring about two lines through the center, at half the
+    /// angle of the turn from each other. The originals are mirrored once into
+    /// construction points, about a construction line from the center through their
+    /// first point away from the center. Each instance is then a mirror of those
+    /// points, about another construction line from the center, at half the angle from
+    /// the line of the instance before it. This holds the pattern in place, so that it
+    /// follows when the originals or the center are moved, without the copies having to
+    /// be joined by lines of their own.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to create the copies in.
+    /// * `entities` - The points, line segments, arcs, circles and cubics to copy.
+    /// * `center` - The point to turn the copies around.
+    /// * `angle` - The angle between instances, in degrees, counter-clockwise on the
+    ///   workplane of the center.
+    /// * `count` - The number of copies to create, not counting the originals.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an entity or the center is not found in the system, if an
+    /// entity is not a point or curve, if the entities are not on the workplane of the
+    /// center, if they have no point away from the center, or if the count is zero or
+    /// the angle is a whole number of turns.
+    pub fn pattern_circular(
+        &mut self,
+        group: &Group,
+        entities: &[&dyn AsEntityHandle],
+        center: &EntityHandle<Point>,
+        angle: f64,
+        count: usize,
+    ) -> Result<Pattern, &'static str> {
+        if count == 0 {
+            return Err("Expected pattern to have at least one copy.");
+        }
+        if (angle / 360.0 - (angle / 360.0).round()).abs() <= SOLVE_TOLERANCE {
+            return Err("Expected pattern angle not to be a whole number of turns.");
+        }
+        let workplane = self.workplane_of(center.handle())?;
+        let originals = self.originals(entities, &workplane)?;
+        let pivot = self.point_coords_on(center.handle(), workplane.handle())?;
+
+        let mut anchor = None;
+        for point_h in &originals.points {
+            let coords = self.point_coords_on(*point_h, workplane.handle())?;
+            if distance(coords, pivot) > SOLVE_TOLERANCE {
+                anchor = Some((EntityHandle::new(*point_h), coords));
+                break;
+            }
+        }
+        let (anchor, anchor_coords) =
+            anchor.ok_or("Expected entities to have a point away from the center.")?;
+        let radial = sub(anchor_coords, pivot);
+        let first_axis = self.sketch(LineSegment::new(*group, *center, anchor))?;
+
+        let mut construction: Vec<Box<dyn AsEntityHandle>> = vec![Box::new(first_axis)];
+        let mut constraints: Vec<Box<dyn AsConstraintHandle>> = Vec::new();
+
+        // The originals mirrored about the first axis.
+        let mut mirrored = BTreeMap::new();
+        for point_h in &originals.points {
+            let coords = self.point_coords_on(*point_h, workplane.handle())?;
+            let along = dot(sub(coords, pivot), radial) / dot(radial, radial);
+            let foot = add(pivot, scale(radial, along));
+            let point = self.sketch(Point::new_on_workplane(
+                *group,
+                workplane,
+                sub(scale(foot, 2.0), coords),
+            ))?;
+            constraints.push(Box::new(self.constrain(SymmetricLine::new(
+                *group,
+                workplane,
+                EntityHandle::new(*point_h),
+                point,
+                first_axis,
+            ))?));
+            construction.push(Box::new(point));
+            mirrored.insert(*point_h, point);
+        }
+
+        let mut instances: Vec<EntityMap> = Vec::with_capacity(count);
+        let mut previous_axis = first_axis;
+        for k in 1..=count {
+            let turn = (angle * k as f64).to_radians();
+            let end = self.sketch(Point::new_on_workplane(
+                *group,
+                workplane,
+                add(pivot, rotate(radial, turn / 2.0)),
+            ))?;
+            let axis = self.sketch(LineSegment::new(*group, *center, end))?;
+            constraints.push(Box::new(self.constrain(Angle::new(
+                *group,
+                previous_axis,
+                axis,
+                angle / 2.0,
+                Some(workplane),
+                false,
+            ))?));
+            constraints.push(Box::new(self.constrain(EqualLengthLines::new(
+                *group,
+                previous_axis,
+                axis,
+                Some(workplane),
+            ))?));
+            construction.push(Box::new(end));
+            construction.push(Box::new(axis));
+
+            let copies = self.copy_originals(group, &workplane, &originals, false, |coords| {
+                add(pivot, rotate(sub(coords, pivot), turn))
+            })?;
+            for point_h in &originals.points {
+                let copy = copies.point(*point_h);
+                if originals.is_free(*point_h) {
+                    constraints.push(Box::new(self.constrain(SymmetricLine::new(
+                        *group,
+                        workplane,
+                        mirrored[point_h],
+                        copy,
+                        axis,
+                    ))?));
+                }
+            }
+            constraints.extend(self.hold_sizes(group, &originals, instances.last(), &copies)?);
+            instances.push(copies);
+            previous_axis = axis;
+        }
+
+        for entity in &construction {
+            self.set_construction(entity.as_ref(), true)?;
+        }
+
+        Ok(Pattern {
+            instances,
+            construction,
+            constraints,
+        })
+    }
+
+    // The workplane that an entity is on.
+    fn workplane_of(&self, h: Slvs_hEntity) -> Result<EntityHandle<Workplane>, &'static str> {
+        let workplane_h = self.slvs_entity(h)?.wrkpl;
+        if workplane_h == SLVS_FREE_IN_3D {
+            return Err("Expected entity to lie on a workplane.");
+        }
+        Ok(EntityHandle::new(workplane_h))
+    }
+
+    fn originals(
+        &self,
+        entities: &[&dyn AsEntityHandle],
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<Originals, &'static str> {
+        let mut originals = Originals {
+            points: Vec::new(),
+            curves: Vec::new(),
+            arc_ends: BTreeMap::new(),
+        };
+
+        for entity in entities {
+            let slvs_entity = *self.slvs_entity(entity.handle())?;
+            let point_count =
+                match slvs_entity.type_ as _ {
+                    SLVS_E_POINT_IN_2D => 0,
+                    SLVS_E_CIRCLE => 1,
+                    SLVS_E_LINE_SEGMENT => 2,
+                    SLVS_E_ARC_OF_CIRCLE => 3,
+                    SLVS_E_CUBIC => 4,
+                    _ => return Err(
+                        "Expected entities to be points, line segments, arcs, circles or cubics.",
+                    ),
+                };
+            let points = if point_count == 0 {
+                vec![slvs_entity.h]
+            } else {
+                if originals
+                    .curves
+                    .iter()
+                    .any(|curve| curve.h == slvs_entity.h)
+                {
+                    continue;
+                }
+                originals.curves.push(slvs_entity);
+                slvs_entity.point[..point_count].to_vec()
+            };
+            if SLVS_E_ARC_OF_CIRCLE == slvs_entity.type_ as _ {
+                *originals.arc_ends.entry(slvs_entity.point[2]).or_default() += 1;
+            }
+
+            for point_h in points {
+                let slvs_point = self.slvs_entity(point_h)?;
+                if SLVS_E_POINT_IN_2D != slvs_point.type_ as _
+                    || slvs_point.wrkpl != workplane.handle()
+                {
+                    return Err("Expected entities to lie on the same workplane.");
+                }
+                if !originals.points.contains(&point_h) {
+                    originals.points.push(point_h);
+                }
+            }
+        }
+
+        Ok(originals)
+    }
+
+    // Copy the points of the originals with their coordinates transformed, then the
+    // curves on the copied points. Mirrored arcs swap their end points to stay
+    // counter-clockwise.
+    fn copy_originals(
+        &mut self,
+        group: &Group,
+        workplane: &EntityHandle<Workplane>,
+        originals: &Originals,
+        mirrored: bool,
+        transform: impl Fn([f64; 2]) -> [f64; 2],
+    ) -> Result<EntityMap, &'static str> {
+        let mut copies = EntityMap::default();
+
+        for point_h in &originals.points {
+            let coords = transform(self.point_coords_on(*point_h, workplane.handle())?);
+            let point = self.sketch(Point::new_on_workplane(*group, *workplane, coords))?;
+            copies.0.insert(*point_h, Box::new(point));
+        }
+
+        for curve in &originals.curves {
+            let [a, b, c, d] = curve.point.map(|point_h| {
+                if point_h == 0 {
+                    EntityHandle::new(0)
+                } else {
+                    copies.point(point_h)
+                }
+            });
+            let copy: Box<dyn AsEntityHandle> = match curve.type_ as _ {
+                SLVS_E_LINE_SEGMENT => Box::new(self.sketch(LineSegment::new(*group, a, b))?),
+                SLVS_E_ARC_OF_CIRCLE => {
+                    let [start, end] = if mirrored { [c, b] } else { [b, c] };
+                    Box::new(self.sketch(ArcOfCircle::new(*group, *workplane, a, start, end))?)
+                }
+                SLVS_E_CUBIC => Box::new(self.sketch(Cubic::new(*group, a, b, c, d))?),
+                _ => {
+                    let radius = self
+                        .circle_geometry_on(&EntityHandle::new(curve.h), workplane)?
+                        .radius;
+                    let radius = self.sketch(Distance::new(*group, radius))?;
+                    Box::new(self.sketch(Circle::new(
+                        *group,
+                        EntityHandle::new(curve.normal),
+                        a,
+                        radius,
+                    ))?)
+                }
+            };
+            copies.0.insert(curve.h, copy);
+        }
+
+        Ok(copies)
+    }
+
+    // Hold copied circles at the radius, and copied arcs at the length, of the curves
+    // they were copied from, in the instance before, or in the originals if there is
+    // none. An arc that ends where another arc ends is held by the other arc instead.
+    fn hold_sizes(
+        &mut self,
+        group: &Group,
+        originals: &Originals,
+        previous: Option<&EntityMap>,
+        copies: &EntityMap,
+    ) -> Result<Vec<Box<dyn AsConstraintHandle>>, &'static str> {
+        let mut constraints: Vec<Box<dyn AsConstraintHandle>> = Vec::new();
+
+        for curve in &originals.curves {
+            match curve.type_ as _ {
+                SLVS_E_CIRCLE => {
+                    let original = EntityHandle::<Circle>::new(curve.h);
+                    let before =
+                        previous.map_or(Some(original), |previous| previous.get(&original));
+                    if let (Some(before), Some(copy)) = (before, copies.get(&original)) {
+                        constraints.push(Box::new(
+                            self.constrain(EqualRadius::new(*group, before, copy))?,
+                        ));
+                    }
+                }
+                SLVS_E_ARC_OF_CIRCLE if originals.arc_ends[&curve.point[2]] == 1 => {
+                    let original = EntityHandle::<ArcOfCircle>::new(curve.h);
+                    let before =
+                        previous.map_or(Some(original), |previous| previous.get(&original));
+                    if let (Some(before), Some(copy)) = (before, copies.get(&original)) {
+                        constraints.push(Box::new(
+                            self.constrain(ArcArcDifference::new(*group, before, copy, 0.0))?,
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(constraints)
+    }
+}
+
+fn dot(a: [f64; 2], b: [f64; 2]) -> f64 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+fn rotate(v: [f64; 2], angle: f64) -> [f64; 2] {
+    let (sin, cos) = angle.sin_cos();
+    [v[0] * cos - v[1] * sin, v[0] * sin + v[1] * cos]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity::{Circle, Distance, EntityHandle, LineSegment, Normal, Point, Workplane},
+        len_within_tolerance,
+        system::SolveResult,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn mirror_and_patterns() {
+        let mut sys = System::new();
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let g = sys.add_group();
+        let slot = sys
+            .sketch_slot(&g, &workplane, [[0.0, 0.0], [10.0, 0.0]], 2.0, true)
+            .expect("slot created");
+        let center = sys
+            .sketch(Point::new_on_workplane(g, workplane, [5.0, 10.0]))
+            .expect("point created");
+        let radius = sys.sketch(Distance::new(g, 1.0)).expect("radius created");
+        let circle = sys
+            .sketch(Circle::new(g, normal, center, radius))
+            .expect("circle created");
+        let [axis_start, axis_end, pivot] =
+            [[-5.0, 0.0], [-5.0, 10.0], [0.0, -10.0]].map(|coords| {
+                sys.sketch(Point::new_on_workplane(g, workplane, coords))
+                    .expect("point created")
+            });
+        let axis = sys
+            .sketch(LineSegment::new(g, axis_start, axis_end))
+            .expect("line created");
+        assert!(matches!(sys.solve(&g), SolveResult::Ok { .. }));
+
+        let entities = [
+            &slot.lines[0] as _,
+            &slot.arcs[0] as _,
+            &slot.lines[1] as _,
+            &slot.arcs[1] as _,
+            &circle as _,
+        ];
+
+        let mirror_g = sys.add_group();
+        let mirror = sys
+            .mirror(&mirror_g, &entities, &axis)
+            .expect("entities mirrored");
+        assert_eq!(mirror.copies.len(), 12);
+        let linear_g = sys.add_group();
+        let linear = sys
+            .pattern_linear(&linear_g, &entities, &axis, 6.0, 2)
+            .expect("pattern created");
+        assert_eq!(linear.instances.len(), 2);
+        assert!(sys
+            .pattern_linear(&linear_g, &entities, &axis, 6.0, 0)
+            .is_err());
+        let circular_g = sys.add_group();
+        let circular = sys
+            .pattern_circular(&circular_g, &entities, &pivot, 90.0, 3)
+            .expect("pattern created");
+        assert_eq!(circular.instances.len(), 3);
+        assert!(circular
+            .construction
+            .iter()
+            .all(|entity| sys.is_construction(entity.as_ref())));
+        let profiles = sys
+            .profiles(&circular_g, &workplane)
+            .expect("profiles found");
+        assert_eq!(profiles.loops.len(), 6);
+        assert!(profiles.chains.is_empty());
+        assert!(sys
+            .pattern_circular(&circular_g, &entities, &pivot, 360.0, 1)
+            .is_err());
+
+        // Moving the circle moves its copies with it.
+        sys.update_entity(&center, |point| {
+            if let Point::OnWorkplane { coords, .. } = point {
+                coords[0] += 1.0;
+            }
+        })
+        .expect("point moved");
+        assert!(matches!(sys.solve(&g), SolveResult::Ok { .. }));
+        for group in [&mirror_g, &linear_g, &circular_g] {
+            assert!(matches!(sys.solve(group), SolveResult::Ok { dof: 0, .. }));
+        }
+
+        let circle_at = |sys: &System, copy: EntityHandle<Circle>| {
+            let geometry = sys
+                .circle_geometry_on(&copy, &workplane)
+                .expect("circle found");
+            len_within_tolerance!(geometry.radius, 1.0);
+            geometry.center
+        };
+        let mirrored = circle_at(&sys, mirror.copies.get(&circle).expect("circle copied"));
+        len_within_tolerance!(mirrored[0], -16.0);
+        len_within_tolerance!(mirrored[1], 10.0);
+        let last = circle_at(
+            &sys,
+            linear.instances[1].get(&circle).expect("circle copied"),
+        );
+        len_within_tolerance!(last[0], 6.0);
+        len_within_tolerance!(last[1], 22.0);
+        let turned = circle_at(
+            &sys,
+            circular.instances[0].get(&circle).expect("circle copied"),
+        );
+        len_within_tolerance!(turned[0], -20.0);
+        len_within_tolerance!(turned[1], -4.0);
+        let opposite = circle_at(
+            &sys,
+            circular.instances[1].get(&circle).expect("circle copied"),
+        );
+        len_within_tolerance!(opposite[0], -6.0);
+        len_within_tolerance!(opposite[1], -30.0);
+
+        let arc = mirror.copies.get(&slot.arcs[1]).expect("arc copied");
+        let arc = sys.arc_geometry_on(&arc, &workplane).expect("arc found");
+        len_within_tolerance!(arc.center[0], -10.0);
+        len_within_tolerance!(arc.radius, 2.0);
+        len_within_tolerance!(arc.sweep(), 180.0);
+    }
+}
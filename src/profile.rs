@@ -0,0 +1,479 @@
+/*!
+Closed loops and open chains of curves in a sketch.
+
+[`System::profiles`] joins the line segments, arcs and cubic curves in a group end to
+end, where they share an end point or where their end points are held together by a
+[`PointsCoincident`][crate::constraint::PointsCoincident] constraint. Curves joined in
+a ring make a closed loop, which can be extruded or cut. Circles, and arcs whose start
+and end points coincide, are loops on their own.
+
+Where more than two curves meet at a point, the curves between such points are given
+as open chains, along with the chains that end at a point used by a single curve.
+
+Curves marked with [`System::set_construction`] only guide the rest of the sketch, and
+are left out of the profiles.
+*/
+
+use std::collections::BTreeMap;
+
+use crate::{
+    bindings::{
+        Slvs_Entity, Slvs_hEntity, SLVS_C_POINTS_COINCIDENT, SLVS_E_ARC_OF_CIRCLE, SLVS_E_CIRCLE,
+        SLVS_E_CUBIC, SLVS_E_LINE_SEGMENT,
+    },
+    element::AsHandle,
+    entity::{AsEntityHandle, EntityHandle, Point, Workplane},
+    geometry::{in_polygon, signed_area},
+    group::Group,
+    tessellation::Tessellation,
+    System,
+};
+
+/// The direction that a closed loop goes around, in the coordinates of a workplane.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    CounterClockwise,
+    Clockwise,
+}
+
+/// A curve in a loop or chain.
+#[derive(Debug)]
+pub struct ProfileEdge {
+    pub entity: Box<dyn AsEntityHandle>,
+    /// Whether the curve is followed from its end point to its start point.
+    pub reversed: bool,
+}
+
+/// A closed loop of curves, in the order they are followed.
+#[derive(Debug)]
+pub struct Profile {
+    pub edges: Vec<ProfileEdge>,
+    pub orientation: Orientation,
+    /// The index in [`Profiles::loops`] of the smallest loop around this one.
+    pub parent: Option<usize>,
+    /// The number of loops around this one.
+    pub depth: usize,
+}
+
+impl Profile {
+    /// Whether the loop is a hole in the loop around it, rather than an outer
+    /// boundary. Loops inside a hole are outer boundaries again.
+    pub fn is_hole(&self) -> bool {
+        self.depth % 2 == 1
+    }
+}
+
+/// An open chain of curves, in the order they are followed.
+#[derive(Debug)]
+pub struct Chain {
+    pub edges: Vec<ProfileEdge>,
+}
+
+/// Results of [`System::profiles`].
+#[derive(Debug)]
+pub struct Profiles {
+    pub loops: Vec<Profile>,
+    pub chains: Vec<Chain>,
+    /// End points that are not joined to any other curve.
+    pub dangling: Vec<EntityHandle<Point>>,
+}
+
+// A curve between two end points, which are identified by the point they are merged
+// into.
+struct Edge {
+    slvs_entity: Slvs_Entity,
+    ends: [Slvs_hEntity; 2],
+    nodes: [Slvs_hEntity; 2],
+}
+
+impl System {
+    /// Find the closed loops and open chains formed by the curves in a group.
+    ///
+    /// Loops are oriented and nested in the coordinates of `workplane`. A loop inside
+    /// an outer boundary is a hole in it.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group that the curves belong to.
+    /// * `workplane` - The workplane that the loops are oriented in. Curves that are
+    ///   not on the workplane are projected onto it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{LineSegment, Normal, Point, Workplane},
+    ///     profile::Orientation,
+    ///     utils::make_quaternion,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// let g = sys.add_group();
+    /// let corners = [[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]].map(|coords| {
+    ///     sys.sketch(Point::new_on_workplane(g, workplane, coords))
+    ///         .expect("point created")
+    /// });
+    /// for (start, end) in [(0, 1), (2, 1), (2, 0)] {
+    ///     sys.sketch(LineSegment::new(g, corners[start], corners[end]))
+    ///         .expect("line created");
+    /// }
+    ///
+    /// let profiles = sys.profiles(&g, &workplane).expect("profiles found");
+    /// assert_eq!(profiles.loops.len(), 1);
+    /// assert_eq!(profiles.loops[0].orientation, Orientation::CounterClockwise);
+    /// // The second line is followed backwards.
+    /// assert!(profiles.loops[0].edges[1].reversed);
+    /// assert!(profiles.chains.is_empty());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the workplane is not found in the system, or if the
+    /// geometry of a curve cannot be resolved.
+    pub fn profiles(
+        &self,
+        group: &Group,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<Profiles, &'static str> {
+        let mut loops: Vec<Vec<ProfileEdge>> = Vec::new();
+        let mut edges: Vec<Edge> = Vec::new();
+
+        for slvs_entity in self.entities.list.iter().filter(|slvs_entity| {
+            slvs_entity.group == group.handle() && !self.construction.contains(&slvs_entity.h)
+        }) {
+            let ends = match slvs_entity.type_ as _ {
+                SLVS_E_LINE_SEGMENT => [slvs_entity.point[0], slvs_entity.point[1]],
+                SLVS_E_ARC_OF_CIRCLE => [slvs_entity.point[1], slvs_entity.point[2]],
+                SLVS_E_CUBIC => [slvs_entity.point[0], slvs_entity.point[3]],
+                SLVS_E_CIRCLE => {
+                    loops.push(vec![ProfileEdge {
+                        entity: (*slvs_entity).into(),
+                        reversed: false,
+                    }]);
+                    continue;
+                }
+                _ => continue,
+            };
+            edges.push(Edge {
+                slvs_entity: *slvs_entity,
+                ends,
+                nodes: ends,
+            });
+        }
+
+        let merged = self.merged_points();
+        for edge in &mut edges {
+            edge.nodes = edge.ends.map(|end| find(&merged, end));
+        }
+
+        let mut incidences: BTreeMap<Slvs_hEntity, Vec<(usize, usize)>> = BTreeMap::new();
+        for (ix, edge) in edges.iter().enumerate() {
+            for end in 0..2 {
+                incidences
+                    .entry(edge.nodes[end])
+                    .or_default()
+                    .push((ix, end));
+            }
+        }
+
+        let mut visited = vec![false; edges.len()];
+
+        // Chains run between points where the curves do not simply join in pairs.
+        let mut chains = Vec::new();
+        let mut dangling = Vec::new();
+        for incident in incidences.values().filter(|incident| incident.len() != 2) {
+            if let [(ix, end)] = incident.as_slice() {
+                dangling.push(EntityHandle::new(edges[*ix].ends[*end]));
+            }
+            for &(ix, end) in incident {
+                if !visited[ix] {
+                    chains.push(Chain {
+                        edges: walk(&edges, &incidences, &mut visited, ix, end),
+                    });
+                }
+            }
+        }
+        for ix in 0..edges.len() {
+            if !visited[ix] {
+                loops.push(walk(&edges, &incidences, &mut visited, ix, 0));
+            }
+        }
+
+        let tessellation = Tessellation::default();
+        let polygons = loops
+            .iter()
+            .map(|profile_edges| {
+                let mut polygon: Vec<[f64; 2]> = Vec::new();
+                for edge in profile_edges {
+                    let mut points =
+                        self.tessellate_on(edge.entity.handle(), workplane, &tessellation)?;
+                    if edge.reversed {
+                        points.reverse();
+                    }
+                    let skip = usize::from(!polygon.is_empty());
+                    polygon.extend(points.into_iter().skip(skip));
+                }
+                polygon.pop();
+                Ok(polygon)
+            })
+            .collect::<Result<Vec<_>, &'static str>>()?;
+        let areas: Vec<f64> = polygons
+            .iter()
+            .map(|polygon| signed_area(polygon))
+            .collect();
+
+        let loops = loops
+            .into_iter()
+            .enumerate()
+            .map(|(ix, edges)| {
+                // Loops around this one are larger, and contain its first corner.
+                let around: Vec<usize> = (0..polygons.len())
+                    .filter(|&other| {
+                        other != ix
+                            && areas[other].abs() > areas[ix].abs()
+                            && polygons[ix]
+                                .first()
+                                .is_some_and(|&corner| in_polygon(&polygons[other], corner))
+                    })
+                    .collect();
+
+                Profile {
+                    edges,
+                    orientation: if areas[ix] < 0.0 {
+                        Orientation::Clockwise
+                    } else {
+                        Orientation::CounterClockwise
+                    },
+                    parent: around
+                        .iter()
+                        .copied()
+                        .min_by(|&a, &b| areas[a].abs().total_cmp(&areas[b].abs())),
+                    depth: around.len(),
+                }
+            })
+            .collect();
+
+        Ok(Profiles {
+            loops,
+            chains,
+            dangling,
+        })
+    }
+}
+
+impl System {
+    /// Mark an entity as construction geometry, or clear the mark.
+    ///
+    /// Construction curves still take part in solving, but are not part of the outline
+    /// of the sketch, so they are left out of [`System::profiles`] and
+    /// [`System::validate_profiles`].
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity to be marked.
+    /// * `construction` - Whether the entity is construction geometry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entity is not found in the system.
+    pub fn set_construction(
+        &mut self,
+        entity: &dyn AsEntityHandle,
+        construction: bool,
+    ) -> Result<(), &'static str> {
+        let h = self.slvs_entity(entity.handle())?.h;
+        if construction {
+            self.construction.insert(h);
+        } else {
+            self.construction.remove(&h);
+        }
+        Ok(())
+    }
+
+    /// Whether an entity is marked as construction geometry, with
+    /// [`System::set_construction`].
+    pub fn is_construction(&self, entity: &dyn AsEntityHandle) -> bool {
+        self.construction.contains(&entity.handle())
+    }
+
+    // Merge points that are held together by a constraint, mapping each merged point
+    // towards the point it is merged into.
+    pub(crate) fn merged_points(&self) -> BTreeMap<Slvs_hEntity, Slvs_hEntity> {
+        let mut merged = BTreeMap::new();
+        for slvs_constraint in self
+            .constraints
+            .list
+            .iter()
+            .filter(|slvs_constraint| SLVS_C_POINTS_COINCIDENT == slvs_constraint.type_ as _)
+        {
+            let a = find(&merged, slvs_constraint.ptA);
+            let b = find(&merged, slvs_constraint.ptB);
+            if a != b {
+                merged.insert(a.max(b), a.min(b));
+            }
+        }
+        merged
+    }
+}
+
+// Follow curves from the given end of a curve, until reaching a point where the curves
+// do not simply join in pairs, or coming back around to a curve already followed.
+fn walk(
+    edges: &[Edge],
+    incidences: &BTreeMap<Slvs_hEntity, Vec<(usize, usize)>>,
+    visited: &mut [bool],
+    mut ix: usize,
+    mut end: usize,
+) -> Vec<ProfileEdge> {
+    let mut path = Vec::new();
+    loop {
+        visited[ix] = true;
+        path.push(ProfileEdge {
+            entity: edges[ix].slvs_entity.into(),
+            reversed: end == 1,
+        });
+
+        let node = edges[ix].nodes[1 - end];
+        let next = match incidences[&node].as_slice() {
+            [a, b] if *a == (ix, 1 - end) => *b,
+            [a, _] => *a,
+            _ => break,
+        };
+        if visited[next.0] {
+            break;
+        }
+        (ix, end) = next;
+    }
+    path
+}
+
+// The point that a point has been merged into.
+pub(crate) fn find(
+    merged: &BTreeMap<Slvs_hEntity, Slvs_hEntity>,
+    mut h: Slvs_hEntity,
+) -> Slvs_hEntity {
+    while let Some(&parent) = merged.get(&h) {
+        h = parent;
+    }
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constraint::PointsCoincident,
+        element::AsHandle,
+        entity::{ArcOfCircle, Circle, Distance, LineSegment, Normal, Point, Workplane},
+        profile::Orientation,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn plate_with_holes() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let g = sys.add_group();
+        let mut point = |coords| {
+            sys.sketch(Point::new_on_workplane(g, workplane, coords))
+                .expect("point created")
+        };
+
+        // A clockwise square plate.
+        let corners = [[0.0, 0.0], [0.0, 100.0], [100.0, 100.0], [100.0, 0.0]].map(&mut point);
+        // A slot made of an arc and a line, joined through coincident points.
+        let (slot_center, slot_start, slot_end) = (
+            point([70.0, 50.0]),
+            point([70.0, 40.0]),
+            point([70.0, 60.0]),
+        );
+        let (line_start, line_end) = (point([70.0, 60.0]), point([70.0, 40.0]));
+        // A hole, and an open chain of two lines.
+        let hole_center = point([30.0, 50.0]);
+        let chain_points = [[10.0, 10.0], [20.0, 20.0], [30.0, 10.0]].map(&mut point);
+
+        for i in 0..4 {
+            sys.sketch(LineSegment::new(g, corners[i], corners[(i + 1) % 4]))
+                .expect("line created");
+        }
+        sys.sketch(ArcOfCircle::new(
+            g,
+            workplane,
+            slot_center,
+            slot_start,
+            slot_end,
+        ))
+        .expect("arc created");
+        sys.sketch(LineSegment::new(g, line_start, line_end))
+            .expect("line created");
+        sys.constrain(PointsCoincident::new(g, slot_end, line_start, None))
+            .expect("constraint added");
+        sys.constrain(PointsCoincident::new(g, line_end, slot_start, None))
+            .expect("constraint added");
+
+        let radius = sys.sketch(Distance::new(g, 5.0)).expect("distance created");
+        sys.sketch(Circle::new(g, normal, hole_center, radius))
+            .expect("circle created");
+        sys.sketch(LineSegment::new(g, chain_points[0], chain_points[1]))
+            .expect("line created");
+        sys.sketch(LineSegment::new(g, chain_points[2], chain_points[1]))
+            .expect("line created");
+
+        let profiles = sys.profiles(&g, &workplane).expect("profiles found");
+
+        // The circle is found first, then the plate, then the slot.
+        assert_eq!(profiles.loops.len(), 3);
+        let (hole, plate, slot) = (&profiles.loops[0], &profiles.loops[1], &profiles.loops[2]);
+        assert_eq!(plate.edges.len(), 4);
+        assert_eq!(plate.orientation, Orientation::Clockwise);
+        assert!(!plate.is_hole());
+        assert_eq!(slot.edges.len(), 2);
+        assert_eq!(slot.orientation, Orientation::CounterClockwise);
+        assert!(slot.is_hole());
+        assert_eq!(slot.parent, Some(1));
+        assert!(hole.is_hole());
+        assert_eq!(hole.parent, Some(1));
+
+        assert_eq!(profiles.chains.len(), 1);
+        assert_eq!(profiles.chains[0].edges.len(), 2);
+        let mut dangling: Vec<u32> = profiles
+            .dangling
+            .iter()
+            .map(|point| point.handle())
+            .collect();
+        dangling.sort();
+        assert_eq!(
+            dangling,
+            vec![chain_points[0].handle(), chain_points[2].handle()]
+        );
+    }
+}
@@ -0,0 +1,435 @@
+/*!
+Detection of redundant constraints, before solving.
+
+The solver reports any redundant constraint as a failure, even when the values
+agree. For example, constraining the same distance twice gives
+[`FailReason::Inconsistent`][crate::system::FailReason::Inconsistent].
+[`System::find_redundancies`] looks for these constraints ahead of time, and reports
+each one together with the constraints that make it redundant.
+
+Constraints are first compared with each other, to find exact duplicates and
+well-known combinations, such as a [`Horizontal`][crate::constraint::Horizontal]
+constraint between two points that are already
+[`SymmetricHoriz`][crate::constraint::SymmetricHoriz]. Any remaining redundancy is
+found from the constraint Jacobian, as equations that are linear combinations of the
+equations before them.
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::{
+        Slvs_Constraint, Slvs_hConstraint, SLVS_C_ANGLE, SLVS_C_EQUAL_LENGTH_LINES,
+        SLVS_C_EQUAL_RADIUS, SLVS_C_HORIZONTAL, SLVS_C_PARALLEL, SLVS_C_PERPENDICULAR,
+        SLVS_C_POINTS_COINCIDENT, SLVS_C_PT_PT_DISTANCE, SLVS_C_SAME_ORIENTATION,
+        SLVS_C_SYMMETRIC_HORIZ, SLVS_C_SYMMETRIC_VERT, SLVS_C_VERTICAL,
+    },
+    constraint::AsConstraintHandle,
+    element::AsHandle,
+    equations::EquationSource,
+    group::Group,
+    linalg,
+    system::SOLVE_TOLERANCE,
+    System,
+};
+
+// Coefficients smaller than this are not counted as part of a linear combination.
+const COEFFICIENT_TOLERANCE: f64 = 1e-6;
+
+/// Why a constraint is redundant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RedundancyKind {
+    /// The constraint is an exact copy of another constraint.
+    Duplicate,
+    /// The constraint is already implied by another constraint, such as a
+    /// [`PtPtDistance`][crate::constraint::PtPtDistance] of zero between points that are
+    /// also [`PointsCoincident`][crate::constraint::PointsCoincident].
+    Implied,
+    /// The constraint sets the same quantity as another constraint, but to a
+    /// different value, so they can never both be satisfied.
+    Conflicting,
+    /// The equations of the constraint are a linear combination of the equations of
+    /// other constraints, at the current parameter values.
+    Dependent,
+}
+
+/// A constraint found by [`System::find_redundancies`].
+#[derive(Debug)]
+pub struct Redundancy {
+    /// The redundant constraint.
+    pub constraint: Box<dyn AsConstraintHandle>,
+    /// The constraints, added before `constraint`, that make it redundant.
+    pub redundant_with: Vec<Box<dyn AsConstraintHandle>>,
+    pub kind: RedundancyKind,
+}
+
+// The type of a constraint and what it constrains, with the entities of symmetric
+// relations sorted so that the order they were given in does not matter.
+#[derive(Debug, PartialEq, Eq)]
+struct Signature {
+    type_: i32,
+    wrkpl: u32,
+    points: [u32; 2],
+    entities: [u32; 4],
+    others: [i32; 2],
+}
+
+impl System {
+    /// Find the constraints in a group that are redundant, without solving.
+    ///
+    /// Each redundant constraint is reported once, with the constraints added before it
+    /// that make it redundant. Removing every reported constraint leaves a group whose
+    /// constraints are independent. Soft constraints are ignored, and bounded
+    /// constraints only count when they are at one of their limits.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to be checked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     constraint::{ConstraintHandle, PtPtDistance},
+    ///     entity::Point,
+    ///     redundancy::RedundancyKind,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    ///
+    /// let p1 = sys
+    ///     .sketch(Point::new_in_3d(g, [10.0, 10.0, 10.0]))
+    ///     .expect("p1 created");
+    /// let p2 = sys
+    ///     .sketch(Point::new_in_3d(g, [20.0, 20.0, 20.0]))
+    ///     .expect("p2 created");
+    ///
+    /// let first = sys
+    ///     .constrain(PtPtDistance::new(g, p1, p2, 10.0, None))
+    ///     .expect("distance constraint added");
+    /// let second = sys
+    ///     .constrain(PtPtDistance::new(g, p2, p1, 10.0, None))
+    ///     .expect("distance constraint added");
+    ///
+    /// let redundancies = sys.find_redundancies(&g).expect("redundancies found");
+    /// assert_eq!(redundancies.len(), 1);
+    /// assert_eq!(redundancies[0].kind, RedundancyKind::Duplicate);
+    /// assert_eq!(
+    ///     ConstraintHandle::<PtPtDistance>::try_from(&redundancies[0].constraint),
+    ///     Ok(second)
+    /// );
+    /// assert_eq!(
+    ///     ConstraintHandle::<PtPtDistance>::try_from(&redundancies[0].redundant_with[0]),
+    ///     Ok(first)
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the equations of a constraint cannot be evaluated.
+    pub fn find_redundancies(&mut self, group: &Group) -> Result<Vec<Redundancy>, &'static str> {
+        let constraints: Vec<Slvs_Constraint> = self
+            .active_constraints(group.handle())
+            .into_iter()
+            .map(|h| self.slvs_constraint(h).copied())
+            .collect::<Result<_, &'static str>>()?;
+
+        let mut found: Vec<(Slvs_hConstraint, Vec<Slvs_hConstraint>, RedundancyKind)> = Vec::new();
+        for (i, c) in constraints.iter().enumerate() {
+            for earlier in &constraints[..i] {
+                if found.iter().any(|(h, _, _)| *h == earlier.h) {
+                    continue;
+                }
+                if let Some(kind) = self.compare_constraints(c, earlier)? {
+                    found.push((c.h, vec![earlier.h], kind));
+                    break;
+                }
+            }
+        }
+
+        // Anything left over is found from the rank of the Jacobian.
+        let remaining: Vec<Slvs_hConstraint> = constraints
+            .iter()
+            .map(|c| c.h)
+            .filter(|&h| !found.iter().any(|(found_h, _, _)| *found_h == h))
+            .collect();
+        let equations = self.group_equations(group.handle(), &[]);
+        let equations: Vec<EquationSource> = equations
+            .into_iter()
+            .chain(remaining.iter().map(|&h| EquationSource::Constraint(h)))
+            .collect();
+        let jacobian = self.jacobian(group.handle(), &equations)?;
+
+        let mut owners = Vec::new();
+        for &equation in &equations {
+            let len = self.equation_residuals(equation)?.len();
+            owners.extend(vec![equation; len]);
+        }
+
+        // Each row is only checked against the basis of the independent rows before it.
+        let mut basis: Vec<Vec<f64>> = Vec::new();
+        let mut independent: Vec<usize> = Vec::new();
+        let mut dependent: Vec<(Slvs_hConstraint, Vec<Slvs_hConstraint>)> = Vec::new();
+        for (row_ix, row) in jacobian.rows.iter().enumerate() {
+            if linalg::extend_basis(&mut basis, row) {
+                independent.push(row_ix);
+                continue;
+            }
+
+            let EquationSource::Constraint(h) = owners[row_ix] else {
+                continue;
+            };
+            let rows: Vec<&[f64]> = independent
+                .iter()
+                .map(|&i| jacobian.rows[i].as_slice())
+                .collect();
+            let coefficients = combination(&rows, row);
+            let partners = coefficients
+                .iter()
+                .zip(&independent)
+                .filter(|(coefficient, _)| coefficient.abs() > COEFFICIENT_TOLERANCE)
+                .filter_map(|(_, &i)| match owners[i] {
+                    EquationSource::Constraint(partner) if partner != h => Some(partner),
+                    _ => None,
+                });
+
+            let existing = match dependent.iter().position(|(found_h, _)| *found_h == h) {
+                Some(ix) => &mut dependent[ix].1,
+                None => {
+                    dependent.push((h, Vec::new()));
+                    &mut dependent.last_mut().unwrap().1
+                }
+            };
+            for partner in partners {
+                if !existing.contains(&partner) {
+                    existing.push(partner);
+                }
+            }
+        }
+        found.extend(
+            dependent
+                .into_iter()
+                .map(|(h, partners)| (h, partners, RedundancyKind::Dependent)),
+        );
+
+        let order = |h: &Slvs_hConstraint| constraints.iter().position(|c| c.h == *h);
+        found.sort_by_key(|(h, _, _)| order(h));
+
+        found
+            .into_iter()
+            .map(|(h, partners, kind)| {
+                Ok(Redundancy {
+                    constraint: self.boxed_constraint_handle(*self.slvs_constraint(h)?),
+                    redundant_with: partners
+                        .into_iter()
+                        .map(|partner| {
+                            Ok(self.boxed_constraint_handle(*self.slvs_constraint(partner)?))
+                        })
+                        .collect::<Result<_, &'static str>>()?,
+                    kind,
+                })
+            })
+            .collect()
+    }
+
+    // Whether `c` is made redundant by `earlier` alone.
+    fn compare_constraints(
+        &self,
+        c: &Slvs_Constraint,
+        earlier: &Slvs_Constraint,
+    ) -> Result<Option<RedundancyKind>, &'static str> {
+        let signature = self.constraint_signature(c)?;
+        let earlier_signature = self.constraint_signature(earlier)?;
+
+        if signature == earlier_signature {
+            return Ok(Some(if (c.valA - earlier.valA).abs() <= SOLVE_TOLERANCE {
+                RedundancyKind::Duplicate
+            } else {
+                RedundancyKind::Conflicting
+            }));
+        }
+
+        if signature.wrkpl != earlier_signature.wrkpl
+            || signature.points != earlier_signature.points
+            || signature.points[1] == 0
+        {
+            return Ok(None);
+        }
+
+        let pair = [signature.type_ as u32, earlier_signature.type_ as u32];
+        let kind =
+            if pair.contains(&SLVS_C_PT_PT_DISTANCE) && pair.contains(&SLVS_C_POINTS_COINCIDENT) {
+                let distance = if SLVS_C_PT_PT_DISTANCE == c.type_ as _ {
+                    c.valA
+                } else {
+                    earlier.valA
+                };
+                Some(if distance.abs() <= SOLVE_TOLERANCE {
+                    RedundancyKind::Implied
+                } else {
+                    RedundancyKind::Conflicting
+                })
+            } else if (pair.contains(&SLVS_C_HORIZONTAL) && pair.contains(&SLVS_C_SYMMETRIC_HORIZ))
+                || (pair.contains(&SLVS_C_VERTICAL) && pair.contains(&SLVS_C_SYMMETRIC_VERT))
+            {
+                Some(RedundancyKind::Implied)
+            } else {
+                None
+            };
+
+        Ok(kind)
+    }
+
+    fn constraint_signature(&self, c: &Slvs_Constraint) -> Result<Signature, &'static str> {
+        let mut points = [c.ptA, c.ptB];
+        let mut entities = [c.entityA, c.entityB, c.entityC, c.entityD];
+
+        // A horizontal or vertical line is the same as its endpoints being so.
+        if (SLVS_C_HORIZONTAL == c.type_ as _ || SLVS_C_VERTICAL == c.type_ as _) && c.entityA != 0
+        {
+            let slvs_line = self.slvs_entity(c.entityA)?;
+            points = [slvs_line.point[0], slvs_line.point[1]];
+            entities = [0; 4];
+        }
+
+        if [
+            SLVS_C_POINTS_COINCIDENT,
+            SLVS_C_PT_PT_DISTANCE,
+            SLVS_C_EQUAL_LENGTH_LINES,
+            SLVS_C_EQUAL_RADIUS,
+            SLVS_C_PARALLEL,
+            SLVS_C_PERPENDICULAR,
+            SLVS_C_ANGLE,
+            SLVS_C_SAME_ORIENTATION,
+            SLVS_C_HORIZONTAL,
+            SLVS_C_VERTICAL,
+            SLVS_C_SYMMETRIC_HORIZ,
+            SLVS_C_SYMMETRIC_VERT,
+        ]
+        .contains(&(c.type_ as u32))
+        {
+            points.sort_unstable();
+            entities[..2].sort_unstable();
+        }
+
+        Ok(Signature {
+            type_: c.type_,
+            wrkpl: c.wrkpl,
+            points,
+            entities,
+            others: [c.other, c.other2],
+        })
+    }
+}
+
+// Coefficients that express `row` as a combination of the independent `rows`, found
+// by least squares.
+fn combination(rows: &[&[f64]], row: &[f64]) -> Vec<f64> {
+    let gram: Vec<Vec<f64>> = rows
+        .iter()
+        .map(|a| rows.iter().map(|b| linalg::dot(a, b)).collect())
+        .collect();
+    let rhs: Vec<f64> = rows.iter().map(|a| linalg::dot(a, row)).collect();
+
+    linalg::solve(&gram, &rhs).unwrap_or_else(|| vec![0.0; rows.len()])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constraint::{Horizontal, PointsCoincident, PtPtDistance, SymmetricHoriz, Vertical},
+        element::AsHandle,
+        entity::{LineSegment, Normal, Point, Workplane},
+        redundancy::RedundancyKind,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn structural_and_dependent() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let g = sys.add_group();
+        let a = sys
+            .sketch(Point::new_on_workplane(g, workplane, [-5.0, 1.0]))
+            .expect("point created");
+        let b = sys
+            .sketch(Point::new_on_workplane(g, workplane, [5.0, 1.0]))
+            .expect("point created");
+        let c = sys
+            .sketch(Point::new_on_workplane(g, workplane, [5.0, 8.0]))
+            .expect("point created");
+        let d = sys
+            .sketch(Point::new_on_workplane(g, workplane, [5.0, 8.0]))
+            .expect("point created");
+        let ab = sys.sketch(LineSegment::new(g, a, b)).expect("line created");
+        let bc = sys.sketch(LineSegment::new(g, b, c)).expect("line created");
+
+        let symmetric = sys
+            .constrain(SymmetricHoriz::new(g, workplane, a, b))
+            .expect("constraint added");
+        let horizontal = sys
+            .constrain(Horizontal::from_line(g, workplane, ab))
+            .expect("constraint added");
+        let coincident = sys
+            .constrain(PointsCoincident::new(g, c, d, Some(workplane)))
+            .expect("constraint added");
+        let zero_distance = sys
+            .constrain(PtPtDistance::new(g, d, c, 0.0, Some(workplane)))
+            .expect("constraint added");
+
+        // The second vertical constraint, between the same points, is dependent.
+        let vertical = sys
+            .constrain(Vertical::from_line(g, workplane, bc))
+            .expect("constraint added");
+        let vertical_points = sys
+            .constrain(Vertical::from_points(g, workplane, b, d))
+            .expect("constraint added");
+
+        let redundancies = sys.find_redundancies(&g).expect("redundancies found");
+        assert_eq!(redundancies.len(), 3);
+
+        assert_eq!(redundancies[0].constraint.handle(), horizontal.handle());
+        assert_eq!(redundancies[0].kind, RedundancyKind::Implied);
+        assert_eq!(
+            redundancies[0].redundant_with[0].handle(),
+            symmetric.handle()
+        );
+
+        assert_eq!(redundancies[1].constraint.handle(), zero_distance.handle());
+        assert_eq!(redundancies[1].kind, RedundancyKind::Implied);
+        assert_eq!(
+            redundancies[1].redundant_with[0].handle(),
+            coincident.handle()
+        );
+
+        assert_eq!(
+            redundancies[2].constraint.handle(),
+            vertical_points.handle()
+        );
+        assert_eq!(redundancies[2].kind, RedundancyKind::Dependent);
+        let partners: Vec<u32> = redundancies[2]
+            .redundant_with
+            .iter()
+            .map(|partner| partner.handle())
+            .collect();
+        assert!(partners.contains(&vertical.handle()));
+        assert!(partners.contains(&coincident.handle()));
+    }
+}
@@ -0,0 +1,431 @@
+/*!
+Area, perimeter, centroid and second moments of area of closed profiles.
+
+The properties are integrals over the region inside a closed loop found by
+[`System::profiles`], which are turned into integrals along its edges by Green's
+theorem. The edges are integrated with Gauss-Legendre quadrature, which is exact for
+line segments and cubic curves, and accurate to machine precision for arcs. Only the
+perimeter of a cubic curve is approximate, since its length has no closed form.
+*/
+
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+use crate::{
+    bindings::{SLVS_E_ARC_OF_CIRCLE, SLVS_E_CIRCLE, SLVS_E_CUBIC, SLVS_E_LINE_SEGMENT},
+    element::AsHandle,
+    entity::{EntityHandle, Workplane},
+    geometry::{add, scale, sub, CubicGeometry},
+    profile::{Profile, Profiles},
+    System,
+};
+
+// Nodes and weights of 8 point Gauss-Legendre quadrature on [-1, 1], which is exact
+// for polynomials up to degree 15.
+const GAUSS_LEGENDRE: [(f64, f64); 8] = [
+    (-0.960_289_856_497_536_3, 0.101_228_536_290_376_26),
+    (-0.796_666_477_413_626_7, 0.222_381_034_453_374_47),
+    (-0.525_532_409_916_329, 0.313_706_645_877_887),
+    (-0.183_434_642_495_649_8, 0.362_683_783_378_362),
+    (0.183_434_642_495_649_8, 0.362_683_783_378_362),
+    (0.525_532_409_916_329, 0.313_706_645_877_887),
+    (0.796_666_477_413_626_7, 0.222_381_034_453_374_47),
+    (0.960_289_856_497_536_3, 0.101_228_536_290_376_26),
+];
+// Cubic curves are split into this many pieces when finding their length.
+const CUBIC_PIECES: usize = 16;
+
+/// Properties of a region in a workplane, found by [`System::profile_properties`]
+/// and [`System::region_properties`].
+///
+/// The centroid is in the coordinates of the workplane. Second moments of area are
+/// taken about axes through the centroid, parallel to the `u` and `v` axes of the
+/// workplane.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SectionProperties {
+    pub area: f64,
+    /// The total length of the boundaries of the region, including its holes.
+    pub perimeter: f64,
+    pub centroid: [f64; 2],
+    /// The second moment of area about the axis parallel to `u`.
+    pub ixx: f64,
+    /// The second moment of area about the axis parallel to `v`.
+    pub iyy: f64,
+    /// The product moment of area.
+    pub ixy: f64,
+}
+
+impl System {
+    /// Find the properties of the region inside a closed loop.
+    ///
+    /// The area is positive whichever way the loop goes around. Holes in the loop are
+    /// not taken into account, see [`System::region_properties`] for that.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - A closed loop, found by [`System::profiles`].
+    /// * `workplane` - The workplane that the loop was found in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the curves in the loop or the workplane are not found in the
+    /// system, or if an arc or circle in the loop is not parallel to the workplane.
+    pub fn profile_properties(
+        &self,
+        profile: &Profile,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<SectionProperties, &'static str> {
+        let edges = self.section_edges(profile, workplane)?;
+        let reference = edges.first().map_or([0.0; 2], |edge| edge.point(0.0));
+
+        let integrals = Integrals::around(&edges, reference);
+        Ok(integrals.properties(reference))
+    }
+
+    /// Find the properties of the region inside a closed loop, less the holes in it.
+    ///
+    /// # Arguments
+    ///
+    /// * `profiles` - Loops found by [`System::profiles`].
+    /// * `outer` - The index of the loop around the region. Loops whose
+    ///   [parent][Profile::parent] is this loop are holes in the region.
+    /// * `workplane` - The workplane that the loops were found in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{Circle, Distance, LineSegment, Normal, Point, Workplane},
+    ///     utils::make_quaternion,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// let g = sys.add_group();
+    /// let mut point = |coords| {
+    ///     sys.sketch(Point::new_on_workplane(g, workplane, coords))
+    ///         .expect("point created")
+    /// };
+    /// let corners = [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0]].map(&mut point);
+    /// let center = point([5.0, 5.0]);
+    ///
+    /// for i in 0..4 {
+    ///     sys.sketch(LineSegment::new(g, corners[i], corners[(i + 1) % 4]))
+    ///         .expect("line created");
+    /// }
+    /// let radius = sys.sketch(Distance::new(g, 2.0)).expect("distance created");
+    /// sys.sketch(Circle::new(g, normal, center, radius))
+    ///     .expect("circle created");
+    ///
+    /// let profiles = sys.profiles(&g, &workplane).expect("profiles found");
+    /// let outer = profiles
+    ///     .loops
+    ///     .iter()
+    ///     .position(|profile| profile.parent.is_none())
+    ///     .expect("outer loop found");
+    /// let plate = sys
+    ///     .region_properties(&profiles, outer, &workplane)
+    ///     .expect("properties found");
+    ///
+    /// let pi = std::f64::consts::PI;
+    /// assert!((plate.area - (100.0 - 4.0 * pi)).abs() < 1e-9);
+    /// assert!((plate.perimeter - (40.0 + 4.0 * pi)).abs() < 1e-9);
+    /// assert!((plate.centroid[0] - 5.0).abs() < 1e-9);
+    /// assert!((plate.ixx - (10_000.0 / 12.0 - 4.0 * pi)).abs() < 1e-9);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `outer` is not the index of a loop, if the curves in the
+    /// loops or the workplane are not found in the system, or if an arc or circle in
+    /// the loops is not parallel to the workplane.
+    pub fn region_properties(
+        &self,
+        profiles: &Profiles,
+        outer: usize,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<SectionProperties, &'static str> {
+        let outer_profile = profiles.loops.get(outer).ok_or("Profile not found.")?;
+        let edges = self.section_edges(outer_profile, workplane)?;
+        let reference = edges.first().map_or([0.0; 2], |edge| edge.point(0.0));
+
+        let mut integrals = Integrals::around(&edges, reference);
+        for hole in profiles
+            .loops
+            .iter()
+            .filter(|profile| profile.parent == Some(outer))
+        {
+            let hole_integrals =
+                Integrals::around(&self.section_edges(hole, workplane)?, reference);
+            integrals = integrals.less(&hole_integrals);
+        }
+
+        Ok(integrals.properties(reference))
+    }
+
+    fn section_edges(
+        &self,
+        profile: &Profile,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<Vec<SectionEdge>, &'static str> {
+        profile
+            .edges
+            .iter()
+            .map(|edge| {
+                let h = edge.entity.handle();
+                let curve = match self.slvs_entity(h)?.type_ as _ {
+                    SLVS_E_LINE_SEGMENT => {
+                        let line = self.line_geometry_on(&EntityHandle::new(h), workplane)?;
+                        Curve::Line(line.start, line.end)
+                    }
+                    SLVS_E_ARC_OF_CIRCLE => {
+                        let arc = self.arc_geometry_on(&EntityHandle::new(h), workplane)?;
+                        Curve::Arc {
+                            center: arc.center,
+                            radius: arc.radius,
+                            start_angle: arc.start_angle.to_radians(),
+                            sweep: arc.sweep().to_radians(),
+                        }
+                    }
+                    SLVS_E_CIRCLE => {
+                        let circle = self.circle_geometry_on(&EntityHandle::new(h), workplane)?;
+                        Curve::Arc {
+                            center: circle.center,
+                            radius: circle.radius,
+                            start_angle: 0.0,
+                            sweep: 2.0 * PI,
+                        }
+                    }
+                    SLVS_E_CUBIC => {
+                        Curve::Cubic(self.cubic_geometry_on(&EntityHandle::new(h), workplane)?)
+                    }
+                    _ => return Err("Expected entity to be a line segment, arc, circle or cubic."),
+                };
+
+                Ok(SectionEdge {
+                    curve,
+                    reversed: edge.reversed,
+                })
+            })
+            .collect()
+    }
+}
+
+enum Curve {
+    Line([f64; 2], [f64; 2]),
+    Arc {
+        center: [f64; 2],
+        radius: f64,
+        // Angles are in radians.
+        start_angle: f64,
+        sweep: f64,
+    },
+    Cubic(CubicGeometry<2>),
+}
+
+struct SectionEdge {
+    curve: Curve,
+    reversed: bool,
+}
+
+impl SectionEdge {
+    // The point at `s`, which goes from 0 to 1 in the direction the edge is followed.
+    fn point(&self, s: f64) -> [f64; 2] {
+        let t = if self.reversed { 1.0 - s } else { s };
+
+        match self.curve {
+            Curve::Line(start, end) => add(start, scale(sub(end, start), t)),
+            Curve::Arc {
+                center,
+                radius,
+                start_angle,
+                sweep,
+            } => {
+                let angle = start_angle + sweep * t;
+                add(center, [radius * angle.cos(), radius * angle.sin()])
+            }
+            Curve::Cubic(cubic) => cubic.point_at(t),
+        }
+    }
+
+    // The derivative of the point with respect to `s`.
+    fn derivative(&self, s: f64) -> [f64; 2] {
+        let (t, sign) = if self.reversed {
+            (1.0 - s, -1.0)
+        } else {
+            (s, 1.0)
+        };
+
+        let derivative = match self.curve {
+            Curve::Line(start, end) => sub(end, start),
+            Curve::Arc {
+                radius,
+                start_angle,
+                sweep,
+                ..
+            } => {
+                let angle = start_angle + sweep * t;
+                [-radius * sweep * angle.sin(), radius * sweep * angle.cos()]
+            }
+            Curve::Cubic(cubic) => cubic.tangent_at(t),
+        };
+        scale(derivative, sign)
+    }
+
+    // The number of pieces that the edge is integrated in. Arcs are integrated in
+    // pieces of at most a quarter turn.
+    fn pieces(&self) -> usize {
+        match self.curve {
+            Curve::Line(..) => 1,
+            Curve::Arc { sweep, .. } => (sweep / (PI / 2.0)).ceil().max(1.0) as usize,
+            Curve::Cubic(_) => CUBIC_PIECES,
+        }
+    }
+}
+
+// Integrals over a region, relative to a reference point. Areas are signed, and are
+// positive for a counter-clockwise boundary.
+#[derive(Clone, Copy, Debug, Default)]
+struct Integrals {
+    perimeter: f64,
+    area: f64,
+    // The integrals of x and y.
+    first: [f64; 2],
+    // The integrals of x², y² and xy.
+    second: [f64; 3],
+}
+
+impl Integrals {
+    // The integrals over the region inside a loop, made positive whichever way the
+    // loop goes around.
+    fn around(edges: &[SectionEdge], reference: [f64; 2]) -> Self {
+        let mut integrals = Integrals::default();
+
+        for edge in edges {
+            let pieces = edge.pieces();
+            for piece in 0..pieces {
+                let (low, high) = (
+                    piece as f64 / pieces as f64,
+                    (piece + 1) as f64 / pieces as f64,
+                );
+                for (node, weight) in GAUSS_LEGENDRE {
+                    let s = low + (high - low) * (node + 1.0) / 2.0;
+                    let weight = weight * (high - low) / 2.0;
+                    let [x, y] = sub(edge.point(s), reference);
+                    let [dx, dy] = edge.derivative(s);
+
+                    integrals.perimeter += weight * dx.hypot(dy);
+                    integrals.area += weight * (x * dy - y * dx) / 2.0;
+                    integrals.first[0] += weight * x * x * dy / 2.0;
+                    integrals.first[1] -= weight * y * y * dx / 2.0;
+                    integrals.second[0] += weight * x * x * x * dy / 3.0;
+                    integrals.second[1] -= weight * y * y * y * dx / 3.0;
+                    integrals.second[2] += weight * x * x * y * dy / 2.0;
+                }
+            }
+        }
+
+        if integrals.area < 0.0 {
+            Integrals {
+                perimeter: integrals.perimeter,
+                area: -integrals.area,
+                first: integrals.first.map(|v| -v),
+                second: integrals.second.map(|v| -v),
+            }
+        } else {
+            integrals
+        }
+    }
+
+    // The integrals over this region with a hole cut out, whose boundary adds to the
+    // perimeter.
+    fn less(&self, hole: &Integrals) -> Self {
+        Integrals {
+            perimeter: self.perimeter + hole.perimeter,
+            area: self.area - hole.area,
+            first: [0, 1].map(|i| self.first[i] - hole.first[i]),
+            second: [0, 1, 2].map(|i| self.second[i] - hole.second[i]),
+        }
+    }
+
+    fn properties(&self, reference: [f64; 2]) -> SectionProperties {
+        let [cx, cy] = self.first.map(|v| v / self.area);
+
+        SectionProperties {
+            area: self.area,
+            perimeter: self.perimeter,
+            centroid: add(reference, [cx, cy]),
+            ixx: self.second[1] - self.area * cy * cy,
+            iyy: self.second[0] - self.area * cx * cx,
+            ixy: self.second[2] - self.area * cx * cy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity::{Cubic, LineSegment, Normal, Point, Workplane},
+        len_within_tolerance,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn cubic_edge_is_exact() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let g = sys.add_group();
+        let [start, control_a, control_b, end] = [[3.0, 0.0], [3.0, 3.0], [0.0, 3.0], [0.0, 0.0]]
+            .map(|coords| {
+                sys.sketch(Point::new_on_workplane(g, workplane, coords))
+                    .expect("point created")
+            });
+        sys.sketch(LineSegment::new(g, end, start))
+            .expect("line created");
+        sys.sketch(Cubic::new(g, start, control_a, control_b, end))
+            .expect("cubic created");
+
+        let profiles = sys.profiles(&g, &workplane).expect("profiles found");
+        let properties = sys
+            .profile_properties(&profiles.loops[0], &workplane)
+            .expect("properties found");
+
+        // Exact values, found by integrating the Bezier polynomials.
+        len_within_tolerance!(properties.area, 27.0 / 5.0);
+        len_within_tolerance!(properties.centroid[0], 1.5);
+        len_within_tolerance!(properties.centroid[1], 27.0 / 28.0);
+        len_within_tolerance!(
+            properties.ixx,
+            243.0 / 35.0 - 27.0 / 5.0 * (27.0_f64 / 28.0).powi(2)
+        );
+    }
+}
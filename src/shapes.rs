@@ -0,0 +1,833 @@
+/*!
+Builders that sketch several entities at once.
+
+Each builder creates the points and curves of a shape on a workplane, sharing the
+point entities where curves meet, and returns the handles to all of them. Builders for
+rectangles, slots and regular polygons also add the constraints that keep the shape,
+and can dimension its size.
+*/
+
+use std::f64::consts::PI;
+
+use crate::{
+    constraint::{
+        ArcLineTangent, ConstraintHandle, Diameter, EqualLengthLines, EqualRadius, Horizontal,
+        PtOnCircle, PtPtDistance, SymmetricHoriz, SymmetricVert, Vertical,
+    },
+    element::AsHandle,
+    entity::{ArcOfCircle, Circle, Distance, EntityHandle, LineSegment, Normal, Point, Workplane},
+    geometry::{add, scale, sub},
+    group::Group,
+    utils::distance,
+    System,
+};
+
+/// An open chain of line segments, created by [`System::sketch_polyline`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polyline {
+    pub points: Vec<EntityHandle<Point>>,
+    /// The line from `points[i]` to `points[i + 1]` is at `lines[i]`.
+    pub lines: Vec<EntityHandle<LineSegment>>,
+}
+
+/// A closed ring of line segments, created by [`System::sketch_polygon`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    pub points: Vec<EntityHandle<Point>>,
+    /// The line from `points[i]` to the next point around is at `lines[i]`, so the
+    /// last line goes back to the first point.
+    pub lines: Vec<EntityHandle<LineSegment>>,
+}
+
+/// A rectangle with sides along the axes of its workplane, created by
+/// [`System::sketch_rectangle`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rectangle {
+    /// The corners, counter-clockwise from the bottom left.
+    pub points: [EntityHandle<Point>; 4],
+    /// The bottom, right, top and left sides, with `lines[i]` going from `points[i]`
+    /// to the next corner around.
+    pub lines: [EntityHandle<LineSegment>; 4],
+    /// Constraints on the bottom and top sides.
+    pub horizontal: [ConstraintHandle<Horizontal>; 2],
+    /// Constraints on the right and left sides.
+    pub vertical: [ConstraintHandle<Vertical>; 2],
+    /// The distance between the bottom corners, if dimensioned.
+    pub width: Option<ConstraintHandle<PtPtDistance>>,
+    /// The distance between the right corners, if dimensioned.
+    pub height: Option<ConstraintHandle<PtPtDistance>>,
+}
+
+/// A rectangle with sides along the axes of its workplane, centered on a point, created
+/// by [`System::sketch_centered_rectangle`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CenteredRectangle {
+    /// The corners, counter-clockwise from the bottom left.
+    pub points: [EntityHandle<Point>; 4],
+    /// The bottom, right, top and left sides, with `lines[i]` going from `points[i]`
+    /// to the next corner around.
+    pub lines: [EntityHandle<LineSegment>; 4],
+    /// A workplane with its origin at the center, parallel to the workplane of the
+    /// rectangle. The corners are mirrored across its axes.
+    pub center_workplane: EntityHandle<Workplane>,
+    /// Constraints mirroring the bottom corners and the top corners across the
+    /// vertical axis through the center.
+    pub symmetric_horiz: [ConstraintHandle<SymmetricHoriz>; 2],
+    /// The constraint mirroring the right corners across the horizontal axis through
+    /// the center.
+    pub symmetric_vert: ConstraintHandle<SymmetricVert>,
+    /// The distance between the bottom corners, if dimensioned.
+    pub width: Option<ConstraintHandle<PtPtDistance>>,
+    /// The distance between the right corners, if dimensioned.
+    pub height: Option<ConstraintHandle<PtPtDistance>>,
+}
+
+/// Two parallel lines joined at their ends by half circles, created by
+/// [`System::sketch_slot`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slot {
+    /// The centers of the arcs at the first and second end.
+    pub centers: [EntityHandle<Point>; 2],
+    /// The ends of the lines, counter-clockwise from the start of the first line.
+    pub points: [EntityHandle<Point>; 4],
+    /// The line going from the first end to the second, and the line coming back.
+    pub lines: [EntityHandle<LineSegment>; 2],
+    /// The arcs at the second and first end.
+    pub arcs: [EntityHandle<ArcOfCircle>; 2],
+    /// Constraints holding the start and end of each arc tangent to the lines.
+    pub tangents: [ConstraintHandle<ArcLineTangent>; 4],
+    pub equal_radius: ConstraintHandle<EqualRadius<ArcOfCircle, ArcOfCircle>>,
+    /// The distance between the centers, if dimensioned.
+    pub length: Option<ConstraintHandle<PtPtDistance>>,
+    /// The diameter of the arcs, if dimensioned.
+    pub diameter: Option<ConstraintHandle<Diameter<ArcOfCircle>>>,
+}
+
+/// A polygon with equal sides, inscribed in a circle, created by
+/// [`System::sketch_regular_polygon`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegularPolygon {
+    pub center: EntityHandle<Point>,
+    /// The circle through the corners. This is a real circle in the group, but it is
+    /// marked as construction geometry, so it is not part of the outline.
+    pub circle: EntityHandle<Circle>,
+    /// The corners, counter-clockwise.
+    pub points: Vec<EntityHandle<Point>>,
+    /// The line from `points[i]` to the next point around is at `lines[i]`.
+    pub lines: Vec<EntityHandle<LineSegment>>,
+    /// Constraints holding each corner on the circle.
+    pub on_circle: Vec<ConstraintHandle<PtOnCircle<Circle>>>,
+    /// Constraints holding each line after the first to the length of the first.
+    pub equal_length: Vec<ConstraintHandle<EqualLengthLines>>,
+    /// The diameter of the circle, if dimensioned.
+    pub diameter: Option<ConstraintHandle<Diameter<Circle>>>,
+}
+
+impl System {
+    /// Sketch an open chain of line segments through a list of points on a workplane.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group that the entities are created in.
+    /// * `workplane` - The workplane that the points are on.
+    /// * `coords` - The coordinates of the points on the workplane, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if fewer than two points are given, or if the workplane is not
+    /// found in the system.
+    pub fn sketch_polyline(
+        &mut self,
+        group: &Group,
+        workplane: &EntityHandle<Workplane>,
+        coords: &[[f64; 2]],
+    ) -> Result<Polyline, &'static str> {
+        if coords.len() < 2 {
+            return Err("Expected at least 2 points for a polyline.");
+        }
+
+        let points = self.sketch_points(group, workplane, coords)?;
+        let lines = points
+            .windows(2)
+            .map(|pair| self.sketch(LineSegment::new(*group, pair[0], pair[1])))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Polyline { points, lines })
+    }
+
+    /// Sketch a closed ring of line segments through a list of points on a workplane.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group that the entities are created in.
+    /// * `workplane` - The workplane that the points are on.
+    /// * `coords` - The coordinates of the corners on the workplane, in order. The first
+    ///   corner is not repeated at the end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{Normal, Point, Workplane},
+    ///     utils::make_quaternion,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// let g = sys.add_group();
+    /// let triangle = sys
+    ///     .sketch_polygon(&g, &workplane, &[[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]])
+    ///     .expect("polygon created");
+    /// assert_eq!(triangle.points.len(), 3);
+    /// assert_eq!(triangle.lines.len(), 3);
+    ///
+    /// let profiles = sys.profiles(&g, &workplane).expect("profiles found");
+    /// assert_eq!(profiles.loops.len(), 1);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if fewer than three corners are given, or if the workplane is
+    /// not found in the system.
+    pub fn sketch_polygon(
+        &mut self,
+        group: &Group,
+        workplane: &EntityHandle<Workplane>,
+        coords: &[[f64; 2]],
+    ) -> Result<Polygon, &'static str> {
+        if coords.len() < 3 {
+            return Err("Expected at least 3 points for a polygon.");
+        }
+
+        let points = self.sketch_points(group, workplane, coords)?;
+        let lines = (0..points.len())
+            .map(|i| {
+                self.sketch(LineSegment::new(
+                    *group,
+                    points[i],
+                    points[(i + 1) % points.len()],
+                ))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Polygon { points, lines })
+    }
+
+    /// Sketch a rectangle with sides along the axes of a workplane.
+    ///
+    /// The sides are constrained to be horizontal and vertical, which leaves the
+    /// rectangle free to move and to change its width and height.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group that the entities and constraints are created in.
+    /// * `workplane` - The workplane that the rectangle is on.
+    /// * `corner_a`, `corner_b` - Opposite corners of the rectangle.
+    /// * `dimensioned` - Whether to fix the width and height of the rectangle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{Normal, Point, Workplane},
+    ///     utils::make_quaternion,
+    ///     system::SolveResult,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// let g = sys.add_group();
+    /// let rectangle = sys
+    ///     .sketch_rectangle(&g, &workplane, [0.0, 0.0], [20.0, 10.0], true)
+    ///     .expect("rectangle created");
+    /// assert!(rectangle.width.is_some());
+    ///
+    /// // Only the position of the rectangle is left free.
+    /// match sys.solve(&g) {
+    ///     SolveResult::Ok { dof, .. } => assert_eq!(dof, 2),
+    ///     SolveResult::Fail { .. } => panic!("rectangle should solve"),
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rectangle has no width or height, or if the workplane
+    /// is not found in the system.
+    pub fn sketch_rectangle(
+        &mut self,
+        group: &Group,
+        workplane: &EntityHandle<Workplane>,
+        corner_a: [f64; 2],
+        corner_b: [f64; 2],
+        dimensioned: bool,
+    ) -> Result<Rectangle, &'static str> {
+        let [left, right] = [corner_a[0].min(corner_b[0]), corner_a[0].max(corner_b[0])];
+        let [bottom, top] = [corner_a[1].min(corner_b[1]), corner_a[1].max(corner_b[1])];
+        let (width, height) = (right - left, top - bottom);
+        if width == 0.0 || height == 0.0 {
+            return Err("Expected rectangle to have non-zero width and height.");
+        }
+
+        let [a, b, c, d] = [[left, bottom], [right, bottom], [right, top], [left, top]]
+            .map(|coords| self.sketch(Point::new_on_workplane(*group, *workplane, coords)));
+        let points = [a?, b?, c?, d?];
+        let lines = self.sketch_ring(group, points)?;
+
+        let horizontal = [
+            self.constrain(Horizontal::from_line(*group, *workplane, lines[0]))?,
+            self.constrain(Horizontal::from_line(*group, *workplane, lines[2]))?,
+        ];
+        let vertical = [
+            self.constrain(Vertical::from_line(*group, *workplane, lines[1]))?,
+            self.constrain(Vertical::from_line(*group, *workplane, lines[3]))?,
+        ];
+        let width = self.dimension(group, workplane, [points[0], points[1]], width, dimensioned)?;
+        let height = self.dimension(
+            group,
+            workplane,
+            [points[1], points[2]],
+            height,
+            dimensioned,
+        )?;
+
+        Ok(Rectangle {
+            points,
+            lines,
+            horizontal,
+            vertical,
+            width,
+            height,
+        })
+    }
+
+    /// Sketch a rectangle centered on a point, with sides along the axes of a
+    /// workplane.
+    ///
+    /// The corners are constrained to be symmetric about the axes of a new workplane,
+    /// which has its origin at the center and is parallel to `workplane`. This leaves
+    /// the rectangle free to change its width and height, and it moves with the center
+    /// point.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group that the entities and constraints are created in.
+    /// * `workplane` - The workplane that the rectangle is on.
+    /// * `center` - The point that the rectangle is centered on. Points that are not on
+    ///   `workplane` are projected onto it.
+    /// * `size` - The width and height of the rectangle.
+    /// * `dimensioned` - Whether to fix the width and height of the rectangle.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the width or height is not positive, or if the workplane or
+    /// center point are not found in the system.
+    pub fn sketch_centered_rectangle(
+        &mut self,
+        group: &Group,
+        workplane: &EntityHandle<Workplane>,
+        center: &EntityHandle<Point>,
+        size: [f64; 2],
+        dimensioned: bool,
+    ) -> Result<CenteredRectangle, &'static str> {
+        if size.iter().any(|&length| length <= 0.0) {
+            return Err("Expected rectangle to have positive width and height.");
+        }
+
+        let [x, y] = self.point_coords_on(center.handle(), workplane.handle())?;
+        let [u, v] = size.map(|length| length / 2.0);
+        let [a, b, c, d] = [
+            [x - u, y - v],
+            [x + u, y - v],
+            [x + u, y + v],
+            [x - u, y + v],
+        ]
+        .map(|coords| self.sketch(Point::new_on_workplane(*group, *workplane, coords)));
+        let points = [a?, b?, c?, d?];
+        let lines = self.sketch_ring(group, points)?;
+
+        let normal = EntityHandle::<Normal>::new(self.slvs_entity(workplane.handle())?.normal);
+        let center_workplane = self.sketch(Workplane::new(*group, *center, normal))?;
+        let symmetric_horiz = [
+            self.constrain(SymmetricHoriz::new(
+                *group,
+                center_workplane,
+                points[0],
+                points[1],
+            ))?,
+            self.constrain(SymmetricHoriz::new(
+                *group,
+                center_workplane,
+                points[3],
+                points[2],
+            ))?,
+        ];
+        let symmetric_vert = self.constrain(SymmetricVert::new(
+            *group,
+            center_workplane,
+            points[1],
+            points[2],
+        ))?;
+        let width = self.dimension(
+            group,
+            workplane,
+            [points[0], points[1]],
+            size[0],
+            dimensioned,
+        )?;
+        let height = self.dimension(
+            group,
+            workplane,
+            [points[1], points[2]],
+            size[1],
+            dimensioned,
+        )?;
+
+        Ok(CenteredRectangle {
+            points,
+            lines,
+            center_workplane,
+            symmetric_horiz,
+            symmetric_vert,
+            width,
+            height,
+        })
+    }
+
+    /// Sketch a slot, made of two parallel lines joined at their ends by half circles.
+    ///
+    /// The arcs are constrained to be tangent to the lines and to have equal radii,
+    /// which leaves the slot free to move, turn, and change its length and width.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group that the entities and constraints are created in.
+    /// * `workplane` - The workplane that the slot is on.
+    /// * `centers` - The centers of the half circles at either end.
+    /// * `radius` - The radius of the half circles, which is half the width of the slot.
+    /// * `dimensioned` - Whether to fix the distance between the centers and the
+    ///   diameter of the half circles.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the centers coincide or the radius is not positive, or if
+    /// the workplane is not found in the system.
+    pub fn sketch_slot(
+        &mut self,
+        group: &Group,
+        workplane: &EntityHandle<Workplane>,
+        centers: [[f64; 2]; 2],
+        radius: f64,
+        dimensioned: bool,
+    ) -> Result<Slot, &'static str> {
+        let length = distance(centers[0], centers[1]);
+        if length == 0.0 {
+            return Err("Expected slot to have distinct centers.");
+        }
+        if radius <= 0.0 {
+            return Err("Expected slot to have a positive radius.");
+        }
+
+        // Offset from the centers to the left of the direction from the first center
+        // to the second.
+        let direction = scale(sub(centers[1], centers[0]), 1.0 / length);
+        let offset = scale([-direction[1], direction[0]], radius);
+
+        let [first, second] =
+            centers.map(|coords| self.sketch(Point::new_on_workplane(*group, *workplane, coords)));
+        let [a, b, c, d] = [
+            sub(centers[0], offset),
+            sub(centers[1], offset),
+            add(centers[1], offset),
+            add(centers[0], offset),
+        ]
+        .map(|coords| self.sketch(Point::new_on_workplane(*group, *workplane, coords)));
+        let centers = [first?, second?];
+        let points = [a?, b?, c?, d?];
+
+        let lines = [
+            self.sketch(LineSegment::new(*group, points[0], points[1]))?,
+            self.sketch(LineSegment::new(*group, points[2], points[3]))?,
+        ];
+        let arcs = [
+            self.sketch(ArcOfCircle::new(
+                *group, *workplane, centers[1], points[1], points[2],
+            ))?,
+            self.sketch(ArcOfCircle::new(
+                *group, *workplane, centers[0], points[3], points[0],
+            ))?,
+        ];
+
+        let tangents = [
+            self.constrain(ArcLineTangent::new(
+                *group, *workplane, arcs[0], lines[0], false,
+            ))?,
+            self.constrain(ArcLineTangent::new(
+                *group, *workplane, arcs[0], lines[1], true,
+            ))?,
+            self.constrain(ArcLineTangent::new(
+                *group, *workplane, arcs[1], lines[1], false,
+            ))?,
+            self.constrain(ArcLineTangent::new(
+                *group, *workplane, arcs[1], lines[0], true,
+            ))?,
+        ];
+        let equal_radius = self.constrain(EqualRadius::new(*group, arcs[0], arcs[1]))?;
+
+        let length = self.dimension(group, workplane, centers, length, dimensioned)?;
+        let diameter = if dimensioned {
+            Some(self.constrain(Diameter::new(*group, arcs[0], 2.0 * radius))?)
+        } else {
+            None
+        };
+
+        Ok(Slot {
+            centers,
+            points,
+            lines,
+            arcs,
+            tangents,
+            equal_radius,
+            length,
+            diameter,
+        })
+    }
+
+    /// Sketch a regular polygon, with its corners on a circle.
+    ///
+    /// The corners are constrained to lie on the circle, and the sides to have equal
+    /// length, which leaves the polygon free to move, turn, and change its size. The
+    /// first corner is placed in the direction of the workplane's horizontal axis
+    /// from the center.
+    ///
+    /// The circle is a real entity in `group`, which takes part in solving. It is
+    /// marked as construction geometry with [`System::set_construction`], so that it
+    /// is left out of the profiles of the sketch.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group that the entities and constraints are created in.
+    /// * `workplane` - The workplane that the polygon is on.
+    /// * `center` - The center of the circle.
+    /// * `radius` - The radius of the circle.
+    /// * `sides` - The number of sides.
+    /// * `dimensioned` - Whether to fix the diameter of the circle.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are fewer than three sides or the radius is not
+    /// positive, or if the workplane is not found in the system.
+    pub fn sketch_regular_polygon(
+        &mut self,
+        group: &Group,
+        workplane: &EntityHandle<Workplane>,
+        center: [f64; 2],
+        radius: f64,
+        sides: usize,
+        dimensioned: bool,
+    ) -> Result<RegularPolygon, &'static str> {
+        if sides < 3 {
+            return Err("Expected at least 3 sides for a regular polygon.");
+        }
+        if radius <= 0.0 {
+            return Err("Expected regular polygon to have a positive radius.");
+        }
+
+        let normal = EntityHandle::new(self.slvs_entity(workplane.handle())?.normal);
+        let center_point = self.sketch(Point::new_on_workplane(*group, *workplane, center))?;
+        let radius_distance = self.sketch(Distance::new(*group, radius))?;
+        let circle = self.sketch(Circle::new(*group, normal, center_point, radius_distance))?;
+        self.set_construction(&circle, true)?;
+
+        let corners: Vec<[f64; 2]> = (0..sides)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f64 / sides as f64;
+                add(center, [radius * angle.cos(), radius * angle.sin()])
+            })
+            .collect();
+        let Polygon { points, lines } = self.sketch_polygon(group, workplane, &corners)?;
+
+        let on_circle = points
+            .iter()
+            .map(|point| self.constrain(PtOnCircle::new(*group, *point, circle)))
+            .collect::<Result<_, _>>()?;
+        let equal_length = lines[1..]
+            .iter()
+            .map(|line| {
+                self.constrain(EqualLengthLines::new(
+                    *group,
+                    lines[0],
+                    *line,
+                    Some(*workplane),
+                ))
+            })
+            .collect::<Result<_, _>>()?;
+        let diameter = if dimensioned {
+            Some(self.constrain(Diameter::new(*group, circle, 2.0 * radius))?)
+        } else {
+            None
+        };
+
+        Ok(RegularPolygon {
+            center: center_point,
+            circle,
+            points,
+            lines,
+            on_circle,
+            equal_length,
+            diameter,
+        })
+    }
+
+    // Fix the distance between two points on a workplane, if dimensioned.
+    fn dimension(
+        &mut self,
+        group: &Group,
+        workplane: &EntityHandle<Workplane>,
+        [point_a, point_b]: [EntityHandle<Point>; 2],
+        distance: f64,
+        dimensioned: bool,
+    ) -> Result<Option<ConstraintHandle<PtPtDistance>>, &'static str> {
+        if !dimensioned {
+            return Ok(None);
+        }
+
+        self.constrain(PtPtDistance::new(
+            *group,
+            point_a,
+            point_b,
+            distance,
+            Some(*workplane),
+        ))
+        .map(Some)
+    }
+
+    // Join four corners in a ring, with `lines[i]` going from `points[i]` to the next
+    // corner around.
+    fn sketch_ring(
+        &mut self,
+        group: &Group,
+        points: [EntityHandle<Point>; 4],
+    ) -> Result<[EntityHandle<LineSegment>; 4], &'static str> {
+        let [a, b, c, d] = [0, 1, 2, 3]
+            .map(|i| self.sketch(LineSegment::new(*group, points[i], points[(i + 1) % 4])));
+
+        Ok([a?, b?, c?, d?])
+    }
+
+    fn sketch_points(
+        &mut self,
+        group: &Group,
+        workplane: &EntityHandle<Workplane>,
+        coords: &[[f64; 2]],
+    ) -> Result<Vec<EntityHandle<Point>>, &'static str> {
+        coords
+            .iter()
+            .map(|coords| self.sketch(Point::new_on_workplane(*group, *workplane, *coords)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity::{EntityHandle, LineSegment, Normal, Point, Workplane},
+        len_within_tolerance,
+        system::SolveResult,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn share_end_points() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let g = sys.add_group();
+        let polyline = sys
+            .sketch_polyline(&g, &workplane, &[[0.0, 0.0], [5.0, 0.0], [5.0, 5.0]])
+            .expect("polyline created");
+        assert_eq!(polyline.points.len(), 3);
+        assert_eq!(polyline.lines.len(), 2);
+
+        for (i, line) in polyline.lines.iter().enumerate() {
+            let LineSegment {
+                point_a, point_b, ..
+            } = sys.entity_data(line).expect("line found");
+            assert_eq!(point_a, polyline.points[i]);
+            assert_eq!(point_b, polyline.points[i + 1]);
+        }
+
+        let profiles = sys.profiles(&g, &workplane).expect("profiles found");
+        assert!(profiles.loops.is_empty());
+        assert_eq!(profiles.chains.len(), 1);
+
+        assert!(sys.sketch_polyline(&g, &workplane, &[[0.0, 0.0]]).is_err());
+        assert!(sys
+            .sketch_polygon(&g, &workplane, &[[0.0, 0.0], [1.0, 0.0]])
+            .is_err());
+    }
+
+    #[test]
+    fn solve_shapes() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let coords = |sys: &System, point: &EntityHandle<Point>| match sys
+            .entity_data(point)
+            .expect("point found")
+        {
+            Point::OnWorkplane { coords, .. } => coords,
+            Point::In3d { .. } => unreachable!(),
+        };
+
+        let rectangle_g = sys.add_group();
+        let rectangle = sys
+            .sketch_rectangle(&rectangle_g, &workplane, [3.0, 4.0], [-1.0, 1.0], true)
+            .expect("rectangle created");
+        let center = sys
+            .sketch(Point::new_on_workplane(workplane_g, workplane, [2.0, -1.0]))
+            .expect("center created");
+        let centered_g = sys.add_group();
+        let centered = sys
+            .sketch_centered_rectangle(&centered_g, &workplane, &center, [6.0, 2.0], true)
+            .expect("centered rectangle created");
+        let slot_g = sys.add_group();
+        let slot = sys
+            .sketch_slot(&slot_g, &workplane, [[0.0, 0.0], [4.0, 3.0]], 1.0, true)
+            .expect("slot created");
+        let polygon_g = sys.add_group();
+        let polygon = sys
+            .sketch_regular_polygon(&polygon_g, &workplane, [1.0, 1.0], 2.0, 6, true)
+            .expect("polygon created");
+
+        let before: Vec<[f64; 2]> = rectangle
+            .points
+            .iter()
+            .chain(&centered.points)
+            .chain(&slot.points)
+            .chain(&polygon.points)
+            .map(|point| coords(&sys, point))
+            .collect();
+
+        for (g, expected_dof) in [
+            (rectangle_g, 2),
+            (centered_g, 0),
+            (slot_g, 3),
+            (polygon_g, 3),
+        ] {
+            match sys.solve(&g) {
+                SolveResult::Ok { dof, .. } => assert_eq!(dof, expected_dof),
+                SolveResult::Fail { .. } => panic!("shape should solve"),
+            }
+        }
+
+        let after: Vec<[f64; 2]> = rectangle
+            .points
+            .iter()
+            .chain(&centered.points)
+            .chain(&slot.points)
+            .chain(&polygon.points)
+            .map(|point| coords(&sys, point))
+            .collect();
+        for (before, after) in before.iter().zip(&after) {
+            len_within_tolerance!(before[0], after[0]);
+            len_within_tolerance!(before[1], after[1]);
+        }
+
+        assert_eq!(coords(&sys, &rectangle.points[0]), [-1.0, 1.0]);
+        assert_eq!(coords(&sys, &centered.points[2]), [5.0, 0.0]);
+        assert_eq!(polygon.lines.len(), 6);
+        assert_eq!(polygon.equal_length.len(), 5);
+
+        // The centered rectangle follows its center point.
+        sys.update_entity(&center, |point| {
+            if let Point::OnWorkplane { coords, .. } = point {
+                *coords = [4.0, -3.0];
+            }
+        })
+        .expect("center updated");
+        match sys.solve(&centered_g) {
+            SolveResult::Ok { dof, .. } => assert_eq!(dof, 0),
+            SolveResult::Fail { .. } => panic!("shape should solve"),
+        }
+        let [x, y] = coords(&sys, &centered.points[2]);
+        len_within_tolerance!(x, 7.0);
+        len_within_tolerance!(y, -2.0);
+
+        // The construction circle is not part of the outline.
+        assert!(sys.is_construction(&polygon.circle));
+        for g in [centered_g, polygon_g] {
+            let profiles = sys.profiles(&g, &workplane).expect("profiles found");
+            assert_eq!(profiles.loops.len(), 1);
+            assert!(profiles.chains.is_empty());
+            assert!(sys
+                .validate_profiles(&g, &workplane, 0.1)
+                .expect("profiles checked")
+                .is_empty());
+        }
+
+        assert!(sys
+            .sketch_rectangle(&rectangle_g, &workplane, [0.0, 0.0], [0.0, 1.0], false)
+            .is_err());
+        assert!(sys
+            .sketch_slot(&slot_g, &workplane, [[0.0, 0.0], [0.0, 0.0]], 1.0, false)
+            .is_err());
+        assert!(sys
+            .sketch_regular_polygon(&polygon_g, &workplane, [0.0, 0.0], 1.0, 2, false)
+            .is_err());
+    }
+}
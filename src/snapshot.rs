@@ -0,0 +1,290 @@
+/*!
+A flat, serializable description of the curves in a group, for rendering.
+
+[`System::geometry_snapshot`] resolves every curve in a group into world coordinates,
+so that a renderer can draw it without following references to points, normals and
+workplanes. Each primitive keeps the handle of the entity it was made from, and the
+[`Status`] of that entity.
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::{SLVS_E_ARC_OF_CIRCLE, SLVS_E_CIRCLE, SLVS_E_CUBIC, SLVS_E_LINE_SEGMENT},
+    element::AsHandle,
+    entity::{ArcOfCircle, Circle, Cubic, EntityHandle, LineSegment},
+    group::Group,
+    status::Status,
+    utils::quaternion_n,
+    System,
+};
+
+/// A curve that can be drawn, in world coordinates.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Primitive {
+    /// Straight segments joining each point to the next.
+    Polyline {
+        entity: EntityHandle<LineSegment>,
+        points: Vec<[f64; 3]>,
+    },
+    /// An arc going counter-clockwise around `normal`, from `start` to `end`.
+    Arc {
+        entity: EntityHandle<ArcOfCircle>,
+        center: [f64; 3],
+        start: [f64; 3],
+        end: [f64; 3],
+        normal: [f64; 3],
+        radius: f64,
+        /// The angle swept by the arc, in degrees.
+        sweep: f64,
+    },
+    Circle {
+        entity: EntityHandle<Circle>,
+        center: [f64; 3],
+        normal: [f64; 3],
+        radius: f64,
+    },
+    /// A cubic Bezier curve, with its start point, start control point, end control
+    /// point and end point.
+    Bezier {
+        entity: EntityHandle<Cubic>,
+        control_points: [[f64; 3]; 4],
+    },
+}
+
+/// A primitive, along with the constraint status of the entity it was made from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Drawable {
+    pub primitive: Primitive,
+    pub status: Status,
+}
+
+/// The curves in a group, found by [`System::geometry_snapshot`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GeometrySnapshot {
+    pub group: Group,
+    pub drawables: Vec<Drawable>,
+}
+
+impl System {
+    /// Resolve the line segments, arcs, circles and cubic curves in a group into
+    /// primitives in world coordinates.
+    ///
+    /// The status of each primitive is found with [`System::constraint_status`], so
+    /// the group should be solved first.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to take a snapshot of.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{LineSegment, Point},
+    ///     snapshot::Primitive,
+    ///     status::Status,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let start = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("point created");
+    /// let end = sys
+    ///     .sketch(Point::new_in_3d(g, [10.0, 0.0, 0.0]))
+    ///     .expect("point created");
+    /// let line = sys
+    ///     .sketch(LineSegment::new(g, start, end))
+    ///     .expect("line created");
+    ///
+    /// let snapshot = sys.geometry_snapshot(&g).expect("snapshot taken");
+    /// assert_eq!(snapshot.drawables.len(), 1);
+    /// assert_eq!(
+    ///     snapshot.drawables[0].primitive,
+    ///     Primitive::Polyline {
+    ///         entity: line,
+    ///         points: vec![[0.0, 0.0, 0.0], [10.0, 0.0, 0.0]],
+    ///     }
+    /// );
+    /// assert_eq!(
+    ///     snapshot.drawables[0].status,
+    ///     Status::UnderConstrained { dof: 6 }
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the geometry of an entity in the group cannot be resolved.
+    pub fn geometry_snapshot(&mut self, group: &Group) -> Result<GeometrySnapshot, &'static str> {
+        let statuses = self.constraint_status(group)?.entities;
+        let status_of = |h: u32| {
+            statuses
+                .iter()
+                .find(|entity_status| entity_status.entity.handle() == h)
+                .map_or(Status::FullyConstrained, |entity_status| {
+                    entity_status.status
+                })
+        };
+
+        let mut drawables = Vec::new();
+        for slvs_entity in self
+            .entities
+            .list
+            .iter()
+            .filter(|slvs_entity| slvs_entity.group == group.handle())
+        {
+            let primitive = match slvs_entity.type_ as _ {
+                SLVS_E_LINE_SEGMENT => {
+                    let entity = EntityHandle::new(slvs_entity.h);
+                    let line = self.line_geometry(&entity)?;
+
+                    Primitive::Polyline {
+                        entity,
+                        points: vec![line.start, line.end],
+                    }
+                }
+                SLVS_E_ARC_OF_CIRCLE => {
+                    let entity = EntityHandle::new(slvs_entity.h);
+                    let arc = self.arc_geometry(&entity)?;
+
+                    Primitive::Arc {
+                        entity,
+                        center: arc.center,
+                        start: arc.start,
+                        end: arc.end,
+                        normal: quaternion_n(self.normal_quaternion(slvs_entity.normal)?),
+                        radius: arc.radius,
+                        sweep: arc.sweep(),
+                    }
+                }
+                SLVS_E_CIRCLE => {
+                    let entity = EntityHandle::new(slvs_entity.h);
+                    let circle = self.circle_geometry(&entity)?;
+
+                    Primitive::Circle {
+                        entity,
+                        center: circle.center,
+                        normal: circle.normal,
+                        radius: circle.radius,
+                    }
+                }
+                SLVS_E_CUBIC => {
+                    let entity = EntityHandle::new(slvs_entity.h);
+
+                    Primitive::Bezier {
+                        entity,
+                        control_points: self.cubic_geometry(&entity)?.control_points,
+                    }
+                }
+                _ => continue,
+            };
+
+            drawables.push(Drawable {
+                primitive,
+                status: status_of(slvs_entity.h),
+            });
+        }
+
+        Ok(GeometrySnapshot {
+            group: *group,
+            drawables,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constraint::{Diameter, WhereDragged},
+        entity::{ArcOfCircle, Circle, Distance, Normal, Point, Workplane},
+        len_within_tolerance,
+        snapshot::Primitive,
+        status::Status,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn arc_and_circle_on_workplane() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 3.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let g = sys.add_group();
+        let center = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 0.0]))
+            .expect("point created");
+        let start = sys
+            .sketch(Point::new_on_workplane(g, workplane, [5.0, 0.0]))
+            .expect("point created");
+        let end = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 5.0]))
+            .expect("point created");
+        let arc = sys
+            .sketch(ArcOfCircle::new(g, workplane, center, start, end))
+            .expect("arc created");
+
+        let circle_center = sys
+            .sketch(Point::new_on_workplane(g, workplane, [20.0, 0.0]))
+            .expect("point created");
+        let radius = sys.sketch(Distance::new(g, 4.0)).expect("distance created");
+        let circle = sys
+            .sketch(Circle::new(g, normal, circle_center, radius))
+            .expect("circle created");
+        sys.constrain(WhereDragged::new(g, circle_center, Some(workplane)))
+            .expect("constraint added");
+        sys.constrain(Diameter::new(g, circle, 8.0))
+            .expect("constraint added");
+
+        let snapshot = sys.geometry_snapshot(&g).expect("snapshot taken");
+        assert_eq!(snapshot.drawables.len(), 2);
+
+        if let Primitive::Arc {
+            entity,
+            end,
+            normal,
+            radius,
+            sweep,
+            ..
+        } = snapshot.drawables[0].primitive
+        {
+            assert_eq!(entity, arc);
+            len_within_tolerance!(end[1], 5.0);
+            len_within_tolerance!(end[2], 3.0);
+            len_within_tolerance!(normal[2], 1.0);
+            len_within_tolerance!(radius, 5.0);
+            len_within_tolerance!(sweep, 90.0);
+        } else {
+            unreachable!()
+        }
+        assert!(matches!(
+            snapshot.drawables[0].status,
+            Status::UnderConstrained { .. }
+        ));
+
+        assert_eq!(
+            snapshot.drawables[1].primitive,
+            Primitive::Circle {
+                entity: circle,
+                center: [20.0, 0.0, 3.0],
+                normal: [0.0, 0.0, 1.0],
+                radius: 4.0,
+            }
+        );
+        assert_eq!(snapshot.drawables[1].status, Status::FullyConstrained);
+    }
+}
@@ -0,0 +1,483 @@
+/*!
+Soft constraints, which express a preference rather than a requirement.
+
+A soft constraint is added with [`System::constrain_soft`]. When solving, the group is
+first solved with only the hard constraints. The remaining freedom is then used to
+minimize the weighted sum of squared residuals of the soft constraints, while keeping
+every hard constraint satisfied. Soft constraints therefore never cause a solve to fail.
+
+Each soft constraint has a weight and a priority. Within one priority level,
+conflicting soft constraints are traded off according to their weights. Each level of
+priority makes a soft constraint [`PRIORITY_SCALE`] times more important, so that
+higher priorities are effectively satisfied first. Priorities go up to
+[`MAX_PRIORITY`], beyond which the levels can no longer be told apart in double
+precision.
+
+Parameters of the entity set with [`System::set_dragged`] are not moved while the soft
+constraints are satisfied.
+
+The residual of every soft constraint in a solved group is reported by
+[`System::soft_statuses`].
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::{Slvs_hConstraint, SLVS_C_WHERE_DRAGGED},
+    constraint::{AsConstraintData, AsConstraintHandle, ConstraintHandle},
+    element::AsHandle,
+    equations::EquationSource,
+    group::Group,
+    linalg,
+    system::{SolveResult, SOLVE_TOLERANCE},
+    System,
+};
+
+/// The factor by which each level of priority scales the weight of a soft constraint.
+pub const PRIORITY_SCALE: f64 = 1000.0;
+/// The highest priority that a soft constraint can have.
+pub const MAX_PRIORITY: u32 = 4;
+
+const MAX_ITERATIONS: usize = 50;
+const MAX_BACKTRACKS: usize = 8;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SoftConstraint {
+    weight: f64,
+    priority: u32,
+    // For WhereDragged, the parameter values of the point when it was constrained.
+    target: Vec<f64>,
+}
+
+impl SoftConstraint {
+    fn scaled_weight(&self) -> f64 {
+        scaled_weight(self.weight, self.priority)
+    }
+}
+
+/// The state of a soft constraint, found by [`System::soft_statuses`].
+#[derive(Debug)]
+pub struct SoftStatus {
+    pub constraint: Box<dyn AsConstraintHandle>,
+    pub weight: f64,
+    pub priority: u32,
+    /// The root of the sum of squared residuals of the constraint's equations. This
+    /// is zero when the constraint is satisfied exactly.
+    pub residual: f64,
+}
+
+impl System {
+    /// Add a constraint to the system that is satisfied as closely as possible, but
+    /// not necessarily exactly.
+    ///
+    /// For [`WhereDragged`][crate::constraint::WhereDragged], the point is pulled
+    /// towards its position at the time this method is called.
+    ///
+    /// # Arguments
+    ///
+    /// * `constraint_data` - The constraint to be added.
+    /// * `weight` - How strongly the constraint is preferred over other soft constraints
+    ///   with the same priority. Must be positive.
+    /// * `priority` - Soft constraints with higher priorities are preferred over those
+    ///   with lower priorities. At most [`MAX_PRIORITY`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     constraint::{PtPtDistance, WhereDragged},
+    ///     entity::Point,
+    ///     system::SolveResult,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g1 = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g1, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    ///
+    /// let g2 = sys.add_group();
+    /// let p = sys
+    ///     .sketch(Point::new_in_3d(g2, [3.0, 4.0, 0.0]))
+    ///     .expect("p created");
+    ///
+    /// // p must be 10 units from the origin, but would prefer to stay where it is.
+    /// sys.constrain(PtPtDistance::new(g2, origin, p, 10.0, None))
+    ///     .expect("distance constraint added");
+    /// sys.constrain_soft(WhereDragged::new(g2, p, None), 1.0, 0)
+    ///     .expect("soft constraint added");
+    ///
+    /// if let SolveResult::Ok { .. } = sys.solve(&g2) {
+    ///     let soft = sys.soft_statuses(&g2);
+    ///     assert!((soft[0].residual - 5.0).abs() < 1e-3);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `weight` is not positive, if `priority` is above
+    /// [`MAX_PRIORITY`], if the weight scaled by the priority is not finite, or if any
+    /// of the entities referenced in `constraint_data` are not found in the system.
+    pub fn constrain_soft<C: AsConstraintData>(
+        &mut self,
+        constraint_data: C,
+        weight: f64,
+        priority: u32,
+    ) -> Result<ConstraintHandle<C>, &'static str> {
+        if !(weight > 0.0 && weight.is_finite()) {
+            return Err("Weight of soft constraint must be positive.");
+        }
+        if priority > MAX_PRIORITY {
+            return Err("Priority of soft constraint is above the maximum.");
+        }
+        if !scaled_weight(weight, priority).is_finite() {
+            return Err("Weight of soft constraint is too large for its priority.");
+        }
+
+        let constraint_handle = self.constrain(constraint_data)?;
+        let slvs_constraint = self.slvs_constraint(constraint_handle.handle())?;
+        let target = if SLVS_C_WHERE_DRAGGED == slvs_constraint.type_ as _ {
+            self.constraint_residuals(slvs_constraint)?
+        } else {
+            Vec::new()
+        };
+
+        self.soft.insert(
+            constraint_handle.handle(),
+            SoftConstraint {
+                weight,
+                priority,
+                target,
+            },
+        );
+
+        Ok(constraint_handle)
+    }
+
+    /// The status of each soft constraint in a group, at the current values of its
+    /// parameters.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to report the soft constraints of.
+    pub fn soft_statuses(&self, group: &Group) -> Vec<SoftStatus> {
+        self.soft
+            .iter()
+            .filter_map(|(&h, soft_constraint)| {
+                let slvs_constraint = *self.slvs_constraint(h).ok()?;
+                if slvs_constraint.group != group.handle() {
+                    return None;
+                }
+
+                Some(SoftStatus {
+                    constraint: self.boxed_constraint_handle(slvs_constraint),
+                    weight: soft_constraint.weight,
+                    priority: soft_constraint.priority,
+                    residual: self
+                        .soft_residuals(h, soft_constraint)
+                        .map_or(f64::NAN, |residuals| linalg::norm(&residuals)),
+                })
+            })
+            .collect()
+    }
+
+    // Solve the hard constraints, then use the remaining freedom to satisfy the soft
+    // constraints in the group as closely as possible.
+    pub(crate) fn solve_soft(
+        &mut self,
+        group: &Group,
+        excluded: &[Slvs_hConstraint],
+    ) -> SolveResult {
+        let soft: Vec<(Slvs_hConstraint, SoftConstraint)> = self
+            .soft
+            .iter()
+            .filter(|(&h, _)| {
+                self.slvs_constraint(h)
+                    .is_ok_and(|slvs_constraint| slvs_constraint.group == group.handle())
+            })
+            .map(|(&h, soft_constraint)| (h, soft_constraint.clone()))
+            .collect();
+
+        let excluded: Vec<Slvs_hConstraint> = excluded
+            .iter()
+            .copied()
+            .chain(soft.iter().map(|&(h, _)| h))
+            .collect();
+        let result = self.solve_excluding(group, &excluded);
+
+        if let SolveResult::Ok { .. } = result {
+            if !soft.is_empty() {
+                // A failure to improve on the soft constraints still leaves the hard
+                // constraints satisfied, so it is not reported.
+                let _ = self.refine_soft(group, &excluded, &soft);
+            }
+        }
+
+        result
+    }
+
+    // Gauss-Newton iterations on the weighted soft residuals, with each step restricted
+    // to the null space of the hard constraint Jacobian. The hard constraints are
+    // solved again after each step, to correct for their curvature.
+    fn refine_soft(
+        &mut self,
+        group: &Group,
+        excluded: &[Slvs_hConstraint],
+        soft: &[(Slvs_hConstraint, SoftConstraint)],
+    ) -> Result<(), &'static str> {
+        let hard_constraints: Vec<Slvs_hConstraint> = self
+            .constraints
+            .list
+            .iter()
+            .filter(|slvs_constraint| {
+                slvs_constraint.group == group.handle() && !excluded.contains(&slvs_constraint.h)
+            })
+            .map(|slvs_constraint| slvs_constraint.h)
+            .collect();
+        let hard_equations = self.group_equations(group.handle(), &hard_constraints);
+        let soft_equations: Vec<EquationSource> = soft
+            .iter()
+            .map(|&(h, _)| EquationSource::Constraint(h))
+            .collect();
+
+        let mut cost = self.soft_cost(soft)?;
+
+        for _ in 0..MAX_ITERATIONS {
+            let hard_jacobian = self.jacobian(group.handle(), &hard_equations)?;
+            let soft_jacobian = self.jacobian(group.handle(), &soft_equations)?;
+            // The parameters of the dragged entity are held where they are.
+            let mut hard_rows = hard_jacobian.rows.clone();
+            for (col, param_h) in hard_jacobian.params.iter().enumerate() {
+                if self.dragged.contains(param_h) {
+                    let mut row = vec![0.0; hard_jacobian.params.len()];
+                    row[col] = 1.0;
+                    hard_rows.push(row);
+                }
+            }
+            let null_space = linalg::null_space(&hard_rows, hard_jacobian.params.len());
+            if null_space.is_empty() {
+                break;
+            }
+
+            let mut weights = Vec::new();
+            let mut residuals = Vec::new();
+            for (h, soft_constraint) in soft {
+                let constraint_residuals = self.soft_residuals(*h, soft_constraint)?;
+                weights.extend(vec![
+                    soft_constraint.scaled_weight();
+                    constraint_residuals.len()
+                ]);
+                residuals.extend(constraint_residuals);
+            }
+
+            // Normal equations for the step, in null space coordinates.
+            let reduced: Vec<Vec<f64>> = soft_jacobian
+                .rows
+                .iter()
+                .map(|row| null_space.iter().map(|v| linalg::dot(row, v)).collect())
+                .collect();
+            let k = null_space.len();
+            let mut normal = vec![vec![0.0; k]; k];
+            let mut rhs = vec![0.0; k];
+            for ((row, weight), residual) in reduced.iter().zip(&weights).zip(&residuals) {
+                for i in 0..k {
+                    rhs[i] -= weight * residual * row[i];
+                    for j in 0..k {
+                        normal[i][j] += weight * row[i] * row[j];
+                    }
+                }
+            }
+            let damping = 1e-9 * (0..k).fold(1.0_f64, |max, i| max.max(normal[i][i]));
+            (0..k).for_each(|i| normal[i][i] += damping);
+
+            let Some(coords) = linalg::solve(&normal, &rhs) else {
+                break;
+            };
+            let step: Vec<f64> = (0..hard_jacobian.params.len())
+                .map(|col| {
+                    coords
+                        .iter()
+                        .zip(&null_space)
+                        .map(|(c, v)| c * v[col])
+                        .sum()
+                })
+                .collect();
+
+            let start = self.params.list.clone();
+            let mut scale = 1.0;
+            let mut improved = None;
+            for _ in 0..MAX_BACKTRACKS {
+                for (&param_h, delta) in hard_jacobian.params.iter().zip(&step) {
+                    self.mut_slvs_param(param_h)?.val += scale * delta;
+                }
+                if let SolveResult::Ok { .. } = self.solve_excluding(group, excluded) {
+                    let new_cost = self.soft_cost(soft)?;
+                    if new_cost < cost {
+                        improved = Some(new_cost);
+                        break;
+                    }
+                }
+                self.params.list.clone_from(&start);
+                scale /= 2.0;
+            }
+
+            match improved {
+                Some(new_cost) => {
+                    let converged = cost - new_cost <= 1e-12 * cost
+                        || scale * linalg::norm(&step) < SOLVE_TOLERANCE;
+                    cost = new_cost;
+                    if converged {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn soft_residuals(
+        &self,
+        h: Slvs_hConstraint,
+        soft_constraint: &SoftConstraint,
+    ) -> Result<Vec<f64>, &'static str> {
+        let mut residuals = self.constraint_residuals(self.slvs_constraint(h)?)?;
+        residuals
+            .iter_mut()
+            .zip(&soft_constraint.target)
+            .for_each(|(residual, target)| *residual -= target);
+
+        Ok(residuals)
+    }
+
+    fn soft_cost(&self, soft: &[(Slvs_hConstraint, SoftConstraint)]) -> Result<f64, &'static str> {
+        soft.iter().try_fold(0.0, |cost, (h, soft_constraint)| {
+            let residuals = self.soft_residuals(*h, soft_constraint)?;
+            Ok(cost + soft_constraint.scaled_weight() * linalg::dot(&residuals, &residuals))
+        })
+    }
+}
+
+fn scaled_weight(weight: f64, priority: u32) -> f64 {
+    weight * PRIORITY_SCALE.powi(priority.min(MAX_PRIORITY) as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constraint::{Horizontal, PtPtDistance, Vertical},
+        element::AsHandle,
+        entity::{LineSegment, Normal, Point, Workplane},
+        soft::MAX_PRIORITY,
+        system::SolveResult,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn priorities() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+        let center = sys
+            .sketch(Point::new_on_workplane(workplane_g, workplane, [0.0, 0.0]))
+            .expect("point created");
+
+        let g = sys.add_group();
+        let end = sys
+            .sketch(Point::new_on_workplane(g, workplane, [6.0, 8.0]))
+            .expect("point created");
+        let line = sys
+            .sketch(LineSegment::new(g, center, end))
+            .expect("line created");
+        sys.constrain(PtPtDistance::new(g, center, end, 10.0, Some(workplane)))
+            .expect("constraint added");
+
+        // The line cannot be both horizontal and vertical, but the solve still succeeds.
+        let horizontal = sys
+            .constrain_soft(Horizontal::from_line(g, workplane, line), 1.0, 0)
+            .expect("soft constraint added");
+        sys.constrain_soft(Vertical::from_line(g, workplane, line), 1.0, 1)
+            .expect("soft constraint added");
+
+        match sys.solve(&g) {
+            SolveResult::Ok { dof } => {
+                let soft = sys.soft_statuses(&g);
+                assert_eq!(dof, 1);
+                assert_eq!(soft.len(), 2);
+                assert_eq!(soft[0].constraint.handle(), horizontal.handle());
+                assert!((soft[0].residual - 10.0).abs() < 1e-3);
+                assert!(soft[1].residual < 1e-3);
+            }
+            SolveResult::Fail { .. } => panic!("solve failed"),
+        }
+    }
+
+    #[test]
+    fn limits_and_dragged() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let g = sys.add_group();
+        let [start, end] = [[0.0, 0.0], [6.0, 8.0]].map(|coords| {
+            sys.sketch(Point::new_on_workplane(g, workplane, coords))
+                .expect("point created")
+        });
+        let line = sys
+            .sketch(LineSegment::new(g, start, end))
+            .expect("line created");
+        sys.constrain(PtPtDistance::new(g, start, end, 10.0, Some(workplane)))
+            .expect("constraint added");
+
+        for (weight, priority) in [
+            (1.0, MAX_PRIORITY + 1),
+            (1.0, u32::MAX),
+            (f64::MAX, MAX_PRIORITY),
+        ] {
+            assert!(sys
+                .constrain_soft(Horizontal::from_line(g, workplane, line), weight, priority)
+                .is_err());
+        }
+
+        // The end is dragged, so the start moves to level the line. The end is only
+        // nudged by the solver, while it corrects the distance after each step.
+        sys.constrain_soft(Horizontal::from_line(g, workplane, line), 1.0, MAX_PRIORITY)
+            .expect("soft constraint added");
+        sys.set_dragged(&end).expect("end dragged");
+        assert!(matches!(sys.solve(&g), SolveResult::Ok { .. }));
+        sys.clear_dragged();
+
+        if let Point::OnWorkplane { coords, .. } = sys.entity_data(&end).expect("point found") {
+            assert!((coords[0] - 6.0).abs() < 1e-3);
+            assert!((coords[1] - 8.0).abs() < 1e-3);
+        }
+        assert!(sys.soft_statuses(&g)[0].residual < 1e-3);
+    }
+}
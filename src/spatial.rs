@@ -0,0 +1,735 @@
+/*!
+A spatial index over the geometry of a group, for picking and selection in an editor.
+
+[`System::spatial_index`] resolves the points, lines, arcs, circles and cubic curves in
+a group into the coordinates of a workplane, and files them in a uniform grid. Curves
+are stored as polylines, following the default [`Tessellation`], so distances to them
+are accurate to its chordal deviation.
+
+The system keeps each index it builds, and brings it up to date after
+[`System::solve`] and [`System::update_entity`]. Only the entities whose geometry
+changed are filed again, unless the workplane of the index itself has moved. Entities
+that were sketched or deleted since are picked up the next time the index is asked for.
+*/
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::{
+    bindings::{
+        Slvs_Entity, Slvs_hEntity, Slvs_hGroup, Slvs_hParam, SLVS_E_ARC_OF_CIRCLE, SLVS_E_CIRCLE,
+        SLVS_E_CUBIC, SLVS_E_LINE_SEGMENT, SLVS_E_POINT_IN_2D, SLVS_E_POINT_IN_3D,
+    },
+    element::AsHandle,
+    entity::{AsEntityHandle, EntityHandle, Workplane},
+    geometry::in_polygon,
+    group::Group,
+    tessellation::Tessellation,
+    utils::distance,
+    System,
+};
+
+// Entities covering more grid cells than this are kept outside of the grid, and are
+// checked by every query.
+const MAX_CELLS: i64 = 1024;
+
+/// How an entity is chosen by an area selection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// The entity lies entirely within the area.
+    Inside,
+    /// Some part of the entity lies within the area.
+    Touching,
+}
+
+/// An entity found near a point, by [`SpatialIndex::pick`].
+#[derive(Debug)]
+pub struct Hit {
+    pub entity: Box<dyn AsEntityHandle>,
+    /// The distance from the point to the entity, in the coordinates of the workplane.
+    pub distance: f64,
+}
+
+/// An index of the entities in a group, made by [`System::spatial_index`].
+#[derive(Debug)]
+pub struct SpatialIndex {
+    group: Slvs_hGroup,
+    workplane: EntityHandle<Workplane>,
+    cell_size: f64,
+    // The parameter values of the workplane, when the index was last refreshed.
+    workplane_params: Vec<f64>,
+    entries: BTreeMap<Slvs_hEntity, Entry>,
+    cells: HashMap<(i64, i64), Vec<Slvs_hEntity>>,
+    oversized: BTreeSet<Slvs_hEntity>,
+    // Entities that could not be resolved onto the workplane, with their parameter
+    // values when they were last tried.
+    unresolved: BTreeMap<Slvs_hEntity, (Slvs_Entity, Vec<f64>)>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    slvs_entity: Slvs_Entity,
+    // The parameter values of the entity, the entities it references and the
+    // workplanes it is projected from, used to tell when it needs to be filed again.
+    params: Vec<f64>,
+    // A single point, or a polyline along a curve.
+    outline: Vec<[f64; 2]>,
+    bounds: Bounds,
+}
+
+impl SpatialIndex {
+    /// Find the entity closest to a point, within `radius` of it.
+    ///
+    /// Points are preferred over curves at the same distance, so that picking the end
+    /// of a line finds its end point.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The point to search around, in the coordinates of the workplane.
+    /// * `radius` - How far away from `point` an entity can be.
+    pub fn pick(&self, point: [f64; 2], radius: f64) -> Option<Hit> {
+        self.pick_all(point, radius).into_iter().next()
+    }
+
+    /// Find every entity within `radius` of a point, from the closest to the furthest.
+    pub fn pick_all(&self, point: [f64; 2], radius: f64) -> Vec<Hit> {
+        let area = Bounds {
+            min: [point[0] - radius, point[1] - radius],
+            max: [point[0] + radius, point[1] + radius],
+        };
+
+        let mut hits: Vec<(f64, bool, &Entry)> = self
+            .candidates(&area)
+            .into_iter()
+            .filter_map(|entry| {
+                let distance = outline_distance(&entry.outline, point);
+                (distance <= radius).then_some((distance, entry.outline.len() > 1, entry))
+            })
+            .collect();
+        hits.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+        hits.into_iter()
+            .map(|(distance, _, entry)| Hit {
+                entity: entry.slvs_entity.into(),
+                distance,
+            })
+            .collect()
+    }
+
+    /// Find the entities within a rectangle.
+    ///
+    /// # Arguments
+    ///
+    /// * `corner_a`, `corner_b` - Opposite corners of the rectangle, in the
+    ///   coordinates of the workplane.
+    /// * `mode` - Whether entities need to be entirely within the rectangle.
+    pub fn select_rectangle(
+        &self,
+        corner_a: [f64; 2],
+        corner_b: [f64; 2],
+        mode: SelectionMode,
+    ) -> Vec<Box<dyn AsEntityHandle>> {
+        self.select_lasso(
+            &[
+                corner_a,
+                [corner_b[0], corner_a[1]],
+                corner_b,
+                [corner_a[0], corner_b[1]],
+            ],
+            mode,
+        )
+    }
+
+    /// Find the entities within a polygon, such as one drawn freehand.
+    ///
+    /// # Arguments
+    ///
+    /// * `polygon` - The corners of the polygon, in the coordinates of the workplane.
+    ///   The last corner is joined to the first.
+    /// * `mode` - Whether entities need to be entirely within the polygon.
+    pub fn select_lasso(
+        &self,
+        polygon: &[[f64; 2]],
+        mode: SelectionMode,
+    ) -> Vec<Box<dyn AsEntityHandle>> {
+        let Some(area) = Bounds::around(polygon) else {
+            return Vec::new();
+        };
+        let edges: Vec<[[f64; 2]; 2]> = polygon
+            .iter()
+            .zip(polygon.iter().cycle().skip(1))
+            .map(|(&start, &end)| [start, end])
+            .collect();
+
+        self.candidates(&area)
+            .into_iter()
+            .filter(|entry| {
+                let crosses = entry.outline.windows(2).any(|segment| {
+                    edges
+                        .iter()
+                        .any(|edge| segments_cross(segment[0], segment[1], edge[0], edge[1]))
+                });
+
+                match mode {
+                    SelectionMode::Inside => {
+                        !crosses
+                            && entry
+                                .outline
+                                .iter()
+                                .all(|&point| in_polygon(polygon, point))
+                    }
+                    SelectionMode::Touching => {
+                        crosses
+                            || entry
+                                .outline
+                                .iter()
+                                .any(|&point| in_polygon(polygon, point))
+                    }
+                }
+            })
+            .map(|entry| entry.slvs_entity.into())
+            .collect()
+    }
+
+    /// The entities in the group that could not be resolved onto the workplane, and
+    /// so are not in the index, such as curves that reference deleted entities.
+    pub fn unresolved(&self) -> Vec<Box<dyn AsEntityHandle>> {
+        self.unresolved
+            .values()
+            .map(|(slvs_entity, _)| (*slvs_entity).into())
+            .collect()
+    }
+
+    // The entries that could be within an area, in order of their handles.
+    fn candidates(&self, area: &Bounds) -> Vec<&Entry> {
+        let (low, high) = (self.cell(area.min), self.cell(area.max));
+        let mut handles: BTreeSet<Slvs_hEntity> = self.oversized.clone();
+
+        // Searching a huge area cell by cell is slower than checking every entry.
+        if cell_count(low, high).map_or(true, |count| count > self.entries.len() as i64) {
+            handles.extend(self.entries.keys());
+        } else {
+            for x in low.0..=high.0 {
+                for y in low.1..=high.1 {
+                    if let Some(cell) = self.cells.get(&(x, y)) {
+                        handles.extend(cell);
+                    }
+                }
+            }
+        }
+
+        handles
+            .iter()
+            .map(|h| &self.entries[h])
+            .filter(|entry| entry.bounds.overlaps(area))
+            .collect()
+    }
+
+    fn cell(&self, point: [f64; 2]) -> (i64, i64) {
+        (
+            (point[0] / self.cell_size).floor() as i64,
+            (point[1] / self.cell_size).floor() as i64,
+        )
+    }
+
+    fn insert(&mut self, h: Slvs_hEntity, entry: Entry) {
+        let (low, high) = (self.cell(entry.bounds.min), self.cell(entry.bounds.max));
+
+        if cell_count(low, high).map_or(true, |count| count > MAX_CELLS) {
+            self.oversized.insert(h);
+        } else {
+            for x in low.0..=high.0 {
+                for y in low.1..=high.1 {
+                    self.cells.entry((x, y)).or_default().push(h);
+                }
+            }
+        }
+        self.entries.insert(h, entry);
+    }
+
+    fn remove(&mut self, h: Slvs_hEntity) {
+        self.unresolved.remove(&h);
+        if let Some(entry) = self.entries.remove(&h) {
+            if !self.oversized.remove(&h) {
+                let (low, high) = (self.cell(entry.bounds.min), self.cell(entry.bounds.max));
+                for x in low.0..=high.0 {
+                    for y in low.1..=high.1 {
+                        if let Some(cell) = self.cells.get_mut(&(x, y)) {
+                            cell.retain(|&other| other != h);
+                            if cell.is_empty() {
+                                self.cells.remove(&(x, y));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl System {
+    /// Build a spatial index over the points and curves in a group, in the coordinates
+    /// of a workplane, or get the one already built for them.
+    ///
+    /// The system keeps the index, and brings it up to date after [`System::solve`]
+    /// and [`System::update_entity`]. Entities that were sketched or deleted since the
+    /// index was last asked for are added to or removed from it first. Entities that
+    /// cannot be resolved onto the workplane are left out, and are listed by
+    /// [`SpatialIndex::unresolved`].
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to index.
+    /// * `workplane` - The workplane that queries are made in. Entities that are not
+    ///   on the workplane are projected onto it.
+    /// * `cell_size` - The size of the grid cells that the entities are filed in.
+    ///   Queries are fastest when this is similar to the size of a typical entity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{LineSegment, Normal, Point, Workplane},
+    ///     spatial::SelectionMode,
+    ///     utils::make_quaternion,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// let g = sys.add_group();
+    /// let start = sys
+    ///     .sketch(Point::new_on_workplane(g, workplane, [0.0, 0.0]))
+    ///     .expect("point created");
+    /// let end = sys
+    ///     .sketch(Point::new_on_workplane(g, workplane, [10.0, 0.0]))
+    ///     .expect("point created");
+    /// sys.sketch(LineSegment::new(g, start, end))
+    ///     .expect("line created");
+    ///
+    /// let index = sys
+    ///     .spatial_index(&g, &workplane, 5.0)
+    ///     .expect("index built");
+    ///
+    /// let hit = index.pick([5.0, 0.5], 1.0).expect("line picked");
+    /// assert_eq!(hit.distance, 0.5);
+    ///
+    /// let selected = index.select_rectangle([-1.0, -1.0], [5.0, 1.0], SelectionMode::Inside);
+    /// assert_eq!(selected.len(), 1);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `cell_size` is not positive, or if the workplane is not found
+    /// in the system.
+    pub fn spatial_index(
+        &mut self,
+        group: &Group,
+        workplane: &EntityHandle<Workplane>,
+        cell_size: f64,
+    ) -> Result<&SpatialIndex, &'static str> {
+        if cell_size <= 0.0 {
+            return Err("Cell size must be positive.");
+        }
+
+        let mut index = match self
+            .spatial_indexes
+            .iter()
+            .position(|index| index.group == group.handle() && index.workplane == *workplane)
+        {
+            Some(ix) if self.spatial_indexes[ix].cell_size == cell_size => {
+                self.spatial_indexes.remove(ix)
+            }
+            found => {
+                if let Some(ix) = found {
+                    self.spatial_indexes.remove(ix);
+                }
+                SpatialIndex {
+                    group: group.handle(),
+                    workplane: *workplane,
+                    cell_size,
+                    workplane_params: Vec::new(),
+                    entries: BTreeMap::new(),
+                    cells: HashMap::new(),
+                    oversized: BTreeSet::new(),
+                    unresolved: BTreeMap::new(),
+                }
+            }
+        };
+        self.refresh_spatial_index(&mut index)?;
+        self.spatial_indexes.push(index);
+
+        Ok(self.spatial_indexes.last().unwrap())
+    }
+
+    // Bring every spatial index kept by the system up to date, dropping those whose
+    // workplane has been deleted.
+    pub(crate) fn refresh_spatial_indexes(&mut self) {
+        let mut indexes = std::mem::take(&mut self.spatial_indexes);
+        indexes.retain_mut(|index| self.refresh_spatial_index(index).is_ok());
+        self.spatial_indexes = indexes;
+    }
+
+    // Bring a spatial index up to date with the system, after entities have been
+    // added, deleted, updated or moved by a solve.
+    fn refresh_spatial_index(&self, index: &mut SpatialIndex) -> Result<(), &'static str> {
+        let workplane_params = self.param_values(&self.entity_params(index.workplane.handle())?)?;
+        if workplane_params != index.workplane_params {
+            index.entries.clear();
+            index.cells.clear();
+            index.oversized.clear();
+            index.unresolved.clear();
+            index.workplane_params = workplane_params;
+        }
+
+        let current: BTreeSet<Slvs_hEntity> = self
+            .entities
+            .list
+            .iter()
+            .filter(|slvs_entity| {
+                slvs_entity.group == index.group
+                    && [
+                        SLVS_E_POINT_IN_2D,
+                        SLVS_E_POINT_IN_3D,
+                        SLVS_E_LINE_SEGMENT,
+                        SLVS_E_ARC_OF_CIRCLE,
+                        SLVS_E_CIRCLE,
+                        SLVS_E_CUBIC,
+                    ]
+                    .contains(&(slvs_entity.type_ as _))
+            })
+            .map(|slvs_entity| slvs_entity.h)
+            .collect();
+
+        let removed: Vec<Slvs_hEntity> = index
+            .entries
+            .keys()
+            .chain(index.unresolved.keys())
+            .filter(|h| !current.contains(h))
+            .copied()
+            .collect();
+        for h in removed {
+            index.remove(h);
+        }
+
+        for h in current {
+            let slvs_entity = *self.slvs_entity(h)?;
+            let params = self
+                .filing_params(&slvs_entity, &index.workplane)
+                .unwrap_or_default();
+            if index
+                .entries
+                .get(&h)
+                .map(|entry| &entry.params)
+                .or(index.unresolved.get(&h).map(|(_, params)| params))
+                .is_some_and(|filed| *filed == params)
+            {
+                continue;
+            }
+
+            index.remove(h);
+            let outline = self
+                .tessellate_on(h, &index.workplane, &Tessellation::default())
+                .ok();
+            match outline.and_then(|outline| Some((Bounds::around(&outline)?, outline))) {
+                Some((bounds, outline)) => index.insert(
+                    h,
+                    Entry {
+                        slvs_entity,
+                        params,
+                        outline,
+                        bounds,
+                    },
+                ),
+                None => {
+                    index.unresolved.insert(h, (slvs_entity, params));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // The parameter values that the outline of an entity on a workplane depends on,
+    // including those of any other workplane that it is projected from.
+    fn filing_params(
+        &self,
+        slvs_entity: &Slvs_Entity,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<Vec<f64>, &'static str> {
+        let mut params = self.entity_params(slvs_entity.h)?;
+
+        let mut workplanes = vec![slvs_entity.wrkpl];
+        for &point_h in slvs_entity.point.iter().filter(|&&h| h != 0) {
+            workplanes.push(self.slvs_entity(point_h)?.wrkpl);
+        }
+        for workplane_h in workplanes {
+            if workplane_h == 0 || workplane_h == workplane.handle() {
+                continue;
+            }
+            for param in self.entity_params(workplane_h)? {
+                if !params.contains(&param) {
+                    params.push(param);
+                }
+            }
+        }
+
+        self.param_values(&params)
+    }
+
+    fn param_values(&self, params: &[Slvs_hParam]) -> Result<Vec<f64>, &'static str> {
+        params
+            .iter()
+            .map(|&param| Ok(self.slvs_param(param)?.val))
+            .collect()
+    }
+}
+
+// An axis-aligned bounding box.
+#[derive(Clone, Copy, Debug)]
+struct Bounds {
+    min: [f64; 2],
+    max: [f64; 2],
+}
+
+impl Bounds {
+    fn around(points: &[[f64; 2]]) -> Option<Self> {
+        let first = *points.first()?;
+
+        Some(points.iter().fold(
+            Bounds {
+                min: first,
+                max: first,
+            },
+            |bounds, point| Bounds {
+                min: [bounds.min[0].min(point[0]), bounds.min[1].min(point[1])],
+                max: [bounds.max[0].max(point[0]), bounds.max[1].max(point[1])],
+            },
+        ))
+    }
+
+    fn overlaps(&self, other: &Bounds) -> bool {
+        (0..2).all(|i| self.min[i] <= other.max[i] && other.min[i] <= self.max[i])
+    }
+}
+
+// The number of grid cells from `low` to `high`, or `None` if there are too many to
+// count, as for areas far larger than the cells.
+fn cell_count(low: (i64, i64), high: (i64, i64)) -> Option<i64> {
+    let width = high.0.checked_sub(low.0)?.checked_add(1)?;
+    let height = high.1.checked_sub(low.1)?.checked_add(1)?;
+    width.checked_mul(height)
+}
+
+// The distance from a point to a single point, or to a polyline.
+fn outline_distance(outline: &[[f64; 2]], point: [f64; 2]) -> f64 {
+    if let [single] = outline {
+        return distance(*single, point);
+    }
+
+    outline
+        .windows(2)
+        .map(|segment| {
+            let [start, end] = [segment[0], segment[1]];
+            let direction = [end[0] - start[0], end[1] - start[1]];
+            let length_squared = direction[0].powi(2) + direction[1].powi(2);
+            let t = if length_squared == 0.0 {
+                0.0
+            } else {
+                (((point[0] - start[0]) * direction[0] + (point[1] - start[1]) * direction[1])
+                    / length_squared)
+                    .clamp(0.0, 1.0)
+            };
+
+            distance(
+                point,
+                [start[0] + direction[0] * t, start[1] + direction[1] * t],
+            )
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+// Whether two segments cross or touch.
+fn segments_cross(a0: [f64; 2], a1: [f64; 2], b0: [f64; 2], b1: [f64; 2]) -> bool {
+    let side = |origin: [f64; 2], end: [f64; 2], point: [f64; 2]| {
+        ((end[0] - origin[0]) * (point[1] - origin[1])
+            - (end[1] - origin[1]) * (point[0] - origin[0]))
+            .signum()
+    };
+
+    side(a0, a1, b0) * side(a0, a1, b1) <= 0.0 && side(b0, b1, a0) * side(b0, b1, a1) <= 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        element::AsHandle,
+        entity::{Circle, Distance, LineSegment, Normal, Point, Workplane},
+        spatial::SelectionMode,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn refresh_after_update() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let g = sys.add_group();
+        let mut points = Vec::new();
+        let mut lines = Vec::new();
+        for i in 0..100 {
+            let x = i as f64 * 3.0;
+            let start = sys
+                .sketch(Point::new_on_workplane(g, workplane, [x, 0.0]))
+                .expect("point created");
+            let end = sys
+                .sketch(Point::new_on_workplane(g, workplane, [x + 2.0, 2.0]))
+                .expect("point created");
+            lines.push(
+                sys.sketch(LineSegment::new(g, start, end))
+                    .expect("line created"),
+            );
+            points.push(end);
+        }
+        let radius = sys
+            .sketch(Distance::new(g, 1000.0))
+            .expect("distance created");
+        let circle = sys
+            .sketch(Circle::new(g, normal, points[0], radius))
+            .expect("circle created");
+
+        let index = sys.spatial_index(&g, &workplane, 4.0).expect("index built");
+
+        // The end point of a line is preferred over the line itself.
+        let hit = index.pick([32.1, 2.0], 0.5).expect("picked");
+        assert_eq!(hit.entity.handle(), points[10].handle());
+
+        // The circle is too large for the grid, but is still found.
+        let hit = index.pick([1002.0, 2.0], 0.5).expect("picked");
+        assert_eq!(hit.entity.handle(), circle.handle());
+
+        let inside = index.select_rectangle([-1.0, -1.0], [8.5, 3.0], SelectionMode::Inside);
+        assert_eq!(inside.len(), 9);
+        let touching = index.select_lasso(
+            &[[4.0, -1.0], [4.0, 1.0], [100.0, 1.0]],
+            SelectionMode::Touching,
+        );
+        assert!(touching.len() > 2);
+
+        // Areas too large to count the cells of fall back to checking every entity.
+        let everything =
+            index.select_rectangle([-1e300, -1e300], [1e300, 1e300], SelectionMode::Inside);
+        assert_eq!(everything.len(), 301);
+
+        // Move the end of the first line far away. The system refreshes the index that
+        // it keeps, without it being asked for again.
+        sys.update_entity(&points[0], |point| {
+            if let Point::OnWorkplane { coords, .. } = point {
+                *coords = [-500.0, 0.0];
+            }
+        })
+        .expect("point updated");
+        let index = &sys.spatial_indexes[0];
+
+        let hit = index.pick([-250.0, 0.0], 0.5).expect("picked");
+        assert_eq!(hit.entity.handle(), lines[0].handle());
+        assert!(index.pick([2.0, 2.0], 0.1).is_none());
+    }
+
+    #[test]
+    fn projected_and_unresolved() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+        let other_origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 5.0]))
+            .expect("origin created");
+        let other_workplane = sys
+            .sketch(Workplane::new(workplane_g, other_origin, normal))
+            .expect("workplane created");
+        let tilted = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+            ))
+            .expect("normal created");
+
+        // A point on a workplane above the indexed one, a circle standing up from the
+        // indexed workplane, and a circle whose radius has been deleted.
+        let g = sys.add_group();
+        let point = sys
+            .sketch(Point::new_on_workplane(g, other_workplane, [1.0, 1.0]))
+            .expect("point created");
+        let center = sys
+            .sketch(Point::new_on_workplane(g, workplane, [10.0, 0.0]))
+            .expect("point created");
+        let radius = sys.sketch(Distance::new(g, 1.0)).expect("distance created");
+        let standing = sys
+            .sketch(Circle::new(g, tilted, center, radius))
+            .expect("circle created");
+        let broken_radius = sys.sketch(Distance::new(g, 1.0)).expect("distance created");
+        let broken = sys
+            .sketch(Circle::new(g, normal, center, broken_radius))
+            .expect("circle created");
+        sys.delete_entity(broken_radius).expect("distance deleted");
+
+        let index = sys.spatial_index(&g, &workplane, 1.0).expect("index built");
+        let hit = index.pick([1.0, 1.0], 0.1).expect("picked");
+        assert_eq!(hit.entity.handle(), point.handle());
+        let hit = index.pick([11.0, 0.0], 0.1).expect("picked");
+        assert_eq!(hit.entity.handle(), standing.handle());
+        let unresolved = index.unresolved();
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].handle(), broken.handle());
+
+        // Moving the other workplane moves the point projected from it.
+        sys.update_entity(&other_origin, |point| {
+            if let Point::In3d { coords, .. } = point {
+                *coords = [3.0, 0.0, 5.0];
+            }
+        })
+        .expect("point updated");
+        let index = &sys.spatial_indexes[0];
+        assert!(index.pick([1.0, 1.0], 0.1).is_none());
+        let hit = index.pick([4.0, 1.0], 0.1).expect("picked");
+        assert_eq!(hit.entity.handle(), point.handle());
+    }
+}
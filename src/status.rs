@@ -0,0 +1,320 @@
+/*!
+How well constrained each entity and parameter in a group is.
+
+The status is found from the constraint Jacobian at the current parameter values,
+which should be a solution of the group. A parameter can still move if some motion
+along it leaves every constraint satisfied to first order, and the number of degrees
+of freedom of an entity is the number of independent motions of its parameters. A
+parameter is over-constrained if it appears in a set of constraint equations that
+are linearly dependent, so that one of them could be removed without freeing anything.
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::{Slvs_hEntity, Slvs_hParam},
+    constraint::AsConstraintHandle,
+    element::AsHandle,
+    entity::AsEntityHandle,
+    equations::EquationSource,
+    group::Group,
+    linalg, System,
+};
+
+// Components of a null space vector smaller than this are treated as zero.
+const NULL_TOLERANCE: f64 = 1e-6;
+
+/// Whether an entity or parameter is constrained.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Status {
+    /// It cannot move without violating a constraint.
+    FullyConstrained,
+    /// It can still move in `dof` independent ways.
+    UnderConstrained { dof: usize },
+    /// It takes part in a set of redundant or conflicting constraints.
+    OverConstrained,
+}
+
+/// The status of an entity in the group.
+#[derive(Debug)]
+pub struct EntityStatus {
+    pub entity: Box<dyn AsEntityHandle>,
+    pub status: Status,
+}
+
+/// The status of a parameter in the group.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamStatus {
+    /// The handle of the parameter, as found in [`System::params`].
+    pub param: Slvs_hParam,
+    pub status: Status,
+}
+
+/// Results of [`System::constraint_status`].
+#[derive(Debug)]
+pub struct ConstraintStatus {
+    /// The number of unconstrained degrees of freedom in the group.
+    pub dof: i32,
+    /// The status of each entity in the group.
+    pub entities: Vec<EntityStatus>,
+    /// The status of each parameter in the group.
+    pub params: Vec<ParamStatus>,
+    /// Constraints whose equations depend on the equations of other constraints.
+    pub over_constrained: Vec<Box<dyn AsConstraintHandle>>,
+}
+
+impl System {
+    /// Find out which entities and parameters in a group are fully constrained, which
+    /// can still move, and which take part in an over-constraint.
+    ///
+    /// This is computed around the current parameter values, so the group should be
+    /// solved first. Soft constraints are ignored, and bounded constraints only count
+    /// when they are at one of their limits.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to be analysed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     constraint::PtPtDistance,
+    ///     entity::Point,
+    ///     status::Status,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g1 = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g1, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    ///
+    /// let g2 = sys.add_group();
+    /// let p = sys
+    ///     .sketch(Point::new_in_3d(g2, [10.0, 0.0, 0.0]))
+    ///     .expect("p created");
+    /// sys.constrain(PtPtDistance::new(g2, origin, p, 10.0, None))
+    ///     .expect("distance constraint added");
+    ///
+    /// // p can still move on a sphere around the origin.
+    /// let status = sys.constraint_status(&g2).expect("status found");
+    /// assert_eq!(status.dof, 2);
+    /// assert_eq!(status.entities[0].status, Status::UnderConstrained { dof: 2 });
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the equations of a constraint cannot be evaluated.
+    pub fn constraint_status(&mut self, group: &Group) -> Result<ConstraintStatus, &'static str> {
+        let constraints = self.active_constraints(group.handle());
+        let equations = self.group_equations(group.handle(), &constraints);
+        let jacobian = self.jacobian(group.handle(), &equations)?;
+        let cols = jacobian.params.len();
+
+        let null_space = linalg::null_space(&jacobian.rows, cols);
+
+        // Each vector in the left null space is a combination of equations that
+        // cancels out, and the equations that make it up are over-constrained.
+        let left_null_space = linalg::null_space(
+            &linalg::transpose(&jacobian.rows, cols),
+            jacobian.rows.len(),
+        );
+        let dependent_rows: Vec<bool> = (0..jacobian.rows.len())
+            .map(|row| {
+                left_null_space
+                    .iter()
+                    .any(|v| v[row].abs() > NULL_TOLERANCE)
+            })
+            .collect();
+
+        let mut over_constrained = Vec::new();
+        let mut row = 0;
+        for &equation in &equations {
+            let len = self.equation_residuals(equation)?.len();
+            if let EquationSource::Constraint(h) = equation {
+                if dependent_rows[row..row + len]
+                    .iter()
+                    .any(|&dependent| dependent)
+                {
+                    over_constrained.push(self.boxed_constraint_handle(*self.slvs_constraint(h)?));
+                }
+            }
+            row += len;
+        }
+
+        let params: Vec<ParamStatus> = jacobian
+            .params
+            .iter()
+            .enumerate()
+            .map(|(col, &param)| {
+                let over = jacobian
+                    .rows
+                    .iter()
+                    .zip(&dependent_rows)
+                    .any(|(row, &dependent)| dependent && row[col] != 0.0);
+                let free = null_space.iter().any(|v| v[col].abs() > NULL_TOLERANCE);
+
+                let status = if over {
+                    Status::OverConstrained
+                } else if free {
+                    Status::UnderConstrained { dof: 1 }
+                } else {
+                    Status::FullyConstrained
+                };
+                ParamStatus { param, status }
+            })
+            .collect();
+
+        let entities = self
+            .entities
+            .list
+            .iter()
+            .filter(|slvs_entity| slvs_entity.group == group.handle())
+            .map(|slvs_entity| {
+                let cols: Vec<usize> = self
+                    .entity_params(slvs_entity.h)?
+                    .iter()
+                    .filter_map(|param| jacobian.params.iter().position(|p| p == param))
+                    .collect();
+
+                let status = if cols
+                    .iter()
+                    .any(|&col| params[col].status == Status::OverConstrained)
+                {
+                    Status::OverConstrained
+                } else {
+                    let motions: Vec<Vec<f64>> = null_space
+                        .iter()
+                        .map(|v| cols.iter().map(|&col| v[col]).collect::<Vec<f64>>())
+                        .filter(|motion| linalg::norm(motion) > NULL_TOLERANCE)
+                        .collect();
+
+                    match linalg::rank(&motions) {
+                        0 => Status::FullyConstrained,
+                        dof => Status::UnderConstrained { dof },
+                    }
+                };
+
+                Ok(EntityStatus {
+                    entity: (*slvs_entity).into(),
+                    status,
+                })
+            })
+            .collect::<Result<_, &'static str>>()?;
+
+        Ok(ConstraintStatus {
+            dof: (cols - linalg::rank(&jacobian.rows)) as i32,
+            entities,
+            params,
+            over_constrained,
+        })
+    }
+
+    // The parameters that define an entity, including those of the points, normal and
+    // distance it is built from. The workplane it lies on is not included.
+    pub(crate) fn entity_params(&self, h: Slvs_hEntity) -> Result<Vec<Slvs_hParam>, &'static str> {
+        let slvs_entity = self.slvs_entity(h)?;
+        let mut params: Vec<Slvs_hParam> = slvs_entity
+            .param
+            .iter()
+            .copied()
+            .filter(|&param| param != 0)
+            .collect();
+
+        for child in slvs_entity
+            .point
+            .iter()
+            .chain([&slvs_entity.normal, &slvs_entity.distance])
+        {
+            if *child != 0 {
+                for param in self.entity_params(*child)? {
+                    if !params.contains(&param) {
+                        params.push(param);
+                    }
+                }
+            }
+        }
+
+        Ok(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constraint::{Horizontal, PtPtDistance, Vertical},
+        element::AsHandle,
+        entity::{LineSegment, Normal, Point, Workplane},
+        status::Status,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn under_and_over_constrained() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+        let center = sys
+            .sketch(Point::new_on_workplane(workplane_g, workplane, [0.0, 0.0]))
+            .expect("point created");
+
+        let g = sys.add_group();
+        let end = sys
+            .sketch(Point::new_on_workplane(g, workplane, [10.0, 0.0]))
+            .expect("point created");
+        let line = sys
+            .sketch(LineSegment::new(g, center, end))
+            .expect("line created");
+        sys.constrain(Horizontal::from_line(g, workplane, line))
+            .expect("constraint added");
+
+        let status = sys.constraint_status(&g).expect("status found");
+        assert_eq!(status.dof, 1);
+        assert!(status.over_constrained.is_empty());
+        let line_status = status
+            .entities
+            .iter()
+            .find(|entity_status| entity_status.entity.handle() == line.handle())
+            .expect("line found");
+        assert_eq!(line_status.status, Status::UnderConstrained { dof: 1 });
+
+        sys.constrain(PtPtDistance::new(g, center, end, 10.0, Some(workplane)))
+            .expect("constraint added");
+        let status = sys.constraint_status(&g).expect("status found");
+        assert_eq!(status.dof, 0);
+        assert!(status
+            .entities
+            .iter()
+            .all(|entity_status| entity_status.status == Status::FullyConstrained));
+
+        // A horizontal line cannot also be vertical, so these conflict.
+        let vertical = sys
+            .constrain(Vertical::from_line(g, workplane, line))
+            .expect("constraint added");
+        let status = sys.constraint_status(&g).expect("status found");
+        assert_eq!(status.over_constrained.len(), 2);
+        assert!(status
+            .over_constrained
+            .iter()
+            .any(|constraint| constraint.handle() == vertical.handle()));
+        assert!(status
+            .params
+            .iter()
+            .any(|param_status| param_status.status == Status::OverConstrained));
+    }
+}
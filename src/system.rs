@@ -3,7 +3,10 @@ Object used to store and interact with all the elements and constraints in the s
 */
 
 use serde::{Deserialize, Serialize};
-use std::sync::{Mutex, MutexGuard};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Mutex, MutexGuard},
+};
 
 use crate::{
     bindings::{
@@ -24,6 +27,10 @@ use crate::{
         ArcOfCircle, AsEntityData, AsEntityHandle, Circle, Cubic, EntityHandle, LineSegment, Normal,
     },
     group::Group,
+    inequality::Bound,
+    soft::SoftConstraint,
+    spatial::SpatialIndex,
+    utils::{convert_2d_to_3d, project_on_plane},
 };
 
 /// Wrapper around the SolveSpace C structs.
@@ -75,6 +82,11 @@ pub struct System {
     /// be a relatively slow process.
     pub calculate_faileds: bool,
     pub(crate) dragged: [Slvs_hParam; 4],
+    pub(crate) bounds: BTreeMap<Slvs_hConstraint, Bound>,
+    pub(crate) soft: BTreeMap<Slvs_hConstraint, SoftConstraint>,
+    pub(crate) construction: BTreeSet<Slvs_hEntity>,
+    #[serde(skip)]
+    pub(crate) spatial_indexes: Vec<SpatialIndex>,
 }
 
 impl System {
@@ -86,6 +98,10 @@ impl System {
             constraints: Elements::new(),
             calculate_faileds: true,
             dragged: [0; 4],
+            bounds: BTreeMap::new(),
+            soft: BTreeMap::new(),
+            construction: BTreeSet::new(),
+            spatial_indexes: Vec::new(),
         }
     }
 }
@@ -203,14 +219,31 @@ impl System {
         &mut self,
         constraint_data: C,
     ) -> Result<ConstraintHandle<C>, &'static str> {
-        let workplane_h = self.constraint_target(&constraint_data)?;
+        let h = self.constraints.next_h();
+        let slvs_constraint = self.slvs_constraint_from(&constraint_data, h)?;
+
+        self.constraints.list.push(slvs_constraint);
+
+        let constraint_handle = ConstraintHandle::new(slvs_constraint.h);
+
+        Ok(constraint_handle)
+    }
+
+    // The solver's form of a constraint, with the handle `h`, without adding it to the
+    // system.
+    pub(crate) fn slvs_constraint_from<C: AsConstraintData>(
+        &self,
+        constraint_data: &C,
+        h: Slvs_hConstraint,
+    ) -> Result<Slvs_Constraint, &'static str> {
+        let workplane_h = self.constraint_target(constraint_data)?;
 
         let [pt_a, pt_b] = constraint_data.points().unwrap_or([0; 2]);
         let [entity_a, entity_b, entity_c, entity_d] = constraint_data.entities().unwrap_or([0; 4]);
         let [other, other2] = constraint_data.others();
 
-        let slvs_constraint = Slvs_Constraint {
-            h: self.constraints.next_h(),
+        Ok(Slvs_Constraint {
+            h,
             group: constraint_data.group(),
             type_: constraint_data.slvs_type(),
             wrkpl: workplane_h.unwrap_or(SLVS_FREE_IN_3D),
@@ -223,13 +256,7 @@ impl System {
             entityD: entity_d,
             other: other as _,
             other2: other2 as _,
-        };
-
-        self.constraints.list.push(slvs_constraint);
-
-        let constraint_handle = ConstraintHandle::new(slvs_constraint.h);
-
-        Ok(constraint_handle)
+        })
     }
 
     /// Get a list of groups within the system.
@@ -462,6 +489,9 @@ impl System {
 
     /// Update the entity data within the system, and return the updated entity data.
     ///
+    /// Spatial indexes built with [`System::spatial_index`] are brought up to date with
+    /// the change.
+    ///
     /// # Arguments
     ///
     /// * `entity_handle` - Handle for the entity you want to update.
@@ -545,6 +575,7 @@ impl System {
                 val.map(|val| self.update_param(param_h[i], entity_data.group(), val))
             })
             .collect::<Result<Vec<_>, _>>()?;
+        self.refresh_spatial_indexes();
 
         Ok(entity_data)
     }
@@ -680,6 +711,7 @@ impl System {
 
         let ix = self.entity_ix(entity_handle.handle())?;
         let deleted_entity = self.entities.list.remove(ix);
+        self.construction.remove(&deleted_entity.h);
 
         for param_h in deleted_entity.param {
             if param_h != 0 {
@@ -707,6 +739,8 @@ impl System {
 
         let ix = self.constraint_ix(constraint_handle.handle())?;
         self.constraints.list.remove(ix);
+        self.bounds.remove(&constraint_handle.handle());
+        self.soft.remove(&constraint_handle.handle());
 
         Ok(constraint_data)
     }
@@ -801,10 +835,27 @@ impl System {
     /// * The solver cannot prove that two constraints are inconsistent, but it cannot
     /// find a solution. The list of unsatisfied constraints are included in [`SolveResult::Fail`].
     ///
+    /// Constraints added with [`System::constrain_bounded`] are only enforced when their
+    /// bounds would otherwise be violated. See the [`inequality`][crate::inequality]
+    /// module for details.
+    ///
+    /// Constraints added with [`System::constrain_soft`] never cause the solve to fail.
+    /// They are satisfied as closely as the other constraints allow. See the
+    /// [`soft`][crate::soft] module for details.
+    ///
+    /// Spatial indexes built with [`System::spatial_index`] are brought up to date with
+    /// the solution.
+    ///
     /// # Arguments
     ///
     /// * `group` - Only entities within this group are modified during solve.
     pub fn solve(&mut self, group: &Group) -> SolveResult {
+        let result = self.solve_with_bounds(group);
+        self.refresh_spatial_indexes();
+        result
+    }
+
+    pub(crate) fn solve_slvs(&mut self, group: &Group) -> SolveResult {
         let _lock = SolverGuard::lock();
 
         let mut failed_handles: Vec<Slvs_hConstraint> = vec![0; self.constraints.list.len()];
@@ -1164,6 +1215,101 @@ impl System {
         }
     }
 
+    pub(crate) fn param_vals(&self, slvs_entity: &Slvs_Entity) -> Result<Vec<f64>, &'static str> {
+        slvs_entity
+            .param
+            .iter()
+            .filter(|&&param_h| param_h != 0)
+            .map(|&param_h| Ok(self.slvs_param(param_h)?.val))
+            .collect()
+    }
+
+    pub(crate) fn normal_quaternion(&self, h: Slvs_hEntity) -> Result<[f64; 4], &'static str> {
+        let slvs_entity = self.slvs_entity(h)?;
+
+        match slvs_entity.type_ as _ {
+            SLVS_E_NORMAL_IN_3D => self
+                .param_vals(slvs_entity)?
+                .try_into()
+                .map_err(|_| "Expected exactly 4 parameters"),
+            SLVS_E_NORMAL_IN_2D => {
+                self.normal_quaternion(self.slvs_entity(slvs_entity.wrkpl)?.normal)
+            }
+            _ => Err("Expected entity to have type SLVS_E_NORMAL_IN_2D or SLVS_E_NORMAL_IN_3D."),
+        }
+    }
+
+    pub(crate) fn workplane_frame(
+        &self,
+        h: Slvs_hEntity,
+    ) -> Result<([f64; 3], [f64; 4]), &'static str> {
+        let slvs_workplane = self.slvs_entity(h)?;
+
+        if SLVS_E_WORKPLANE != slvs_workplane.type_ as _ {
+            return Err("Expected entity to have type SLVS_E_WORKPLANE.");
+        }
+
+        Ok((
+            self.point_coords(slvs_workplane.point[0])?,
+            self.normal_quaternion(slvs_workplane.normal)?,
+        ))
+    }
+
+    pub(crate) fn point_coords(&self, h: Slvs_hEntity) -> Result<[f64; 3], &'static str> {
+        let slvs_entity = self.slvs_entity(h)?;
+        let param_vals = self.param_vals(slvs_entity)?;
+
+        match slvs_entity.type_ as _ {
+            SLVS_E_POINT_IN_3D => param_vals
+                .try_into()
+                .map_err(|_| "Expected exactly 3 parameters"),
+            SLVS_E_POINT_IN_2D => {
+                let coords: [f64; 2] = param_vals
+                    .try_into()
+                    .map_err(|_| "Expected exactly 2 parameters")?;
+                let (origin, quaternion) = self.workplane_frame(slvs_entity.wrkpl)?;
+
+                Ok(convert_2d_to_3d(coords, origin, quaternion))
+            }
+            _ => Err("Expected entity to have type SLVS_E_POINT_IN_2D or SLVS_E_POINT_IN_3D."),
+        }
+    }
+
+    pub(crate) fn point_coords_on(
+        &self,
+        h: Slvs_hEntity,
+        workplane_h: Slvs_hEntity,
+    ) -> Result<[f64; 2], &'static str> {
+        let slvs_entity = self.slvs_entity(h)?;
+
+        if SLVS_E_POINT_IN_2D == slvs_entity.type_ as _ && slvs_entity.wrkpl == workplane_h {
+            self.param_vals(slvs_entity)?
+                .try_into()
+                .map_err(|_| "Expected exactly 2 parameters")
+        } else {
+            let (origin, quaternion) = self.workplane_frame(workplane_h)?;
+            Ok(project_on_plane(self.point_coords(h)?, origin, quaternion))
+        }
+    }
+
+    // Coordinates in 3d. If a workplane is specified, the point is first projected onto it.
+    pub(crate) fn point_projected(
+        &self,
+        h: Slvs_hEntity,
+        workplane_h: Slvs_hEntity,
+    ) -> Result<[f64; 3], &'static str> {
+        if workplane_h == SLVS_FREE_IN_3D {
+            self.point_coords(h)
+        } else {
+            let (origin, quaternion) = self.workplane_frame(workplane_h)?;
+            Ok(convert_2d_to_3d(
+                self.point_coords_on(h, workplane_h)?,
+                origin,
+                quaternion,
+            ))
+        }
+    }
+
     pub(crate) fn boxed_constraint_handle(
         &self,
         slvs_constraint: Slvs_Constraint,
@@ -0,0 +1,465 @@
+/*!
+Flatten curves into polylines, for drawing and export.
+
+Arcs and circles are split into segments of equal angle, and cubic curves are split in
+half until each piece is flat enough. The number of segments is chosen so that every
+segment meets both limits in a [`Tessellation`].
+
+Arcs are traced counter-clockwise from their start point to their end point, and an
+arc whose start and end points coincide is traced as a full circle, following the
+rules documented on [`ArcOfCircle`]. Full circles are returned as closed polylines,
+which end on their first point.
+
+The `_on` variants project the polyline in world coordinates onto a workplane. This
+never increases the distance between a segment and the curve it approximates.
+*/
+
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+use crate::{
+    bindings::{
+        Slvs_hEntity, SLVS_E_ARC_OF_CIRCLE, SLVS_E_CIRCLE, SLVS_E_CUBIC, SLVS_E_LINE_SEGMENT,
+    },
+    element::AsHandle,
+    entity::{ArcOfCircle, Circle, Cubic, EntityHandle, Workplane},
+    geometry::{add, scale, sub, CubicGeometry},
+    system::SOLVE_TOLERANCE,
+    utils::{convert_2d_to_3d, distance, project_on_plane},
+    System,
+};
+
+// Cubic curves are split at most this many times in a row.
+const MAX_DEPTH: usize = 16;
+
+/// Limits on how far a polyline can stray from the curve it approximates.
+///
+/// Limits set to `None` are ignored, but at least one of them must be set.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tessellation {
+    /// The largest distance between a segment and the curve.
+    pub chordal_deviation: Option<f64>,
+    /// The largest change in direction along the curve covered by a segment, in
+    /// degrees.
+    pub angular_step: Option<f64>,
+}
+
+impl Default for Tessellation {
+    fn default() -> Self {
+        Self {
+            chordal_deviation: Some(0.01),
+            angular_step: Some(15.0),
+        }
+    }
+}
+
+impl Tessellation {
+    fn validate(&self) -> Result<(), &'static str> {
+        let limits = [self.chordal_deviation, self.angular_step];
+
+        if limits.iter().all(Option::is_none) {
+            Err("Tessellation needs a chordal deviation or an angular step.")
+        } else if limits.iter().flatten().any(|&limit| limit <= 0.0) {
+            Err("Tessellation limits must be positive.")
+        } else {
+            Ok(())
+        }
+    }
+
+    // The largest angle, in radians, that a segment of an arc of `radius` can cover.
+    fn max_step(&self, radius: f64) -> f64 {
+        let chordal = self.chordal_deviation.map_or(PI, |deviation| {
+            if deviation >= radius {
+                PI
+            } else {
+                2.0 * (1.0 - deviation / radius).acos()
+            }
+        });
+        let angular = self.angular_step.map_or(PI, f64::to_radians);
+
+        chordal.min(angular).min(PI)
+    }
+
+    // Points along an arc around the origin, going counter-clockwise from
+    // `start_angle` through `sweep`, both in radians.
+    fn arc_points(&self, radius: f64, start_angle: f64, sweep: f64) -> Vec<[f64; 2]> {
+        let segments = (sweep / self.max_step(radius)).ceil().max(1.0) as usize;
+
+        (0..=segments)
+            .map(|i| {
+                let angle = start_angle + sweep * i as f64 / segments as f64;
+                [radius * angle.cos(), radius * angle.sin()]
+            })
+            .collect()
+    }
+
+    // Split a cubic curve in half until each piece is within the limits.
+    fn cubic_points<const N: usize>(&self, cubic: &CubicGeometry<N>) -> Vec<[f64; N]> {
+        let mut points = vec![cubic.control_points[0]];
+        self.subdivide(cubic, 0, &mut points);
+        points
+    }
+
+    fn subdivide<const N: usize>(
+        &self,
+        cubic: &CubicGeometry<N>,
+        depth: usize,
+        points: &mut Vec<[f64; N]>,
+    ) {
+        let [p0, p1, p2, p3] = cubic.control_points;
+
+        // The curve stays within this distance of the chord, and turns by at most
+        // the angles between the legs of its control polygon.
+        let deviation =
+            0.75 * distance(p1, lerp(p0, p3, 1.0 / 3.0)).max(distance(p2, lerp(p0, p3, 2.0 / 3.0)));
+        let legs: Vec<[f64; N]> = [sub(p1, p0), sub(p2, p1), sub(p3, p2)]
+            .into_iter()
+            .filter(|leg| distance(*leg, [0.0; N]) > SOLVE_TOLERANCE)
+            .collect();
+        let turn: f64 = legs.windows(2).map(|pair| angle(pair[0], pair[1])).sum();
+
+        let flat = self
+            .chordal_deviation
+            .map_or(true, |limit| deviation <= limit)
+            && self
+                .angular_step
+                .map_or(true, |limit| turn <= limit.to_radians());
+
+        if flat || depth >= MAX_DEPTH {
+            points.push(p3);
+        } else {
+            let (before, after) = cubic.split(0.5);
+            self.subdivide(&before, depth + 1, points);
+            self.subdivide(&after, depth + 1, points);
+        }
+    }
+}
+
+impl System {
+    /// Flatten an arc into a polyline, in world coordinates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{ArcOfCircle, Normal, Point, Workplane},
+    ///     tessellation::Tessellation,
+    ///     utils::make_quaternion,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// let center = sys
+    ///     .sketch(Point::new_on_workplane(g, workplane, [0.0, 0.0]))
+    ///     .expect("point created");
+    /// let start = sys
+    ///     .sketch(Point::new_on_workplane(g, workplane, [10.0, 0.0]))
+    ///     .expect("point created");
+    /// let end = sys
+    ///     .sketch(Point::new_on_workplane(g, workplane, [0.0, 10.0]))
+    ///     .expect("point created");
+    /// let arc = sys
+    ///     .sketch(ArcOfCircle::new(g, workplane, center, start, end))
+    ///     .expect("arc created");
+    ///
+    /// let tessellation = Tessellation {
+    ///     chordal_deviation: None,
+    ///     angular_step: Some(30.0),
+    /// };
+    /// let points = sys
+    ///     .tessellate_arc(&arc, &tessellation)
+    ///     .expect("arc tessellated");
+    /// assert_eq!(points.len(), 4);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the arc is not found in the system, or if the tessellation
+    /// limits are not valid.
+    pub fn tessellate_arc(
+        &self,
+        arc: &EntityHandle<ArcOfCircle>,
+        tessellation: &Tessellation,
+    ) -> Result<Vec<[f64; 3]>, &'static str> {
+        tessellation.validate()?;
+
+        let slvs_arc = self.slvs_entity(arc.handle())?;
+        let arc_geometry = self.arc_geometry_on(arc, &EntityHandle::new(slvs_arc.wrkpl))?;
+        let (origin, quaternion) = self.workplane_frame(slvs_arc.wrkpl)?;
+
+        Ok(tessellation
+            .arc_points(
+                arc_geometry.radius,
+                arc_geometry.start_angle.to_radians(),
+                arc_geometry.sweep().to_radians(),
+            )
+            .into_iter()
+            .map(|[x, y]| {
+                let point = [x + arc_geometry.center[0], y + arc_geometry.center[1]];
+                convert_2d_to_3d(point, origin, quaternion)
+            })
+            .collect())
+    }
+
+    /// Flatten an arc into a polyline, projected onto a workplane.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the arc or workplane are not found in the system, or if the
+    /// tessellation limits are not valid.
+    pub fn tessellate_arc_on(
+        &self,
+        arc: &EntityHandle<ArcOfCircle>,
+        workplane: &EntityHandle<Workplane>,
+        tessellation: &Tessellation,
+    ) -> Result<Vec<[f64; 2]>, &'static str> {
+        let points = self.tessellate_arc(arc, tessellation)?;
+        self.project_points(points, workplane)
+    }
+
+    /// Flatten a circle into a closed polyline, in world coordinates. The polyline
+    /// starts on the `u` axis of the circle's normal.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the circle is not found in the system, or if the
+    /// tessellation limits are not valid.
+    pub fn tessellate_circle(
+        &self,
+        circle: &EntityHandle<Circle>,
+        tessellation: &Tessellation,
+    ) -> Result<Vec<[f64; 3]>, &'static str> {
+        tessellation.validate()?;
+
+        let slvs_circle = self.slvs_entity(circle.handle())?;
+        let circle_geometry = self.circle_geometry(circle)?;
+        let quaternion = self.normal_quaternion(slvs_circle.normal)?;
+
+        Ok(tessellation
+            .arc_points(circle_geometry.radius, 0.0, 2.0 * PI)
+            .into_iter()
+            .map(|point| convert_2d_to_3d(point, circle_geometry.center, quaternion))
+            .collect())
+    }
+
+    /// Flatten a circle into a closed polyline, projected onto a workplane.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the circle or workplane are not found in the system, or if
+    /// the tessellation limits are not valid.
+    pub fn tessellate_circle_on(
+        &self,
+        circle: &EntityHandle<Circle>,
+        workplane: &EntityHandle<Workplane>,
+        tessellation: &Tessellation,
+    ) -> Result<Vec<[f64; 2]>, &'static str> {
+        let points = self.tessellate_circle(circle, tessellation)?;
+        self.project_points(points, workplane)
+    }
+
+    /// Flatten a cubic Bezier curve into a polyline, in world coordinates.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the curve is not found in the system, or if the tessellation
+    /// limits are not valid.
+    pub fn tessellate_cubic(
+        &self,
+        cubic: &EntityHandle<Cubic>,
+        tessellation: &Tessellation,
+    ) -> Result<Vec<[f64; 3]>, &'static str> {
+        tessellation.validate()?;
+        Ok(tessellation.cubic_points(&self.cubic_geometry(cubic)?))
+    }
+
+    /// Flatten a cubic Bezier curve into a polyline, projected onto a workplane.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the curve or workplane are not found in the system, or if
+    /// the tessellation limits are not valid.
+    pub fn tessellate_cubic_on(
+        &self,
+        cubic: &EntityHandle<Cubic>,
+        workplane: &EntityHandle<Workplane>,
+        tessellation: &Tessellation,
+    ) -> Result<Vec<[f64; 2]>, &'static str> {
+        tessellation.validate()?;
+        Ok(tessellation.cubic_points(&self.cubic_geometry_on(cubic, workplane)?))
+    }
+
+    // Flatten a point or curve onto a workplane. Points give a single vertex, and
+    // lines give their two ends.
+    pub(crate) fn tessellate_on(
+        &self,
+        h: Slvs_hEntity,
+        workplane: &EntityHandle<Workplane>,
+        tessellation: &Tessellation,
+    ) -> Result<Vec<[f64; 2]>, &'static str> {
+        match self.slvs_entity(h)?.type_ as _ {
+            SLVS_E_LINE_SEGMENT => {
+                let line = self.line_geometry_on(&EntityHandle::new(h), workplane)?;
+                Ok(vec![line.start, line.end])
+            }
+            SLVS_E_ARC_OF_CIRCLE => {
+                self.tessellate_arc_on(&EntityHandle::new(h), workplane, tessellation)
+            }
+            SLVS_E_CIRCLE => {
+                self.tessellate_circle_on(&EntityHandle::new(h), workplane, tessellation)
+            }
+            SLVS_E_CUBIC => {
+                self.tessellate_cubic_on(&EntityHandle::new(h), workplane, tessellation)
+            }
+            _ => Ok(vec![self.point_coords_on(h, workplane.handle())?]),
+        }
+    }
+
+    fn project_points(
+        &self,
+        points: Vec<[f64; 3]>,
+        workplane: &EntityHandle<Workplane>,
+    ) -> Result<Vec<[f64; 2]>, &'static str> {
+        let (origin, quaternion) = self.workplane_frame(workplane.handle())?;
+
+        Ok(points
+            .into_iter()
+            .map(|point| project_on_plane(point, origin, quaternion))
+            .collect())
+    }
+}
+
+fn lerp<const N: usize>(a: [f64; N], b: [f64; N], t: f64) -> [f64; N] {
+    add(a, scale(sub(b, a), t))
+}
+
+// The angle between two vectors, in radians.
+fn angle<const N: usize>(a: [f64; N], b: [f64; N]) -> f64 {
+    let dot: f64 = a.iter().zip(b).map(|(a, b)| a * b).sum();
+    let zero = [0.0; N];
+
+    (dot / (distance(a, zero) * distance(b, zero)))
+        .clamp(-1.0, 1.0)
+        .acos()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity::{ArcOfCircle, Circle, Cubic, Distance, Normal, Point, Workplane},
+        tessellation::Tessellation,
+        utils::{distance, make_quaternion},
+        System,
+    };
+
+    #[test]
+    fn within_chordal_deviation() {
+        let mut sys = System::new();
+        let g = sys.add_group();
+
+        let origin = sys
+            .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(g, origin, normal))
+            .expect("workplane created");
+        let tessellation = Tessellation {
+            chordal_deviation: Some(0.05),
+            angular_step: None,
+        };
+
+        // Start and end coincide, so the arc is a full circle.
+        let center = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 0.0]))
+            .expect("point created");
+        let start = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 10.0]))
+            .expect("point created");
+        let end = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 10.0]))
+            .expect("point created");
+        let arc = sys
+            .sketch(ArcOfCircle::new(g, workplane, center, start, end))
+            .expect("arc created");
+
+        let points = sys
+            .tessellate_arc_on(&arc, &workplane, &tessellation)
+            .expect("arc tessellated");
+        assert!(distance(points[0], points[points.len() - 1]) < 1e-9);
+        // Going counter-clockwise from the top, the next point is on the left.
+        assert!(points[1][0] < 0.0);
+        for pair in points.windows(2) {
+            let midpoint = [
+                (pair[0][0] + pair[1][0]) / 2.0,
+                (pair[0][1] + pair[1][1]) / 2.0,
+            ];
+            assert!(10.0 - distance(midpoint, [0.0, 0.0]) <= 0.05);
+        }
+
+        let radius = sys.sketch(Distance::new(g, 5.0)).expect("distance created");
+        let circle = sys
+            .sketch(Circle::new(g, normal, center, radius))
+            .expect("circle created");
+        let points = sys
+            .tessellate_circle(&circle, &tessellation)
+            .expect("circle tessellated");
+        assert_eq!(points[0], [5.0, 0.0, 0.0]);
+        assert!(points.iter().all(|point| point[2] == 0.0));
+
+        let control_a = sys
+            .sketch(Point::new_on_workplane(g, workplane, [10.0, 10.0]))
+            .expect("point created");
+        let control_b = sys
+            .sketch(Point::new_on_workplane(g, workplane, [10.0, -10.0]))
+            .expect("point created");
+        let cubic = sys
+            .sketch(Cubic::new(g, start, control_a, control_b, center))
+            .expect("cubic created");
+        let cubic_geometry = sys.cubic_geometry_on(&cubic, &workplane).expect("found");
+        let points = sys
+            .tessellate_cubic_on(&cubic, &workplane, &tessellation)
+            .expect("cubic tessellated");
+        assert_eq!(points[0], [0.0, 10.0]);
+        assert_eq!(points[points.len() - 1], [0.0, 0.0]);
+        for t in (0..=100).map(|i| i as f64 / 100.0) {
+            let on_curve = cubic_geometry.point_at(t);
+            let nearest = points
+                .windows(2)
+                .map(|pair| segment_distance(on_curve, pair[0], pair[1]))
+                .fold(f64::INFINITY, f64::min);
+            assert!(nearest <= 0.05);
+        }
+    }
+
+    fn segment_distance(point: [f64; 2], start: [f64; 2], end: [f64; 2]) -> f64 {
+        let direction = [end[0] - start[0], end[1] - start[1]];
+        let t = (((point[0] - start[0]) * direction[0] + (point[1] - start[1]) * direction[1])
+            / (direction[0].powi(2) + direction[1].powi(2)))
+        .clamp(0.0, 1.0);
+
+        distance(
+            point,
+            [start[0] + direction[0] * t, start[1] + direction[1] * t],
+        )
+    }
+}
@@ -0,0 +1,436 @@
+/*!
+Monte-Carlo tolerance stack-up analysis.
+
+Each dimensional constraint can be assigned a [`Tolerance`], describing how far its
+value may deviate from nominal and how those deviations are distributed. The group is
+then solved repeatedly, with every toleranced value drawn at random, and the resulting
+spread of one or more [measurements][crate::measurement::Measurement] is summarized
+in [`ToleranceAnalysis`].
+
+Random values are drawn from a seeded generator, so an analysis run with the same
+seed on the same system always gives the same result.
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::{
+        Slvs_hConstraint, SLVS_C_ANGLE, SLVS_C_ARC_ARC_DIFFERENCE, SLVS_C_ARC_ARC_LEN_RATIO,
+        SLVS_C_ARC_LINE_DIFFERENCE, SLVS_C_ARC_LINE_LEN_RATIO, SLVS_C_DIAMETER,
+        SLVS_C_LENGTH_DIFFERENCE, SLVS_C_LENGTH_RATIO, SLVS_C_PROJ_PT_DISTANCE,
+        SLVS_C_PT_LINE_DISTANCE, SLVS_C_PT_PLANE_DISTANCE, SLVS_C_PT_PT_DISTANCE,
+    },
+    constraint::AsConstraintHandle,
+    group::Group,
+    measurement::Measurement,
+    system::SolveResult,
+    System,
+};
+
+/// How deviations from the nominal value are distributed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Distribution {
+    /// Any value within the tolerance is equally likely.
+    Uniform,
+    /// Normally distributed about the nominal value, with the tolerance taken to be
+    /// three standard deviations.
+    Normal,
+}
+
+/// A symmetric tolerance on the value of a dimensional constraint.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tolerance {
+    constraint: Slvs_hConstraint,
+    /// The maximum deviation from the nominal value, in either direction.
+    pub plus_minus: f64,
+    pub distribution: Distribution,
+}
+
+impl Tolerance {
+    /// Create a new `Tolerance` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `constraint` - Handle for a dimensional constraint, such as [`PtPtDistance`][crate::constraint::PtPtDistance].
+    /// * `plus_minus` - The maximum deviation from the nominal value, in either direction.
+    /// * `distribution` - How deviations are distributed.
+    pub fn new(
+        constraint: &dyn AsConstraintHandle,
+        plus_minus: f64,
+        distribution: Distribution,
+    ) -> Self {
+        Self {
+            constraint: constraint.handle(),
+            plus_minus,
+            distribution,
+        }
+    }
+}
+
+/// Summary statistics for one measurement, over all successfully solved samples.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Statistics {
+    /// The measured value with all constraints at their nominal values.
+    pub nominal: f64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// The sample standard deviation.
+    pub std_dev: f64,
+}
+
+/// Results of [`System::tolerance_analysis`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ToleranceAnalysis {
+    /// The number of samples that were solved successfully.
+    pub samples: usize,
+    /// The number of samples for which the solver failed. These are not included
+    /// in the statistics.
+    pub failed_samples: usize,
+    /// Statistics for each measurement, in the order they were requested.
+    pub statistics: Vec<Statistics>,
+}
+
+impl System {
+    /// Run a Monte-Carlo tolerance analysis on a group.
+    ///
+    /// The group is first solved with every constraint at its nominal value. Then,
+    /// for each sample, the toleranced constraint values are drawn at random and the
+    /// group is solved again, starting from the nominal solution. Every measurement
+    /// is taken after each successful solve.
+    ///
+    /// Once the analysis is complete, constraint values are restored and the system
+    /// is left in its nominal, solved state.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group to be solved.
+    /// * `tolerances` - Tolerances on dimensional constraints within the system.
+    /// * `measurements` - The quantities to collect statistics for.
+    /// * `samples` - The number of randomized solves to run.
+    /// * `seed` - Seed for the random number generator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     constraint::PtPtDistance,
+    ///     entity::Point,
+    ///     measurement::Measurement,
+    ///     tolerance::{Distribution, Tolerance},
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    ///
+    /// let p1 = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("p1 created");
+    /// let p2 = sys
+    ///     .sketch(Point::new_in_3d(g, [10.0, 0.0, 0.0]))
+    ///     .expect("p2 created");
+    /// let d = sys
+    ///     .constrain(PtPtDistance::new(g, p1, p2, 10.0, None))
+    ///     .expect("distance constraint added");
+    ///
+    /// let analysis = sys
+    ///     .tolerance_analysis(
+    ///         &g,
+    ///         &[Tolerance::new(&d, 0.1, Distribution::Uniform)],
+    ///         &[Measurement::PtPtDistance {
+    ///             point_a: p1,
+    ///             point_b: p2,
+    ///             workplane: None,
+    ///         }],
+    ///         100,
+    ///         42,
+    ///     )
+    ///     .expect("analysis complete");
+    ///
+    /// println!("{:#?}", analysis.statistics[0]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a tolerance is applied to a constraint that does not exist
+    /// or does not have a value, if a measurement cannot be taken, or if the group
+    /// cannot be solved with nominal constraint values.
+    pub fn tolerance_analysis(
+        &mut self,
+        group: &Group,
+        tolerances: &[Tolerance],
+        measurements: &[Measurement],
+        samples: usize,
+        seed: u64,
+    ) -> Result<ToleranceAnalysis, &'static str> {
+        let nominal_vals = tolerances
+            .iter()
+            .map(|tolerance| {
+                let slvs_constraint = self.slvs_constraint(tolerance.constraint)?;
+
+                if is_dimensional(slvs_constraint.type_) {
+                    Ok(slvs_constraint.valA)
+                } else {
+                    Err("Tolerances can only be applied to dimensional constraints.")
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let SolveResult::Fail { .. } = self.solve(group) {
+            return Err("Unable to solve group with nominal constraint values.");
+        }
+
+        let nominal_params = self.params.list.clone();
+        let nominal_measurements = measurements
+            .iter()
+            .map(|measurement| self.measure(measurement))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut rng = Rng::new(seed);
+        let mut accumulators: Vec<_> = nominal_measurements
+            .iter()
+            .map(|&nominal| Accumulator::new(nominal))
+            .collect();
+        let mut failed_samples = 0;
+
+        let result = (|| {
+            for _ in 0..samples {
+                for (tolerance, nominal_val) in tolerances.iter().zip(&nominal_vals) {
+                    self.mut_slvs_constraint(tolerance.constraint)?.valA =
+                        nominal_val + rng.deviation(tolerance);
+                }
+                self.params.list.clone_from(&nominal_params);
+
+                match self.solve(group) {
+                    SolveResult::Ok { .. } => {
+                        for (accumulator, measurement) in accumulators.iter_mut().zip(measurements)
+                        {
+                            accumulator.push(self.measure(measurement)?);
+                        }
+                    }
+                    SolveResult::Fail { .. } => failed_samples += 1,
+                }
+            }
+            Ok(())
+        })();
+
+        for (tolerance, nominal_val) in tolerances.iter().zip(&nominal_vals) {
+            self.mut_slvs_constraint(tolerance.constraint)?.valA = *nominal_val;
+        }
+        self.params.list = nominal_params;
+        result?;
+
+        Ok(ToleranceAnalysis {
+            samples: samples - failed_samples,
+            failed_samples,
+            statistics: accumulators
+                .into_iter()
+                .map(|accumulator| accumulator.statistics())
+                .collect(),
+        })
+    }
+}
+
+// Constraints which have a value that can be toleranced.
+pub(crate) fn is_dimensional(slvs_type: i32) -> bool {
+    matches!(
+        slvs_type as _,
+        SLVS_C_PT_PT_DISTANCE
+            | SLVS_C_PT_PLANE_DISTANCE
+            | SLVS_C_PT_LINE_DISTANCE
+            | SLVS_C_PROJ_PT_DISTANCE
+            | SLVS_C_DIAMETER
+            | SLVS_C_ANGLE
+            | SLVS_C_LENGTH_RATIO
+            | SLVS_C_LENGTH_DIFFERENCE
+            | SLVS_C_ARC_ARC_LEN_RATIO
+            | SLVS_C_ARC_LINE_LEN_RATIO
+            | SLVS_C_ARC_ARC_DIFFERENCE
+            | SLVS_C_ARC_LINE_DIFFERENCE
+    )
+}
+
+// Running statistics, using Welford's algorithm.
+struct Accumulator {
+    nominal: f64,
+    count: usize,
+    min: f64,
+    max: f64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Accumulator {
+    fn new(nominal: f64) -> Self {
+        Self {
+            nominal,
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            mean: 0.0,
+            m2: 0.0,
+        }
+    }
+
+    fn push(&mut self, val: f64) {
+        self.count += 1;
+        self.min = self.min.min(val);
+        self.max = self.max.max(val);
+
+        let delta = val - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (val - self.mean);
+    }
+
+    fn statistics(self) -> Statistics {
+        match self.count {
+            0 => Statistics {
+                nominal: self.nominal,
+                min: f64::NAN,
+                max: f64::NAN,
+                mean: f64::NAN,
+                std_dev: f64::NAN,
+            },
+            count => Statistics {
+                nominal: self.nominal,
+                min: self.min,
+                max: self.max,
+                mean: self.mean,
+                std_dev: if count > 1 {
+                    (self.m2 / (count - 1) as f64).sqrt()
+                } else {
+                    0.0
+                },
+            },
+        }
+    }
+}
+
+// A small SplitMix64 generator, so that results are reproducible across platforms.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniformly distributed within [0, 1).
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    // Standard normal distribution, using the Box-Muller transform.
+    fn next_normal(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    fn deviation(&mut self, tolerance: &Tolerance) -> f64 {
+        match tolerance.distribution {
+            Distribution::Uniform => (2.0 * self.next_f64() - 1.0) * tolerance.plus_minus,
+            Distribution::Normal => self.next_normal() * tolerance.plus_minus / 3.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constraint::{PtPtDistance, Vertical},
+        entity::{LineSegment, Normal, Point, Workplane},
+        measurement::Measurement,
+        tolerance::{Distribution, Tolerance},
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn stack_up() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        // Three points stacked vertically, each gap dimensioned and toleranced.
+        let g = sys.add_group();
+        let point_a = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 0.0]))
+            .expect("point created");
+        let point_b = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 10.0]))
+            .expect("point created");
+        let point_c = sys
+            .sketch(Point::new_on_workplane(g, workplane, [0.0, 30.0]))
+            .expect("point created");
+        let line_ab = sys
+            .sketch(LineSegment::new(g, point_a, point_b))
+            .expect("line created");
+        let line_bc = sys
+            .sketch(LineSegment::new(g, point_b, point_c))
+            .expect("line created");
+        sys.constrain(Vertical::from_line(g, workplane, line_ab))
+            .expect("constraint added");
+        sys.constrain(Vertical::from_line(g, workplane, line_bc))
+            .expect("constraint added");
+
+        let gap_ab = sys
+            .constrain(PtPtDistance::new(g, point_a, point_b, 10.0, None))
+            .expect("constraint added");
+        let gap_bc = sys
+            .constrain(PtPtDistance::new(g, point_b, point_c, 20.0, None))
+            .expect("constraint added");
+
+        let tolerances = [
+            Tolerance::new(&gap_ab, 0.1, Distribution::Uniform),
+            Tolerance::new(&gap_bc, 0.2, Distribution::Normal),
+        ];
+        let measurements = [Measurement::PtPtDistance {
+            point_a,
+            point_b: point_c,
+            workplane: None,
+        }];
+
+        let analysis = sys
+            .tolerance_analysis(&g, &tolerances, &measurements, 200, 7)
+            .expect("analysis complete");
+        let stats = analysis.statistics[0];
+
+        assert_eq!(analysis.failed_samples, 0);
+        assert!((stats.nominal - 30.0).abs() < 1e-6);
+        assert!(stats.min >= 30.0 - 0.1 - 0.2 * 2.0);
+        assert!(stats.max <= 30.0 + 0.1 + 0.2 * 2.0);
+        assert!((stats.mean - 30.0).abs() < 0.05);
+
+        // Same seed gives the same result, and constraint values are restored.
+        let repeated = sys
+            .tolerance_analysis(&g, &tolerances, &measurements, 200, 7)
+            .expect("analysis complete");
+        assert_eq!(analysis, repeated);
+        assert_eq!(
+            sys.constraint_data(&gap_ab).expect("data found").distance,
+            10.0
+        );
+    }
+}
@@ -0,0 +1,630 @@
+/*!
+Splitting, trimming and extending curves on a workplane.
+
+[`System::split_at`] breaks a line segment, arc or cubic curve in two at a new point.
+[`System::trim`] cuts a curve back to where it crosses another curve, and
+[`System::extend`] lengthens a line until it meets another curve. The new end point
+is held on the other curve, when it is a line, arc or circle.
+*/
+
+use crate::{
+    bindings::{
+        Slvs_Entity, Slvs_hEntity, SLVS_C_ARC_LINE_TANGENT, SLVS_C_CUBIC_LINE_TANGENT,
+        SLVS_C_CURVE_CURVE_TANGENT, SLVS_E_ARC_OF_CIRCLE, SLVS_E_CIRCLE, SLVS_E_CUBIC,
+        SLVS_E_LINE_SEGMENT, SLVS_FREE_IN_3D,
+    },
+    constraint::{AsConstraintHandle, CurveCurveTangent, PtOnCircle, PtOnLine},
+    element::AsHandle,
+    entity::{
+        ArcOfCircle, AsEntityHandle, Circle, Cubic, EntityHandle, LineSegment, Point, Workplane,
+    },
+    geometry::add,
+    group::Group,
+    intersection::{Contact, Intersection},
+    system::SOLVE_TOLERANCE,
+    utils::distance,
+    System,
+};
+
+/// The two curves that a curve is broken into by [`System::split_at`].
+#[derive(Debug)]
+pub struct Split {
+    /// The new point where the parts meet.
+    pub point: EntityHandle<Point>,
+    /// The part before `point`, which is the original curve, and the new part after it.
+    pub parts: [Box<dyn AsEntityHandle>; 2],
+    /// A constraint holding the parts in line where they meet. This is a point on line
+    /// constraint for line segments and a tangency for cubic curves. The parts of an
+    /// arc share its center, and need none.
+    pub joint: Option<Box<dyn AsConstraintHandle>>,
+}
+
+/// The new end point of a curve cut by [`System::trim`] or lengthened by
+/// [`System::extend`].
+#[derive(Debug)]
+pub struct Cut {
+    pub point: EntityHandle<Point>,
+    /// A constraint holding `point` on the other curve, unless it is a cubic curve.
+    pub on_curve: Option<Box<dyn AsConstraintHandle>>,
+}
+
+impl System {
+    /// Break a line segment, arc or cubic curve in two at the point on it nearest to
+    /// `point`.
+    ///
+    /// The curve is shortened to end at a new point, and a new curve of the same type
+    /// runs from there to the original end point. Constraints on the curve stay with
+    /// the first part, except tangencies at its end, which move to the second part.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The curve to split. It must lie on a workplane.
+    /// * `point` - Where to split the curve, in the coordinates of its workplane.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{LineSegment, Normal, Point, Workplane},
+    ///     utils::make_quaternion,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// let start = sys
+    ///     .sketch(Point::new_on_workplane(g, workplane, [0.0, 0.0]))
+    ///     .expect("point created");
+    /// let end = sys
+    ///     .sketch(Point::new_on_workplane(g, workplane, [10.0, 0.0]))
+    ///     .expect("point created");
+    /// let line = sys
+    ///     .sketch(LineSegment::new(g, start, end))
+    ///     .expect("line created");
+    ///
+    /// let split = sys.split_at(&line, [4.0, 1.0]).expect("line split");
+    /// assert_eq!(
+    ///     sys.entity_data(&line).expect("line found").point_b,
+    ///     split.point
+    /// );
+    /// let line = sys.line_geometry_on(&line, &workplane).expect("line found");
+    /// assert_eq!(line.end, [4.0, 0.0]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the curve is not found in the system, if it is not a line
+    /// segment, arc or cubic curve on a workplane, or if the nearest point is at one
+    /// of its ends.
+    pub fn split_at(
+        &mut self,
+        entity: &dyn AsEntityHandle,
+        point: [f64; 2],
+    ) -> Result<Split, &'static str> {
+        let (slvs_entity, workplane) = self.curve_on_workplane(entity.handle())?;
+        let group = Group(slvs_entity.group);
+        let h = slvs_entity.h;
+        let [start_ix, end_ix] = end_ixs(&slvs_entity)?;
+        let end = EntityHandle::<Point>::new(slvs_entity.point[end_ix]);
+
+        let param = self.nearest_param(h, &workplane, point)?;
+        let coords = self.point_at(&slvs_entity, &workplane, param)?;
+        for ix in [start_ix, end_ix] {
+            let end_coords = self.point_coords_on(slvs_entity.point[ix], workplane.handle())?;
+            if distance(coords, end_coords) <= SOLVE_TOLERANCE {
+                return Err("Expected split point to lie away from the ends of the curve.");
+            }
+        }
+
+        // The control points of the first part of a cubic curve are moved to keep its
+        // shape, before they are needed for the second part.
+        let cubic_halves = if SLVS_E_CUBIC == slvs_entity.type_ as _ {
+            let halves = self
+                .cubic_geometry_on(&EntityHandle::new(h), &workplane)?
+                .split(param);
+            self.move_controls(&slvs_entity, halves.0.control_points)?;
+            Some(halves)
+        } else {
+            None
+        };
+
+        let split_point = self.sketch(Point::new_on_workplane(group, workplane, coords))?;
+        self.mut_slvs_entity(h)?.point[end_ix] = split_point.handle();
+
+        let second: Box<dyn AsEntityHandle> = match slvs_entity.type_ as _ {
+            SLVS_E_LINE_SEGMENT => {
+                Box::new(self.sketch(LineSegment::new(group, split_point, end))?)
+            }
+            SLVS_E_ARC_OF_CIRCLE => Box::new(self.sketch(ArcOfCircle::new(
+                group,
+                workplane,
+                EntityHandle::new(slvs_entity.point[0]),
+                split_point,
+                end,
+            ))?),
+            _ => {
+                let control_points = cubic_halves
+                    .ok_or("Expected a cubic curve.")?
+                    .1
+                    .control_points;
+                let start_control =
+                    self.sketch(Point::new_on_workplane(group, workplane, control_points[1]))?;
+                let end_control =
+                    self.sketch(Point::new_on_workplane(group, workplane, control_points[2]))?;
+                Box::new(self.sketch(Cubic::new(
+                    group,
+                    split_point,
+                    start_control,
+                    end_control,
+                    end,
+                ))?)
+            }
+        };
+        self.move_end_tangents(h, second.handle());
+
+        let joint: Option<Box<dyn AsConstraintHandle>> = match slvs_entity.type_ as _ {
+            SLVS_E_LINE_SEGMENT => Some(Box::new(self.constrain(PtOnLine::new(
+                group,
+                end,
+                EntityHandle::new(h),
+                Some(workplane),
+            ))?)),
+            SLVS_E_CUBIC => Some(Box::new(self.constrain(CurveCurveTangent::new(
+                group,
+                workplane,
+                EntityHandle::<Cubic>::new(h),
+                EntityHandle::<Cubic>::new(second.handle()),
+                true,
+                false,
+            ))?)),
+            _ => None,
+        };
+
+        Ok(Split {
+            point: split_point,
+            parts: [slvs_entity.into(), second],
+            joint,
+        })
+    }
+
+    /// Cut a line segment, arc or cubic curve back to where it crosses another curve.
+    ///
+    /// The curve is cut at the crossing nearest the end being removed, which is
+    /// replaced by a new end point held on `cutter`. The old end point is deleted,
+    /// unless other entities or constraints use it.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The curve to trim. It must lie on a workplane.
+    /// * `cutter` - The curve to trim back to, projected onto the workplane of `entity`.
+    /// * `keep_start` - Whether to keep the part of `entity` before the cut, rather
+    ///   than the part after it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{LineSegment, Normal, Point, Workplane},
+    ///     utils::make_quaternion,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// let mut sketch_line = |start, end| {
+    ///     let start = sys
+    ///         .sketch(Point::new_on_workplane(g, workplane, start))
+    ///         .expect("point created");
+    ///     let end = sys
+    ///         .sketch(Point::new_on_workplane(g, workplane, end))
+    ///         .expect("point created");
+    ///     sys.sketch(LineSegment::new(g, start, end))
+    ///         .expect("line created")
+    /// };
+    /// // A line overshooting a wall.
+    /// let line = sketch_line([0.0, 0.0], [12.0, 0.0]);
+    /// let wall = sketch_line([10.0, -5.0], [10.0, 5.0]);
+    ///
+    /// let cut = sys.trim(&line, &wall, true).expect("line trimmed");
+    /// assert!(cut.on_curve.is_some());
+    /// let line = sys.line_geometry_on(&line, &workplane).expect("line found");
+    /// assert_eq!(line.end, [10.0, 0.0]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either curve is not found in the system, if `entity` is not
+    /// a line segment, arc or cubic curve on a workplane, or if it does not cross
+    /// `cutter` away from its ends.
+    pub fn trim(
+        &mut self,
+        entity: &dyn AsEntityHandle,
+        cutter: &dyn AsEntityHandle,
+        keep_start: bool,
+    ) -> Result<Cut, &'static str> {
+        let (slvs_entity, workplane) = self.curve_on_workplane(entity.handle())?;
+        let ixs = end_ixs(&slvs_entity)?;
+        let end_coords = ixs
+            .map(|ix| self.point_coords_on(slvs_entity.point[ix], workplane.handle()))
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let crossings = self
+            .intersections(entity, cutter, &workplane)?
+            .into_iter()
+            .filter(|intersection| {
+                intersection.contact != Contact::Overlap
+                    && end_coords
+                        .iter()
+                        .all(|end| distance(*end, intersection.point) > SOLVE_TOLERANCE)
+            });
+        let by_param = |a: &Intersection, b: &Intersection| a.param_a.total_cmp(&b.param_a);
+        let crossing = if keep_start {
+            crossings.max_by(by_param)
+        } else {
+            crossings.min_by(by_param)
+        }
+        .ok_or("Expected curve to cross the cutting curve away from its ends.")?;
+
+        if SLVS_E_CUBIC == slvs_entity.type_ as _ {
+            let (before, after) = self
+                .cubic_geometry_on(&EntityHandle::new(slvs_entity.h), &workplane)?
+                .split(crossing.param_a);
+            let kept = if keep_start { before } else { after };
+            self.move_controls(&slvs_entity, kept.control_points)?;
+        }
+
+        let removed_ix = if keep_start { ixs[1] } else { ixs[0] };
+        let point = self.move_end(&slvs_entity, removed_ix, &workplane, crossing.point)?;
+        let on_curve = self.hold_on(Group(slvs_entity.group), point, cutter.handle(), workplane)?;
+
+        Ok(Cut { point, on_curve })
+    }
+
+    /// Lengthen a line segment until it meets another curve.
+    ///
+    /// The line is extended from whichever end needs the shortest extension. That end
+    /// is replaced by a new end point held on `to_entity`, and the old end point is
+    /// deleted, unless other entities or constraints use it.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line to extend. It must lie on a workplane.
+    /// * `to_entity` - The curve to extend the line to, projected onto the workplane
+    ///   of `line`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either entity is not found in the system, if `line` is not
+    /// on a workplane or has zero length, if `to_entity` is not a curve, or if the line
+    /// would not meet it.
+    pub fn extend(
+        &mut self,
+        line: &EntityHandle<LineSegment>,
+        to_entity: &dyn AsEntityHandle,
+    ) -> Result<Cut, &'static str> {
+        let (slvs_entity, workplane) = self.curve_on_workplane(line.handle())?;
+        let geometry = self.line_geometry_on(line, &workplane)?;
+        let param_tolerance = SOLVE_TOLERANCE / geometry.length();
+
+        let (end_ix, coords) = self
+            .line_intersections(geometry.start, geometry.end, to_entity.handle(), &workplane)?
+            .into_iter()
+            .filter(|intersection| intersection.contact != Contact::Overlap)
+            .filter_map(|intersection| {
+                if intersection.param_a > 1.0 + param_tolerance {
+                    Some((1, intersection.param_a - 1.0, intersection.point))
+                } else if intersection.param_a < -param_tolerance {
+                    Some((0, -intersection.param_a, intersection.point))
+                } else {
+                    None
+                }
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(end_ix, _, coords)| (end_ix, coords))
+            .ok_or("Expected line to meet the entity when extended.")?;
+
+        let point = self.move_end(&slvs_entity, end_ix, &workplane, coords)?;
+        let on_curve = self.hold_on(
+            Group(slvs_entity.group),
+            point,
+            to_entity.handle(),
+            workplane,
+        )?;
+
+        Ok(Cut { point, on_curve })
+    }
+
+    fn curve_on_workplane(
+        &self,
+        h: Slvs_hEntity,
+    ) -> Result<(Slvs_Entity, EntityHandle<Workplane>), &'static str> {
+        let slvs_entity = *self.slvs_entity(h)?;
+        if slvs_entity.wrkpl == SLVS_FREE_IN_3D {
+            return Err("Expected curve to lie on a workplane.");
+        }
+        Ok((slvs_entity, EntityHandle::new(slvs_entity.wrkpl)))
+    }
+
+    // The point at a parameter on a line segment, arc or cubic curve, in the
+    // coordinates of a workplane.
+    fn point_at(
+        &self,
+        slvs_entity: &Slvs_Entity,
+        workplane: &EntityHandle<Workplane>,
+        param: f64,
+    ) -> Result<[f64; 2], &'static str> {
+        let h = slvs_entity.h;
+        match slvs_entity.type_ as _ {
+            SLVS_E_LINE_SEGMENT => Ok(self
+                .line_geometry_on(&EntityHandle::new(h), workplane)?
+                .point_at(param)),
+            SLVS_E_ARC_OF_CIRCLE => {
+                let arc = self.arc_geometry_on(&EntityHandle::new(h), workplane)?;
+                let angle = (arc.start_angle + arc.sweep() * param).to_radians();
+                Ok(add(
+                    arc.center,
+                    [arc.radius * angle.cos(), arc.radius * angle.sin()],
+                ))
+            }
+            _ => Ok(self
+                .cubic_geometry_on(&EntityHandle::new(h), workplane)?
+                .point_at(param)),
+        }
+    }
+
+    // Move the control points of a cubic curve to those of the part being kept.
+    fn move_controls(
+        &mut self,
+        slvs_entity: &Slvs_Entity,
+        control_points: [[f64; 2]; 4],
+    ) -> Result<(), &'static str> {
+        for ix in [1, 2] {
+            self.update_entity(
+                &EntityHandle::<Point>::new(slvs_entity.point[ix]),
+                |point| {
+                    if let Point::OnWorkplane { coords, .. } = point {
+                        *coords = control_points[ix];
+                    }
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    // Replace an end point of a curve with a new point, deleting the old one if nothing
+    // else uses it.
+    fn move_end(
+        &mut self,
+        slvs_entity: &Slvs_Entity,
+        ix: usize,
+        workplane: &EntityHandle<Workplane>,
+        coords: [f64; 2],
+    ) -> Result<EntityHandle<Point>, &'static str> {
+        let old_h = slvs_entity.point[ix];
+        let point = self.sketch(Point::new_on_workplane(
+            Group(slvs_entity.group),
+            *workplane,
+            coords,
+        ))?;
+        self.mut_slvs_entity(slvs_entity.h)?.point[ix] = point.handle();
+
+        let used = self
+            .entities
+            .list
+            .iter()
+            .any(|slvs_entity| slvs_entity.point.contains(&old_h))
+            || self.constraints.list.iter().any(|slvs_constraint| {
+                [
+                    slvs_constraint.ptA,
+                    slvs_constraint.ptB,
+                    slvs_constraint.entityA,
+                    slvs_constraint.entityB,
+                    slvs_constraint.entityC,
+                    slvs_constraint.entityD,
+                ]
+                .contains(&old_h)
+            });
+        if !used {
+            self.delete_entity(EntityHandle::<Point>::new(old_h))?;
+        }
+
+        Ok(point)
+    }
+
+    // Tangencies at the end of a curve that has been split move to its second part.
+    fn move_end_tangents(&mut self, h: Slvs_hEntity, second_h: Slvs_hEntity) {
+        for slvs_constraint in &mut self.constraints.list {
+            match slvs_constraint.type_ as _ {
+                SLVS_C_ARC_LINE_TANGENT | SLVS_C_CUBIC_LINE_TANGENT
+                    if slvs_constraint.entityA == h && slvs_constraint.other != 0 =>
+                {
+                    slvs_constraint.entityA = second_h;
+                }
+                SLVS_C_CURVE_CURVE_TANGENT => {
+                    if slvs_constraint.entityA == h && slvs_constraint.other != 0 {
+                        slvs_constraint.entityA = second_h;
+                    }
+                    if slvs_constraint.entityB == h && slvs_constraint.other2 != 0 {
+                        slvs_constraint.entityB = second_h;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Constrain a point to lie on a line segment, arc or circle.
+    fn hold_on(
+        &mut self,
+        group: Group,
+        point: EntityHandle<Point>,
+        curve_h: Slvs_hEntity,
+        workplane: EntityHandle<Workplane>,
+    ) -> Result<Option<Box<dyn AsConstraintHandle>>, &'static str> {
+        Ok(match self.slvs_entity(curve_h)?.type_ as _ {
+            SLVS_E_LINE_SEGMENT => Some(Box::new(self.constrain(PtOnLine::new(
+                group,
+                point,
+                EntityHandle::new(curve_h),
+                Some(workplane),
+            ))?)),
+            SLVS_E_ARC_OF_CIRCLE => Some(Box::new(self.constrain(PtOnCircle::new(
+                group,
+                point,
+                EntityHandle::<ArcOfCircle>::new(curve_h),
+            ))?)),
+            SLVS_E_CIRCLE => Some(Box::new(self.constrain(PtOnCircle::new(
+                group,
+                point,
+                EntityHandle::<Circle>::new(curve_h),
+            ))?)),
+            _ => None,
+        })
+    }
+}
+
+// The indices of the start and end points of a line segment, arc or cubic curve.
+fn end_ixs(slvs_entity: &Slvs_Entity) -> Result<[usize; 2], &'static str> {
+    match slvs_entity.type_ as _ {
+        SLVS_E_LINE_SEGMENT => Ok([0, 1]),
+        SLVS_E_ARC_OF_CIRCLE => Ok([1, 2]),
+        SLVS_E_CUBIC => Ok([0, 3]),
+        _ => Err("Expected entity to be a line segment, arc or cubic."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constraint::{ArcLineTangent, Horizontal},
+        element::AsHandle,
+        entity::{
+            ArcOfCircle, Circle, Cubic, Distance, EntityHandle, LineSegment, Normal, Point,
+            Workplane,
+        },
+        len_within_tolerance,
+        system::SolveResult,
+        utils::make_quaternion,
+        System,
+    };
+
+    #[test]
+    fn split_trim_and_extend() {
+        let mut sys = System::new();
+        let g = sys.add_group();
+
+        let origin = sys
+            .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(g, origin, normal))
+            .expect("workplane created");
+        let mut point = |coords| {
+            sys.sketch(Point::new_on_workplane(g, workplane, coords))
+                .expect("point created")
+        };
+        let [center, arc_start, arc_end, line_end] =
+            [[0.0, 0.0], [5.0, 0.0], [-5.0, 0.0], [-5.0, -10.0]].map(&mut point);
+        let [cubic_start, control_a, control_b, cubic_end] =
+            [[0.0, 20.0], [5.0, 25.0], [10.0, 25.0], [15.0, 20.0]].map(&mut point);
+        let [short_start, short_end] = [[20.0, -3.0], [22.0, -3.0]].map(&mut point);
+
+        // The top half of a circle, with a line leaving its end downwards.
+        let arc = sys
+            .sketch(ArcOfCircle::new(g, workplane, center, arc_start, arc_end))
+            .expect("arc created");
+        let line = sys
+            .sketch(LineSegment::new(g, arc_end, line_end))
+            .expect("line created");
+        let tangent = sys
+            .constrain(ArcLineTangent::new(g, workplane, arc, line, true))
+            .expect("constraint created");
+        let cubic = sys
+            .sketch(Cubic::new(g, cubic_start, control_a, control_b, cubic_end))
+            .expect("cubic created");
+
+        // The tangency at the end of the arc moves to its second half.
+        let split = sys.split_at(&arc, [0.0, 8.0]).expect("arc split");
+        assert!(split.joint.is_none());
+        let second = EntityHandle::<ArcOfCircle>::new(split.parts[1].handle());
+        assert_eq!(
+            sys.constraint_data(&tangent).expect("tangent found").arc,
+            second
+        );
+        let geometry = sys.arc_geometry_on(&second, &workplane).expect("arc found");
+        len_within_tolerance!(geometry.start[0], 0.0);
+        len_within_tolerance!(geometry.start[1], 5.0);
+
+        let split = sys.split_at(&cubic, [7.5, 30.0]).expect("cubic split");
+        assert!(split.joint.is_some());
+        let second = EntityHandle::<Cubic>::new(split.parts[1].handle());
+        let geometry = sys
+            .cubic_geometry_on(&second, &workplane)
+            .expect("cubic found");
+        len_within_tolerance!(geometry.control_points[0][0], 7.5);
+        len_within_tolerance!(geometry.control_points[0][1], 23.75);
+        assert!(sys.split_at(&cubic, [0.0, 10.0]).is_err());
+
+        // The line is cut off where it leaves the circle of the arc, and the old end
+        // point is deleted.
+        let radius = sys.sketch(Distance::new(g, 8.0)).expect("distance created");
+        let circle = sys
+            .sketch(Circle::new(g, normal, center, radius))
+            .expect("circle created");
+        let cut = sys.trim(&line, &circle, true).expect("line trimmed");
+        assert!(cut.on_curve.is_some());
+        assert!(sys.entity_data(&line_end).is_err());
+        let geometry = sys.line_geometry_on(&line, &workplane).expect("line found");
+        len_within_tolerance!(geometry.end[1], -39.0_f64.sqrt());
+
+        // The short line reaches the circle going backwards, not forwards.
+        let short = sys
+            .sketch(LineSegment::new(g, short_start, short_end))
+            .expect("line created");
+        sys.constrain(Horizontal::from_line(g, workplane, short))
+            .expect("constraint created");
+        let cut = sys.extend(&short, &circle).expect("line extended");
+        assert!(cut.on_curve.is_some());
+        let geometry = sys
+            .line_geometry_on(&short, &workplane)
+            .expect("line found");
+        len_within_tolerance!(geometry.start[0], 55.0_f64.sqrt());
+        len_within_tolerance!(geometry.end[0], 22.0);
+        assert!(sys.extend(&short, &cubic).is_err());
+
+        assert!(matches!(sys.solve(&g), SolveResult::Ok { .. }));
+    }
+}
@@ -0,0 +1,362 @@
+/*!
+Checks for flaws that stop the profiles in a sketch from being cut or extruded.
+
+[`System::validate_profiles`] looks over the line segments, arcs, circles and cubic
+curves in a group, in the coordinates of a workplane, for curves that cross or lie on
+top of each other, curves that have collapsed to a point, and end points that are
+close together without being joined.
+*/
+
+use std::collections::BTreeSet;
+
+use crate::{
+    bindings::{
+        Slvs_Entity, Slvs_hEntity, SLVS_E_ARC_OF_CIRCLE, SLVS_E_CIRCLE, SLVS_E_CUBIC,
+        SLVS_E_LINE_SEGMENT,
+    },
+    element::AsHandle,
+    entity::{ArcOfCircle, AsEntityHandle, EntityHandle, LineSegment, Point, Workplane},
+    group::Group,
+    intersection::Contact,
+    profile::find,
+    system::SOLVE_TOLERANCE,
+    utils::distance,
+    System,
+};
+
+/// A flaw in the profiles of a sketch, found by [`System::validate_profiles`].
+#[derive(Debug)]
+pub enum ProfileIssue {
+    /// Two curves cross or touch at `point`, away from their end points.
+    SelfIntersection {
+        entity_a: Box<dyn AsEntityHandle>,
+        entity_b: Box<dyn AsEntityHandle>,
+        /// The intersection, in the coordinates of the workplane.
+        point: [f64; 2],
+    },
+    /// Two curves lie on top of each other for some length.
+    Overlap {
+        entity_a: Box<dyn AsEntityHandle>,
+        entity_b: Box<dyn AsEntityHandle>,
+    },
+    /// A line segment whose start and end points coincide.
+    ZeroLength { line: EntityHandle<LineSegment> },
+    /// An arc whose center coincides with its start point.
+    DegenerateArc { arc: EntityHandle<ArcOfCircle> },
+    /// End points of two curves that are close together, but not joined.
+    Gap {
+        point_a: EntityHandle<Point>,
+        point_b: EntityHandle<Point>,
+        /// The distance between the points, in the coordinates of the workplane.
+        distance: f64,
+    },
+}
+
+// An end point of a curve.
+struct End {
+    point_h: Slvs_hEntity,
+    // The point that the end point is merged into.
+    node: Slvs_hEntity,
+    coords: [f64; 2],
+}
+
+impl System {
+    /// Find the flaws in the profiles formed by the curves in a group.
+    ///
+    /// Curves that meet only at their end points are not flaws. Curves that have
+    /// collapsed to a point are reported, and are not checked against other curves.
+    /// Construction curves, marked with [`System::set_construction`], are not checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group that the curves belong to.
+    /// * `workplane` - The workplane to check the curves in. Curves that are not on the
+    ///   workplane are projected onto it.
+    /// * `gap_tolerance` - End points that are not joined, but are at most this far
+    ///   apart, are reported as gaps.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use slvs::{
+    ///     entity::{LineSegment, Normal, Point, Workplane},
+    ///     utils::make_quaternion,
+    ///     validation::ProfileIssue,
+    ///     System,
+    /// };
+    ///
+    /// let mut sys = System::new();
+    /// let g = sys.add_group();
+    /// let origin = sys
+    ///     .sketch(Point::new_in_3d(g, [0.0, 0.0, 0.0]))
+    ///     .expect("origin created");
+    /// let normal = sys
+    ///     .sketch(Normal::new_in_3d(
+    ///         g,
+    ///         make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ///     ))
+    ///     .expect("normal created");
+    /// let workplane = sys
+    ///     .sketch(Workplane::new(g, origin, normal))
+    ///     .expect("workplane created");
+    ///
+    /// // A bow tie, whose sides cross in the middle.
+    /// let g = sys.add_group();
+    /// let corners = [[0.0, 0.0], [10.0, 10.0], [10.0, 0.0], [0.0, 10.0]].map(|coords| {
+    ///     sys.sketch(Point::new_on_workplane(g, workplane, coords))
+    ///         .expect("point created")
+    /// });
+    /// for i in 0..4 {
+    ///     sys.sketch(LineSegment::new(g, corners[i], corners[(i + 1) % 4]))
+    ///         .expect("line created");
+    /// }
+    ///
+    /// let issues = sys
+    ///     .validate_profiles(&g, &workplane, 0.1)
+    ///     .expect("profiles checked");
+    /// assert_eq!(issues.len(), 1);
+    /// if let ProfileIssue::SelfIntersection { point, .. } = issues[0] {
+    ///     assert!((point[0] - 5.0).abs() < 1e-9 && (point[1] - 5.0).abs() < 1e-9);
+    /// } else {
+    ///     panic!("expected the sides to cross");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the workplane is not found in the system, or if the
+    /// geometry of a curve cannot be resolved.
+    pub fn validate_profiles(
+        &self,
+        group: &Group,
+        workplane: &EntityHandle<Workplane>,
+        gap_tolerance: f64,
+    ) -> Result<Vec<ProfileIssue>, &'static str> {
+        let mut issues = Vec::new();
+
+        let mut curves: Vec<(Slvs_Entity, Vec<End>)> = Vec::new();
+        let merged = self.merged_points();
+        for slvs_entity in self.entities.list.iter().filter(|slvs_entity| {
+            slvs_entity.group == group.handle() && !self.construction.contains(&slvs_entity.h)
+        }) {
+            let end_hs = match slvs_entity.type_ as _ {
+                SLVS_E_LINE_SEGMENT => {
+                    let line = EntityHandle::new(slvs_entity.h);
+                    if self.line_geometry_on(&line, workplane)?.length() <= SOLVE_TOLERANCE {
+                        issues.push(ProfileIssue::ZeroLength { line });
+                        continue;
+                    }
+                    vec![slvs_entity.point[0], slvs_entity.point[1]]
+                }
+                SLVS_E_ARC_OF_CIRCLE => {
+                    let arc = EntityHandle::new(slvs_entity.h);
+                    if self.arc_geometry_on(&arc, workplane)?.radius <= SOLVE_TOLERANCE {
+                        issues.push(ProfileIssue::DegenerateArc { arc });
+                        continue;
+                    }
+                    vec![slvs_entity.point[1], slvs_entity.point[2]]
+                }
+                SLVS_E_CUBIC => vec![slvs_entity.point[0], slvs_entity.point[3]],
+                SLVS_E_CIRCLE => Vec::new(),
+                _ => continue,
+            };
+
+            let ends = end_hs
+                .into_iter()
+                .map(|point_h| {
+                    Ok(End {
+                        point_h,
+                        node: find(&merged, point_h),
+                        coords: self.point_coords_on(point_h, workplane.handle())?,
+                    })
+                })
+                .collect::<Result<_, &'static str>>()?;
+            curves.push((*slvs_entity, ends));
+        }
+
+        for (ix, (slvs_entity_a, ends_a)) in curves.iter().enumerate() {
+            for (slvs_entity_b, ends_b) in &curves[ix + 1..] {
+                if self.overlaps(slvs_entity_a.h, slvs_entity_b.h, workplane)? {
+                    issues.push(ProfileIssue::Overlap {
+                        entity_a: (*slvs_entity_a).into(),
+                        entity_b: (*slvs_entity_b).into(),
+                    });
+                    continue;
+                }
+
+                let entity_a: Box<dyn AsEntityHandle> = (*slvs_entity_a).into();
+                let entity_b: Box<dyn AsEntityHandle> = (*slvs_entity_b).into();
+                for intersection in self
+                    .intersections(entity_a.as_ref(), entity_b.as_ref(), workplane)?
+                    .into_iter()
+                    .filter(|intersection| intersection.contact != Contact::Overlap)
+                {
+                    // Curves meeting end to end are either joined, or reported as a gap.
+                    let at_end = |ends: &[End]| {
+                        ends.iter()
+                            .any(|end| distance(end.coords, intersection.point) <= SOLVE_TOLERANCE)
+                    };
+                    if at_end(ends_a) && at_end(ends_b) {
+                        continue;
+                    }
+
+                    issues.push(ProfileIssue::SelfIntersection {
+                        entity_a: (*slvs_entity_a).into(),
+                        entity_b: (*slvs_entity_b).into(),
+                        point: intersection.point,
+                    });
+                }
+            }
+        }
+
+        // Each pair of merged points is reported once, however many end points they
+        // are made of. The two ends of a short curve are not a gap.
+        let ends: Vec<(usize, &End)> = curves
+            .iter()
+            .enumerate()
+            .flat_map(|(curve, (_, ends))| ends.iter().map(move |end| (curve, end)))
+            .collect();
+        let mut gaps: BTreeSet<(Slvs_hEntity, Slvs_hEntity)> = BTreeSet::new();
+        for (ix, &(curve_a, end_a)) in ends.iter().enumerate() {
+            for &(curve_b, end_b) in &ends[ix + 1..] {
+                let gap = distance(end_a.coords, end_b.coords);
+                if curve_a != curve_b
+                    && end_a.node != end_b.node
+                    && gap <= gap_tolerance
+                    && gaps.insert((end_a.node.min(end_b.node), end_a.node.max(end_b.node)))
+                {
+                    issues.push(ProfileIssue::Gap {
+                        point_a: EntityHandle::new(end_a.point_h),
+                        point_b: EntityHandle::new(end_b.point_h),
+                        distance: gap,
+                    });
+                }
+            }
+        }
+
+        Ok(issues)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        element::AsHandle,
+        entity::{ArcOfCircle, Cubic, LineSegment, Normal, Point, Workplane},
+        utils::make_quaternion,
+        validation::ProfileIssue,
+        System,
+    };
+
+    #[test]
+    fn find_issues() {
+        let mut sys = System::new();
+
+        let workplane_g = sys.add_group();
+        let origin = sys
+            .sketch(Point::new_in_3d(workplane_g, [0.0, 0.0, 0.0]))
+            .expect("origin created");
+        let normal = sys
+            .sketch(Normal::new_in_3d(
+                workplane_g,
+                make_quaternion([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ))
+            .expect("normal created");
+        let workplane = sys
+            .sketch(Workplane::new(workplane_g, origin, normal))
+            .expect("workplane created");
+
+        let g = sys.add_group();
+        let mut point = |coords| {
+            sys.sketch(Point::new_on_workplane(g, workplane, coords))
+                .expect("point created")
+        };
+        // An open square, with a small gap at one corner.
+        let corners = [
+            [0.0, 0.0],
+            [10.0, 0.0],
+            [10.0, 10.0],
+            [0.0, 10.0],
+            [0.0, 0.05],
+        ]
+        .map(&mut point);
+        // A line lying along part of the bottom side.
+        let [overlap_start, overlap_end] = [[2.0, 0.0], [4.0, 0.0]].map(&mut point);
+        let collapsed = point([5.0, 5.0]);
+        let [arc_center, arc_end] = [[20.0, 0.0], [25.0, 0.0]].map(&mut point);
+        // A straight cubic along the top side, a cubic crossing it, and a line shorter
+        // than the gap tolerance.
+        let along_top = [[2.0, 10.0], [4.0, 10.0], [6.0, 10.0], [8.0, 10.0]].map(&mut point);
+        let across_top = [[5.0, 9.0], [5.0, 9.5], [5.0, 10.5], [5.0, 11.0]].map(&mut point);
+        let [short_start, short_end] = [[30.0, 30.0], [30.05, 30.0]].map(&mut point);
+
+        let sides = (0..4)
+            .map(|i| {
+                sys.sketch(LineSegment::new(g, corners[i], corners[i + 1]))
+                    .expect("line created")
+            })
+            .collect::<Vec<_>>();
+        let overlapping = sys
+            .sketch(LineSegment::new(g, overlap_start, overlap_end))
+            .expect("line created");
+        let zero_length = sys
+            .sketch(LineSegment::new(g, collapsed, collapsed))
+            .expect("line created");
+        let degenerate = sys
+            .sketch(ArcOfCircle::new(
+                g, workplane, arc_center, arc_center, arc_end,
+            ))
+            .expect("arc created");
+        let [a, b, c, d] = along_top;
+        let cubic_along = sys
+            .sketch(Cubic::new(g, a, b, c, d))
+            .expect("cubic created");
+        let [a, b, c, d] = across_top;
+        let cubic_across = sys
+            .sketch(Cubic::new(g, a, b, c, d))
+            .expect("cubic created");
+        sys.sketch(LineSegment::new(g, short_start, short_end))
+            .expect("line created");
+
+        let issues = sys
+            .validate_profiles(&g, &workplane, 0.1)
+            .expect("profiles checked");
+        assert_eq!(issues.len(), 7);
+
+        assert!(issues.iter().any(|issue| matches!(
+            issue,
+            ProfileIssue::ZeroLength { line } if *line == zero_length
+        )));
+        assert!(issues.iter().any(|issue| matches!(
+            issue,
+            ProfileIssue::DegenerateArc { arc } if *arc == degenerate
+        )));
+        assert!(issues.iter().any(|issue| matches!(
+            issue,
+            ProfileIssue::Overlap { entity_a, entity_b }
+                if entity_a.handle() == sides[0].handle()
+                    && entity_b.handle() == overlapping.handle()
+        )));
+        assert!(issues.iter().any(|issue| matches!(
+            issue,
+            ProfileIssue::Overlap { entity_a, entity_b }
+                if entity_a.handle() == sides[2].handle()
+                    && entity_b.handle() == cubic_along.handle()
+        )));
+        assert!(issues.iter().any(|issue| matches!(
+            issue,
+            ProfileIssue::SelfIntersection { entity_a, entity_b, point }
+                if entity_a.handle() == cubic_along.handle()
+                    && entity_b.handle() == cubic_across.handle()
+                    && (point[0] - 5.0).abs() < 1e-6
+                    && (point[1] - 10.0).abs() < 1e-6
+        )));
+        assert!(issues.iter().any(|issue| matches!(
+            issue,
+            ProfileIssue::Gap { point_a, point_b, distance }
+                if *point_a == corners[0]
+                    && *point_b == corners[4]
+                    && (distance - 0.05).abs() < 1e-9
+        )));
+    }
+}